@@ -0,0 +1,43 @@
+//! Time the slow parts of a step on huge generated structures, so changes meant to
+//! speed things up have numbers to back them.
+//!
+//! `cargo run --release --example stress` prints a table and quits.
+//! It doesn't open a window or load any assets.
+
+use excavation_site_alpha::StressRun;
+
+use std::{hint::black_box, time::Instant};
+
+/// About how many blocks each structure has
+const SIZES: [usize; 3] = [1_000, 5_000, 20_000];
+/// How many times each part is timed, to average out the noise
+const ROUNDS: u32 = 20;
+
+fn main() {
+    println!(
+        "{:>7} {:>14} {:>14} {:>14}",
+        "blocks", "stability", "damage", "draw prep"
+    );
+    for &size in SIZES.iter() {
+        let run = StressRun::generate(size);
+        let stability = time(|| run.stability_pass());
+        let damage = time(|| run.damage_pass());
+        let draw = time(|| run.draw_prep());
+        println!(
+            "{:>7} {:>11.3} ms {:>11.3} ms {:>11.3} ms",
+            run.block_count(),
+            stability,
+            damage,
+            draw
+        );
+    }
+}
+
+/// Average milliseconds this takes, over `ROUNDS` goes.
+fn time<T>(mut f: impl FnMut() -> T) -> f64 {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / ROUNDS as f64
+}
@@ -0,0 +1,74 @@
+// localStorage bindings for src/save.rs, plus downloads for exported files.
+// Values are arbitrary bytes, so they get stored one byte per UTF-16 code unit.
+(function () {
+    const PREFIX = "excavation-site-alpha/";
+
+    function read_str(ptr, len) {
+        const bytes = new Uint8Array(wasm_memory.buffer, ptr, len);
+        return new TextDecoder().decode(bytes);
+    }
+
+    function lookup(key_ptr, key_len) {
+        return window.localStorage.getItem(PREFIX + read_str(key_ptr, key_len));
+    }
+
+    // Newline-separated names of everything stored under the prefix, UTF-8 encoded
+    function list(prefix_ptr, prefix_len) {
+        const prefix = PREFIX + read_str(prefix_ptr, prefix_len);
+        const names = [];
+        for (let i = 0; i < window.localStorage.length; i++) {
+            const key = window.localStorage.key(i);
+            if (key.startsWith(prefix)) {
+                names.push(key.substring(PREFIX.length));
+            }
+        }
+        return new TextEncoder().encode(names.join("\n"));
+    }
+
+    miniquad_add_plugin({
+        name: "esa_storage",
+        version: 1,
+        register_plugin: function (importObject) {
+            importObject.env.esa_storage_len = function (key_ptr, key_len) {
+                const stored = lookup(key_ptr, key_len);
+                return stored === null ? -1 : stored.length;
+            };
+            importObject.env.esa_storage_get = function (key_ptr, key_len, buf_ptr, buf_len) {
+                const stored = lookup(key_ptr, key_len);
+                const out = new Uint8Array(wasm_memory.buffer, buf_ptr, buf_len);
+                for (let i = 0; i < buf_len; i++) {
+                    out[i] = stored.charCodeAt(i);
+                }
+            };
+            importObject.env.esa_storage_set = function (key_ptr, key_len, val_ptr, val_len) {
+                const bytes = new Uint8Array(wasm_memory.buffer, val_ptr, val_len);
+                let stored = "";
+                for (let i = 0; i < val_len; i++) {
+                    stored += String.fromCharCode(bytes[i]);
+                }
+                window.localStorage.setItem(PREFIX + read_str(key_ptr, key_len), stored);
+            };
+            importObject.env.esa_storage_remove = function (key_ptr, key_len) {
+                window.localStorage.removeItem(PREFIX + read_str(key_ptr, key_len));
+            };
+            importObject.env.esa_storage_list_len = function (prefix_ptr, prefix_len) {
+                return list(prefix_ptr, prefix_len).length;
+            };
+            importObject.env.esa_storage_list_get = function (prefix_ptr, prefix_len, buf_ptr, buf_len) {
+                const names = list(prefix_ptr, prefix_len);
+                new Uint8Array(wasm_memory.buffer, buf_ptr, buf_len).set(names.subarray(0, buf_len));
+            };
+            importObject.env.esa_download = function (name_ptr, name_len, data_ptr, data_len) {
+                // copied out, since wasm memory can move once this returns
+                const bytes = new Uint8Array(wasm_memory.buffer, data_ptr, data_len).slice();
+                const name = read_str(name_ptr, name_len);
+                const url = URL.createObjectURL(new Blob([bytes]));
+                const link = document.createElement("a");
+                link.href = url;
+                link.download = name.substring(name.lastIndexOf("/") + 1);
+                link.click();
+                URL.revokeObjectURL(url);
+            };
+        },
+    });
+})();
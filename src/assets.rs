@@ -1,145 +1,562 @@
 #![allow(clippy::eval_order_dependence)]
 
+#[cfg(not(feature = "bundled-assets"))]
 use std::path::PathBuf;
+use std::{
+    future::Future,
+    ops::Deref,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
 
 use macroquad::{
-    audio::{load_sound, Sound},
-    prelude::{load_texture, FilterMode, Texture2D},
+    audio::{load_sound, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound},
+    experimental::coroutines::{start_coroutine, Coroutine},
+    prelude::{FilterMode, Image, Texture2D, BLACK, MAGENTA},
 };
 use once_cell::sync::Lazy;
 
+use crate::modes::{Blueprint, Level};
+
+/// Blueprints in `assets/blueprints` to offer, in order
+const BLUEPRINTS: &[&str] = &["arch", "pillars", "funnel"];
+/// Campaign levels in `assets/levels`, in order
+const LEVELS: &[&str] = &[
+    "01_first_steps",
+    "02_narrows",
+    "03_scaffolding",
+    "04_solid_ground",
+    "05_hold_on",
+    "06_wide_open",
+    "07_sparse",
+    "08_girders",
+    "09_slow_burn",
+    "10_needle",
+    "11_heavy",
+    "12_the_deep",
+];
+
+/// How many files `Sounds::init` loads, not counting the music, which comes in later
+const SOUND_COUNT: usize = 5;
+/// Everything the loading screen waits on
+const FILE_COUNT: usize = Textures::COUNT + SOUND_COUNT + BLUEPRINTS.len() + LEVELS.len();
+/// Let a frame go by after this many files, so the loading screen gets drawn
+const FILES_PER_FRAME: usize = 4;
+
+/// How many files have loaded so far
+static LOADED: AtomicUsize = AtomicUsize::new(0);
+/// Files that couldn't be loaded and got stand-ins, by their path under the assets root
+static MISSING: Lazy<Mutex<Vec<String>>> = Lazy::new(Mutex::default);
+
 #[derive(Clone)]
 pub struct Assets {
     pub textures: Textures,
     pub sounds: Sounds,
+    pub blueprints: Vec<Blueprint>,
+    pub levels: Vec<Level>,
 }
 
 impl Assets {
     pub async fn init() -> Self {
+        // missing or broken ones just don't get offered
+        let mut blueprints = Vec::new();
+        for name in BLUEPRINTS {
+            blueprints.extend(blueprint(name).await);
+        }
+        let mut levels = Vec::new();
+        for name in LEVELS {
+            levels.extend(level(name).await);
+        }
         Self {
             textures: Textures::init().await,
             sounds: Sounds::init().await,
+            blueprints,
+            levels,
         }
     }
 }
 
-#[derive(Clone)]
-pub struct Textures {
-    pub title_banner: Texture2D,
-    pub title_screen: Texture2D,
-    pub tutorial: Texture2D,
+/// Declares `Textures`, with a field for each texture and the file it's loaded from,
+/// so the loading screen's count of them can't drift from what actually gets loaded
+macro_rules! textures {
+    ($($field:ident: $path:literal),* $(,)?) => {
+        #[derive(Clone)]
+        pub struct Textures {
+            $(pub $field: Texture2D,)*
+        }
+
+        impl Textures {
+            /// How many files `init` loads
+            const COUNT: usize = [$($path),*].len();
+
+            async fn init() -> Self {
+                Self {
+                    $($field: texture($path).await,)*
+                }
+            }
+        }
+    };
+}
 
-    pub scaffold: Texture2D,
-    pub solid: Texture2D,
-    pub anchor: Texture2D,
-    pub connector_atlas: Texture2D,
-    pub damage_atlas: Texture2D,
+textures! {
+    title_banner: "title/banner",
+    title_screen: "titlescreen",
 
-    pub stone: Texture2D,
-    pub stone2: Texture2D,
-    pub stone3: Texture2D,
-    pub dirt_edge: Texture2D,
-    pub dirt_body: Texture2D,
+    scaffold: "scaffold",
+    solid: "rust2",
+    anchor: "terrain-iron-simple-bottom",
+    girder: "girder",
+    reinforced: "reinforced",
+    explosive: "explosive",
+    glue: "glue",
+    crane: "crane",
+    fossil: "fossil",
+    idol: "idol",
+    connector_atlas: "connector_atlas",
+    damage_atlas: "damage_atlas",
 
-    pub conveyor: Texture2D,
-    pub depth_meter: Texture2D,
-    pub number_atlas: Texture2D,
-    pub finish_popup: Texture2D,
+    stone: "stone",
+    stone2: "stone2",
+    stone3: "stone3",
+    dirt_edge: "chasm_edge",
+    dirt_body: "chasm_body",
 
-    pub denoument: Texture2D,
+    conveyor: "conveyor",
+    depth_meter: "depth_meter",
+    number_atlas: "number_atlas",
+    finish_popup: "finish_popup",
+    hold_slot: "hold_slot",
+    reroll: "reroll",
+    font_atlas: "font_atlas",
+
+    denoument: "denoument",
 }
 
-impl Textures {
+#[derive(Clone)]
+pub struct Sounds {
+    pub title_jingle: Music,
+    pub engineer_gaming: Music,
+
+    // These are None if they couldn't be loaded, so they stay quiet
+    pub pickup: Option<Sound>,
+    pub putdown: Option<Sound>,
+    pub rotate: Option<Sound>,
+    pub damage: Option<Sound>,
+    pub fall: Option<Sound>,
+}
+
+impl Sounds {
     async fn init() -> Self {
         Self {
-            title_banner: texture("title/banner").await,
-            title_screen: texture("titlescreen").await,
-            tutorial: texture("tutorial").await,
+            // Music is big and slow to decode, so it gets loaded in the background
+            title_jingle: Music::load("title/jingle"),
+            engineer_gaming: Music::load("engineer_gaming"),
 
-            scaffold: texture("scaffold").await,
-            solid: texture("rust2").await,
-            anchor: texture("terrain-iron-simple-bottom").await,
-            connector_atlas: texture("connector_atlas").await,
-            damage_atlas: texture("damage_atlas").await,
+            pickup: sound_effect("pick_up").await,
+            putdown: sound_effect("drop").await,
+            rotate: sound_effect("rotate").await,
+            damage: sound_effect("break").await,
+            fall: sound_effect("fall").await,
+        }
+    }
+}
 
-            stone: texture("stone").await,
-            stone2: texture("stone2").await,
-            stone3: texture("stone3").await,
-            dirt_edge: texture("chasm_edge").await,
-            dirt_body: texture("chasm_body").await,
+/// The assets, once they've loaded.
+///
+/// Until then the loading screen is the only thing running, and it doesn't touch them,
+/// so everything else can use this as if it were the [`Assets`].
+#[derive(Clone, Default)]
+pub struct AssetHandle(Option<Assets>);
 
-            conveyor: texture("conveyor").await,
-            depth_meter: texture("depth_meter").await,
-            number_atlas: texture("number_atlas").await,
-            finish_popup: texture("finish_popup").await,
+impl AssetHandle {
+    pub fn set(&mut self, assets: Assets) {
+        self.0 = Some(assets);
+    }
 
-            denoument: texture("denoument").await,
+    pub fn is_loaded(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl Deref for AssetHandle {
+    type Target = Assets;
+
+    fn deref(&self) -> &Assets {
+        self.0
+            .as_ref()
+            .expect("assets were used before they finished loading")
+    }
+}
+
+/// The assets loading in the background, for the loading screen to keep an eye on.
+pub struct AssetLoader {
+    coroutine: Coroutine,
+    loaded: Arc<Mutex<Option<Assets>>>,
+}
+
+impl AssetLoader {
+    pub fn start() -> Self {
+        let loaded = Arc::new(Mutex::new(None));
+        let slot = Arc::clone(&loaded);
+        let coroutine = start_coroutine(async move {
+            let assets = Assets::init().await;
+            *slot.lock().unwrap() = Some(assets);
+        });
+        Self { coroutine, loaded }
+    }
+
+    /// How much has loaded, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        (LOADED.load(Ordering::Relaxed) as f32 / FILE_COUNT as f32).min(1.0)
+    }
+
+    /// The assets, if they're done. They only get handed out once.
+    pub fn take(&self) -> Option<Assets> {
+        if self.coroutine.is_done() {
+            self.loaded.lock().unwrap().take()
+        } else {
+            None
         }
     }
 }
 
+/// Count a file as loaded, and every so often let a frame go by.
+///
+/// On the web loading waits on the network anyway, but on desktop it's quick enough
+/// that without this the loading screen would never get drawn in between.
+async fn loaded_one() {
+    let loaded = LOADED.fetch_add(1, Ordering::Relaxed) + 1;
+    if loaded % FILES_PER_FRAME == 0 {
+        NextFrame(false).await;
+    }
+}
+
+/// Waits until the coroutine gets polled again, which is next frame
+struct NextFrame(bool);
+
+impl Future for NextFrame {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut Context) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            Poll::Pending
+        }
+    }
+}
+
+/// A music track that loads in the background after startup.
+///
+/// Playing it before it's ready is fine; it starts once it finishes loading,
+/// unless it's been stopped in the meantime.
 #[derive(Clone)]
-pub struct Sounds {
-    pub title_jingle: Sound,
-    pub engineer_gaming: Sound,
+pub struct Music {
+    state: Arc<Mutex<MusicState>>,
+}
 
-    pub pickup: Sound,
-    pub putdown: Sound,
-    pub rotate: Sound,
-    pub damage: Sound,
-    pub fall: Sound,
+#[derive(Default)]
+struct MusicState {
+    sound: Option<Sound>,
+    /// What it was asked to play with before it was loaded
+    waiting: Option<(bool, f32)>,
 }
 
-impl Sounds {
-    async fn init() -> Self {
-        Self {
-            title_jingle: sound("title/jingle").await,
-            engineer_gaming: sound("engineer_gaming").await,
+impl Music {
+    fn load(path: &'static str) -> Self {
+        let state = Arc::new(Mutex::new(MusicState::default()));
+        let loading = Arc::clone(&state);
+        start_coroutine(async move {
+            // if it's missing, it just never starts
+            let sound = match sound(path).await {
+                Some(it) => it,
+                None => return,
+            };
+            let mut state = loading.lock().unwrap();
+            state.sound = Some(sound);
+            if let Some((looped, volume)) = state.waiting.take() {
+                play_sound(sound, PlaySoundParams { looped, volume });
+            }
+        });
+        Self { state }
+    }
+
+    pub fn play(&self, params: PlaySoundParams) {
+        let mut state = self.state.lock().unwrap();
+        match state.sound {
+            Some(sound) => play_sound(sound, params),
+            None => state.waiting = Some((params.looped, params.volume)),
+        }
+    }
 
-            pickup: sound("pick_up").await,
-            putdown: sound("drop").await,
-            rotate: sound("rotate").await,
-            damage: sound("break").await,
-            fall: sound("fall").await,
+    pub fn stop(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.waiting = None;
+        if let Some(sound) = state.sound {
+            stop_sound(sound);
         }
     }
+
+    /// Turn it up or down while it plays, or before it starts.
+    pub fn set_volume(&self, volume: f32) {
+        let mut state = self.state.lock().unwrap();
+        if let Some((_, waiting_volume)) = &mut state.waiting {
+            *waiting_volume = volume;
+        }
+        if let Some(sound) = state.sound {
+            set_sound_volume(sound, volume);
+        }
+    }
+
+    /// Whether this is the same recording as that, and not just one that sounds alike
+    pub fn same_recording(&self, other: &Music) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
 }
 
 /// Path to the assets root
+#[cfg(not(feature = "bundled-assets"))]
 static ASSETS_ROOT: Lazy<PathBuf> = Lazy::new(|| {
     if cfg!(target_arch = "wasm32") {
         PathBuf::from("./assets")
     } else if cfg!(debug_assertions) {
         PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/assets"))
     } else {
-        todo!("assets path for release hasn't been finalized yet ;-;")
+        // release builds without bundled assets ship the folder next to the executable
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| Some(exe.parent()?.join("assets")))
+            .unwrap_or_else(|| PathBuf::from("./assets"))
     }
 });
 
+/// Everything that couldn't be loaded, by its path under the assets root
+pub fn missing() -> Vec<String> {
+    MISSING.lock().unwrap().clone()
+}
+
+/// Note down that something couldn't be loaded, or couldn't be made sense of,
+/// so it can be shown on screen
+fn report_missing(path: &str, why: impl std::fmt::Display) {
+    eprintln!("couldn't load asset {}, going without: {}", path, why);
+    MISSING.lock().unwrap().push(path.to_owned());
+}
+
+/// The contents of a file under the assets root, like `"textures/stone.png"`
+#[cfg(not(feature = "bundled-assets"))]
+async fn asset_bytes(path: &str) -> Result<Vec<u8>, String> {
+    let full_path = ASSETS_ROOT.join(path);
+    macroquad::file::load_file(full_path.to_string_lossy().as_ref())
+        .await
+        .map_err(|err| format!("{:?}", err))
+}
+
+/// The contents of a file under the assets root, like `"textures/stone.png"`
+#[cfg(feature = "bundled-assets")]
+async fn asset_bytes(path: &str) -> Result<Vec<u8>, String> {
+    bundled::get(path)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| "it isn't in the bundle".to_owned())
+}
+
 async fn texture(path: &str) -> Texture2D {
-    let with_extension = path.to_owned() + ".png";
-    let tex = load_texture(
-        ASSETS_ROOT
-            .join("textures")
-            .join(with_extension)
-            .to_string_lossy()
-            .as_ref(),
-    )
-    .await
-    .unwrap();
+    let full_path = format!("textures/{}.png", path);
+    // macroquad panics on a file it can't decode, so it gets decoded here instead
+    let decoded = asset_bytes(&full_path)
+        .await
+        .and_then(|bytes| image::load_from_memory(&bytes).map_err(|err| err.to_string()));
+    let tex = match decoded {
+        Ok(image) => {
+            let image = image.to_rgba8();
+            Texture2D::from_rgba8(image.width() as u16, image.height() as u16, &image)
+        }
+        Err(err) => {
+            report_missing(&full_path, err);
+            missing_texture()
+        }
+    };
     tex.set_filter(FilterMode::Nearest);
+    loaded_one().await;
     tex
 }
 
-async fn sound(path: &str) -> Sound {
-    let with_extension = path.to_owned() + ".ogg";
-    load_sound(
-        ASSETS_ROOT
-            .join("sounds")
-            .join(with_extension)
-            .to_string_lossy()
-            .as_ref(),
-    )
-    .await
-    .unwrap()
+/// A loud magenta checkerboard, so missing textures stand out
+fn missing_texture() -> Texture2D {
+    let mut image = Image::gen_image_color(16, 16, MAGENTA);
+    for y in 0..16 {
+        for x in 0..16 {
+            if (x / 4 + y / 4) % 2 == 1 {
+                image.set_pixel(x, y, BLACK);
+            }
+        }
+    }
+    Texture2D::from_image(&image)
+}
+
+async fn blueprint(path: &str) -> Option<Blueprint> {
+    let full_path = format!("blueprints/{}.toml", path);
+    let bytes = asset_bytes(&full_path).await;
+    loaded_one().await;
+    let bytes = match bytes {
+        Ok(it) => it,
+        Err(err) => {
+            report_missing(&full_path, err);
+            return None;
+        }
+    };
+    // a typo in a hand-edited file shouldn't stop the game from starting
+    match Blueprint::parse(&String::from_utf8_lossy(&bytes)) {
+        Ok(it) => Some(it),
+        Err(err) => {
+            report_missing(&full_path, err);
+            None
+        }
+    }
+}
+
+async fn level(path: &str) -> Option<Level> {
+    let full_path = format!("levels/{}.toml", path);
+    let bytes = asset_bytes(&full_path).await;
+    loaded_one().await;
+    let bytes = match bytes {
+        Ok(it) => it,
+        Err(err) => {
+            report_missing(&full_path, err);
+            return None;
+        }
+    };
+    match Level::parse(&String::from_utf8_lossy(&bytes)) {
+        Ok(it) => Some(it),
+        Err(err) => {
+            report_missing(&full_path, err);
+            None
+        }
+    }
+}
+
+/// A sound that the loading screen waits on, unlike the music
+async fn sound_effect(path: &str) -> Option<Sound> {
+    let sound = sound(path).await;
+    loaded_one().await;
+    sound
+}
+
+#[cfg(not(feature = "bundled-assets"))]
+async fn sound(path: &str) -> Option<Sound> {
+    let full_path = format!("sounds/{}.ogg", path);
+    match load_sound(ASSETS_ROOT.join(&full_path).to_string_lossy().as_ref()).await {
+        Ok(it) => Some(it),
+        Err(err) => {
+            report_missing(&full_path, format!("{:?}", err));
+            None
+        }
+    }
+}
+
+/// This version of macroquad can only decode sounds from a path,
+/// so bundled ones get unpacked to the temp folder first.
+#[cfg(feature = "bundled-assets")]
+async fn sound(path: &str) -> Option<Sound> {
+    let full_path = format!("sounds/{}.ogg", path);
+    let unpacked = std::env::temp_dir()
+        .join(env!("CARGO_PKG_NAME"))
+        .join(format!("{}.ogg", path));
+    let unpacking = asset_bytes(&full_path).await.and_then(|bytes| {
+        if let Some(parent) = unpacked.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        std::fs::write(&unpacked, bytes).map_err(|err| err.to_string())
+    });
+    if let Err(err) = unpacking {
+        report_missing(&full_path, err);
+        return None;
+    }
+    match load_sound(unpacked.to_string_lossy().as_ref()).await {
+        Ok(it) => Some(it),
+        Err(err) => {
+            report_missing(&full_path, format!("{:?}", err));
+            None
+        }
+    }
+}
+
+/// Every asset, built into the executable.
+/// Anything new in `Assets` has to be added here too, or bundled builds go without it.
+#[cfg(feature = "bundled-assets")]
+mod bundled {
+    #[cfg(target_arch = "wasm32")]
+    compile_error!("bundled assets are for desktop builds; the web build serves the assets folder");
+
+    macro_rules! bundle {
+        ($($path:literal),* $(,)?) => {
+            /// The bytes of an asset by its path under the assets root
+            pub fn get(path: &str) -> Option<&'static [u8]> {
+                match path {
+                    $($path => Some(include_bytes!(concat!(
+                        env!("CARGO_MANIFEST_DIR"),
+                        "/assets/",
+                        $path
+                    ))),)*
+                    _ => None,
+                }
+            }
+        };
+    }
+
+    bundle! {
+        "blueprints/arch.toml",
+        "blueprints/funnel.toml",
+        "blueprints/pillars.toml",
+        "levels/01_first_steps.toml",
+        "levels/02_narrows.toml",
+        "levels/03_scaffolding.toml",
+        "levels/04_solid_ground.toml",
+        "levels/05_hold_on.toml",
+        "levels/06_wide_open.toml",
+        "levels/07_sparse.toml",
+        "levels/08_girders.toml",
+        "levels/09_slow_burn.toml",
+        "levels/10_needle.toml",
+        "levels/11_heavy.toml",
+        "levels/12_the_deep.toml",
+        "sounds/break.ogg",
+        "sounds/drop.ogg",
+        "sounds/engineer_gaming.ogg",
+        "sounds/fall.ogg",
+        "sounds/pick_up.ogg",
+        "sounds/rotate.ogg",
+        "sounds/title/jingle.ogg",
+        "textures/chasm_body.png",
+        "textures/chasm_edge.png",
+        "textures/connector_atlas.png",
+        "textures/conveyor.png",
+        "textures/crane.png",
+        "textures/damage_atlas.png",
+        "textures/denoument.png",
+        "textures/depth_meter.png",
+        "textures/explosive.png",
+        "textures/finish_popup.png",
+        "textures/font_atlas.png",
+        "textures/fossil.png",
+        "textures/girder.png",
+        "textures/glue.png",
+        "textures/hold_slot.png",
+        "textures/idol.png",
+        "textures/number_atlas.png",
+        "textures/reinforced.png",
+        "textures/reroll.png",
+        "textures/rust2.png",
+        "textures/scaffold.png",
+        "textures/stone.png",
+        "textures/stone2.png",
+        "textures/stone3.png",
+        "textures/terrain-iron-simple-bottom.png",
+        "textures/titlescreen.png",
+        "textures/title/banner.png",
+    }
 }
@@ -0,0 +1,86 @@
+//! Playing a frame's worth of sound effects.
+//!
+//! Modes queue sounds up on the [`AudioPlayer`] in `Globals` during `update`, with how far away
+//! they were, and the main loop plays them all at the end of the frame. The same sound queued twice in a frame only plays once.
+//!
+//! Each play is a little louder or quieter than the last, so repeats don't sound canned.
+//! This version of macroquad can only set how loud a sound plays and not its pitch,
+//! so that's where the variety goes.
+//!
+//! Everything plays at the gains in [`Settings`], so the volume hotkeys here
+//! reach every sound in the game.
+
+use crate::settings::Settings;
+
+use macroquad::{
+    audio::{play_sound, PlaySoundParams, Sound},
+    prelude::{is_key_pressed, KeyCode},
+    rand::gen_range,
+};
+
+/// Sounds this many blocks past the edge of the screen can't be heard at all
+const HEARING_RANGE: f32 = 12.0;
+/// The quietest a sound gets from the random variation, next to how loud it would be
+const MIN_VARIATION: f32 = 0.8;
+/// How far the master volume hotkeys turn it, in percent
+const MASTER_STEP: u8 = 10;
+
+/// M mutes everything, and - and = turn the master volume down and up.
+/// Keys the top mode says it's using for something else are left alone.
+///
+/// Returns whether the settings changed.
+pub fn volume_hotkeys(settings: &mut Settings, claimed: impl Fn(KeyCode) -> bool) -> bool {
+    let pressed = |key| is_key_pressed(key) && !claimed(key);
+    if pressed(KeyCode::M) {
+        settings.muted = !settings.muted;
+    } else if pressed(KeyCode::Minus) {
+        settings.muted = false;
+        settings.master_volume = settings.master_volume.saturating_sub(MASTER_STEP);
+    } else if pressed(KeyCode::Equal) {
+        settings.muted = false;
+        settings.master_volume = (settings.master_volume + MASTER_STEP).min(Settings::MAX_VOLUME);
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Collects sounds over a frame to play at the end of it.
+#[derive(Clone, Default)]
+pub struct AudioPlayer {
+    cues: Vec<(Sound, f32)>,
+}
+
+impl AudioPlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue up a sound at this volume, from this many blocks past the edge of the screen.
+    /// Anything on screen is 0 blocks past it.
+    ///
+    /// If it's already queued, the loudest reason to play it wins.
+    pub fn cue(&mut self, sound: Sound, volume: f32, offscreen: f32) {
+        let volume = volume * (1.0 - offscreen / HEARING_RANGE).max(0.0);
+        match self.cues.iter_mut().find(|(queued, _)| *queued == sound) {
+            Some((_, loudest)) => *loudest = loudest.max(volume),
+            None => self.cues.push((sound, volume)),
+        }
+    }
+
+    /// Play everything queued, at this gain, and empty the queue.
+    pub fn play(&mut self, gain: f32) {
+        for (sound, volume) in self.cues.drain(..) {
+            if volume <= 0.0 {
+                continue;
+            }
+            play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: false,
+                    volume: volume * gain * gen_range(MIN_VARIATION, 1.0),
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! The mouse cursor, drawn in pixel space so it's as chunky as everything else.
+//!
+//! The system cursor is hidden, and modes say which of these to draw instead.
+
+use macroquad::prelude::*;
+
+use crate::{drawutils::hexcolor, ui};
+
+/// What the cursor looks like.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CursorIcon {
+    /// Plain old arrow
+    Pointer,
+    /// Something here can be picked up
+    Grab,
+    /// The held block can go here
+    Crosshair,
+    /// Clicking here knocks something down
+    Hammer,
+    /// The held block can't go here
+    Forbidden,
+}
+
+/// Draw the cursor with its hotspot at `(mx, my)`. Do this after everything else.
+pub fn draw_cursor(icon: CursorIcon, mx: f32, my: f32) {
+    let (x, y) = (mx.round(), my.round());
+    let outline = hexcolor(ui::BACKGROUND);
+    let fill = hexcolor(ui::FOREGROUND);
+    match icon {
+        CursorIcon::Pointer => {
+            let (a, b, c) = (vec2(x, y), vec2(x, y + 8.0), vec2(x + 6.0, y + 6.0));
+            draw_triangle(a, b, c, fill);
+            draw_triangle_lines(a, b, c, 1.0, outline);
+        }
+        CursorIcon::Grab => {
+            // a fist: four knuckles over a palm
+            draw_rectangle(x - 4.0, y - 2.0, 8.0, 6.0, outline);
+            draw_rectangle(x - 3.0, y - 1.0, 6.0, 4.0, fill);
+            for knuckle in 0..4 {
+                let kx = x - 4.0 + knuckle as f32 * 2.0;
+                draw_rectangle(kx, y - 4.0, 2.0, 2.0, fill);
+            }
+        }
+        CursorIcon::Crosshair => {
+            // four ticks around the hotspot, leaving it clear
+            let ticks = [
+                (-5.0, 0.0, 4.0, 1.0),
+                (2.0, 0.0, 4.0, 1.0),
+                (0.0, -5.0, 1.0, 4.0),
+                (0.0, 2.0, 1.0, 4.0),
+            ];
+            for &(dx, dy, w, h) in ticks.iter() {
+                draw_rectangle(x + dx - 1.0, y + dy - 1.0, w + 2.0, h + 2.0, outline);
+                draw_rectangle(x + dx, y + dy, w, h, fill);
+            }
+        }
+        CursorIcon::Hammer => {
+            // head up top, handle going down and to the left
+            draw_line(x - 4.0, y + 6.0, x + 1.0, y + 1.0, 2.0, outline);
+            draw_rectangle(x - 2.0, y - 3.0, 7.0, 4.0, outline);
+            draw_rectangle(x - 1.0, y - 2.0, 5.0, 2.0, fill);
+        }
+        CursorIcon::Forbidden => {
+            let alert = hexcolor(ui::ALERT);
+            draw_circle_lines(x, y, 4.0, 3.0, outline);
+            draw_circle_lines(x, y, 4.0, 1.5, alert);
+            draw_line(x - 3.0, y + 3.0, x + 3.0, y - 3.0, 1.5, alert);
+        }
+    }
+}
@@ -2,14 +2,154 @@ use macroquad::prelude::*;
 
 use crate::{wh_deficit, Globals, HEIGHT, WIDTH};
 
+use std::rc::Rc;
+
+/// Horizontal distance between characters in the font atlas
+const GLYPH_ADVANCE: f32 = 4.0;
+/// Size of each cell in the font atlas
+const GLYPH_CELL: (f32, f32) = (4.0, 6.0);
+/// Vertical distance between lines of text
+pub const LINE_HEIGHT: f32 = 7.0;
+
 /// Make a Color from an RRGGBBAA hex code.
 pub fn hexcolor(code: u32) -> Color {
     let [r, g, b, a] = code.to_be_bytes();
     Color::from_rgba(r, g, b, a)
 }
 
+/// A camera that maps the game's `WIDTH`x`HEIGHT` pixel space onto this render target.
+pub fn pixel_camera(target: RenderTarget) -> Camera2D {
+    area_camera(target, Rect::new(0.0, 0.0, WIDTH, HEIGHT))
+}
+
+/// A camera that maps this part of the pixel space onto this render target.
+/// It can reach off the canvas, for pictures bigger than the screen.
+pub fn area_camera(target: RenderTarget, area: Rect) -> Camera2D {
+    // These divides and multiplies are required to get the camera in the center of the area
+    // and having it fill everything.
+    Camera2D {
+        render_target: Some(target),
+        zoom: vec2(area.w.recip() * 2.0, area.h.recip() * 2.0),
+        target: vec2(area.x + area.w / 2.0, area.y + area.h / 2.0),
+        ..Default::default()
+    }
+}
+
+/// Draw something into a render target instead of the screen, like a screenshot.
+///
+/// This leaves the default camera set afterwards, so only call it outside
+/// of drawing to the main canvas.
+pub fn draw_offscreen(target: RenderTarget, draw: impl FnOnce()) {
+    draw_offscreen_area(target, Rect::new(0.0, 0.0, WIDTH, HEIGHT), draw);
+}
+
+/// Like [`draw_offscreen`], but only this part of the pixel space ends up in the target.
+pub fn draw_offscreen_area(target: RenderTarget, area: Rect, draw: impl FnOnce()) {
+    set_camera(&area_camera(target, area));
+    clear_background(WHITE);
+    draw();
+    set_default_camera();
+}
+
+/// A render target that gives its memory back to the graphics card
+/// once the last clone of it is dropped.
+///
+/// Plain `RenderTarget`s are never freed, so anything made per run or per picture uses these.
+#[derive(Clone)]
+pub struct OffscreenTarget(Rc<OwnedTarget>);
+
+struct OwnedTarget(RenderTarget);
+
+impl OffscreenTarget {
+    /// A `width`x`height` target with crisp pixels
+    pub fn new(width: u32, height: u32) -> Self {
+        let target = render_target(width, height);
+        target.texture.set_filter(FilterMode::Nearest);
+        Self(Rc::new(OwnedTarget(target)))
+    }
+
+    /// One the size of the screen
+    pub fn screen() -> Self {
+        Self::new(WIDTH as u32, HEIGHT as u32)
+    }
+
+    /// The target itself, to draw into. Don't hang on to it past this.
+    pub fn get(&self) -> RenderTarget {
+        (self.0).0
+    }
+
+    pub fn texture(&self) -> Texture2D {
+        self.get().texture
+    }
+}
+
+impl Drop for OwnedTarget {
+    fn drop(&mut self) {
+        // deleting the pass deletes its texture too
+        let gl = unsafe { get_internal_gl() };
+        self.0.render_pass.delete(gl.quad_context);
+    }
+}
+
+/// Draw something shrunk or grown by `scale`, around the point `(center_x, center_y)`.
+pub fn draw_scaled(center_x: f32, center_y: f32, scale: f32, draw: impl FnOnce()) {
+    let transform = Mat4::from_translation(vec3(center_x, center_y, 0.0))
+        * Mat4::from_scale(vec3(scale, scale, 1.0))
+        * Mat4::from_translation(vec3(-center_x, -center_y, 0.0));
+    unsafe { get_internal_gl() }
+        .quad_gl
+        .push_model_matrix(transform);
+    draw();
+    unsafe { get_internal_gl() }.quad_gl.pop_model_matrix();
+}
+
+/// How much to shrink the whole canvas to fit it in this part of the canvas,
+/// and where its corner goes. It keeps its shape, centered in what's left over.
+pub fn fit_in(area: Rect) -> (f32, Vec2) {
+    let scale = (area.w / WIDTH).min(area.h / HEIGHT);
+    let corner = vec2(
+        area.x + (area.w - WIDTH * scale) / 2.0,
+        area.y + (area.h - HEIGHT * scale) / 2.0,
+    );
+    (scale, corner)
+}
+
+/// Draw something laid out for the whole canvas, shrunk to fit in this part of it.
+pub fn draw_fitted(area: Rect, draw: impl FnOnce()) {
+    let (scale, corner) = fit_in(area);
+    let transform = Mat4::from_translation(vec3(corner.x, corner.y, 0.0))
+        * Mat4::from_scale(vec3(scale, scale, 1.0));
+    unsafe { get_internal_gl() }
+        .quad_gl
+        .push_model_matrix(transform);
+    draw();
+    unsafe { get_internal_gl() }.quad_gl.pop_model_matrix();
+}
+
+/// Where a point on the canvas is in something drawn with [`draw_fitted`] in this area
+pub fn unfit(area: Rect, x: f32, y: f32) -> (f32, f32) {
+    let (scale, corner) = fit_in(area);
+    ((x - corner.x) / scale, (y - corner.y) / scale)
+}
+
+/// Draw something upside down, flipped over the horizontal line at `center_y`.
+pub fn draw_mirrored(center_y: f32, draw: impl FnOnce()) {
+    let mirror = Mat4::from_translation(vec3(0.0, center_y * 2.0, 0.0))
+        * Mat4::from_scale(vec3(1.0, -1.0, 1.0));
+    unsafe { get_internal_gl() }
+        .quad_gl
+        .push_model_matrix(mirror);
+    draw();
+    unsafe { get_internal_gl() }.quad_gl.pop_model_matrix();
+}
+
 pub fn mouse_position_pixel() -> (f32, f32) {
     let (mx, my) = mouse_position();
+    window_to_pixel(mx, my)
+}
+
+/// Turn a position in the window into pixel coordinates in the game.
+pub fn window_to_pixel(mx: f32, my: f32) -> (f32, f32) {
     let (wd, hd) = wh_deficit();
     let mx = (mx - wd / 2.0) / ((screen_width() - wd) / WIDTH);
     let my = (my - hd / 2.0) / ((screen_height() - hd) / HEIGHT);
@@ -46,3 +186,77 @@ pub fn draw_number(num: i32, corner_x: f32, corner_y: f32, globals: &Globals) {
         );
     }
 }
+
+/// Draw some text with the bitmap font.
+/// `(corner_x, corner_y)` is the upper left corner of the text.
+///
+/// The font only has uppercase letters, so lowercase gets shouted.
+pub fn draw_text_px(text: &str, corner_x: f32, corner_y: f32, color: Color, globals: &Globals) {
+    draw_text_aligned(text, corner_x, corner_y, TextAlign::Left, color, globals);
+}
+
+/// Which side of `x` text goes on in [`draw_text_aligned`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    /// `x` is the left edge
+    Left,
+    /// `x` is the middle
+    Center,
+    /// `x` is the right edge
+    Right,
+}
+
+/// Draw some text with the bitmap font, lined up against `x` and with `y` as its top.
+///
+/// Each `\n` starts a new line `LINE_HEIGHT` further down, and each line is aligned on its own.
+pub fn draw_text_aligned(
+    text: &str,
+    x: f32,
+    y: f32,
+    align: TextAlign,
+    color: Color,
+    globals: &Globals,
+) {
+    for (line_idx, line) in text.lines().enumerate() {
+        let width = text_width_px(line);
+        let corner_x = match align {
+            TextAlign::Left => x,
+            TextAlign::Center => (x - width / 2.0).round(),
+            TextAlign::Right => x - width,
+        };
+        let corner_y = y + line_idx as f32 * LINE_HEIGHT;
+        draw_line_px(line, corner_x, corner_y, color, globals);
+    }
+}
+
+fn draw_line_px(text: &str, corner_x: f32, corner_y: f32, color: Color, globals: &Globals) {
+    for (idx, c) in text.chars().enumerate() {
+        let c = c.to_ascii_uppercase();
+        if !(' '..='~').contains(&c) {
+            // hmm
+            continue;
+        }
+        let atlas_idx = c as u32 - ' ' as u32;
+        let sx = (atlas_idx % 16) as f32 * GLYPH_CELL.0;
+        let sy = (atlas_idx / 16) as f32 * GLYPH_CELL.1;
+
+        draw_texture_ex(
+            globals.assets.textures.font_atlas,
+            corner_x + idx as f32 * GLYPH_ADVANCE,
+            corner_y,
+            color,
+            DrawTextureParams {
+                source: Some(Rect::new(sx, sy, 3.0, 5.0)),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// How many pixels wide this text is when drawn with `draw_text_px`.
+/// For text with several lines, this is the widest one.
+pub fn text_width_px(text: &str) -> f32 {
+    text.lines()
+        .map(|line| (line.chars().count() as f32 * GLYPH_ADVANCE - 1.0).max(0.0))
+        .fold(0.0, f32::max)
+}
@@ -0,0 +1,83 @@
+//! Noticing when the game can't keep up with the display,
+//! and keeping the simulation at the same speed whatever the display does.
+
+use std::collections::VecDeque;
+
+/// How many frames to average over, about five seconds at 60fps
+const WINDOW: usize = 300;
+/// Averaging slower than this (in seconds per frame) counts as struggling
+const SLOW_FRAME_TIME: f32 = 1.0 / 45.0;
+
+/// How many times a second the simulation steps
+pub const SIM_RATE: f32 = 60.0;
+/// After a hitch, catch up by at most this many steps in one frame instead of
+/// freezing up running all of them
+const MAX_TICKS_PER_FRAME: u32 = 4;
+
+/// Saves up time from frames until there's enough for a step of the simulation.
+///
+/// On a 144Hz monitor most frames run no steps; on a slow wasm build some run two.
+#[derive(Clone, Default)]
+pub struct FixedTimestep {
+    /// Steps owed, and how far it is into the next one
+    accumulator: f32,
+    ticks: u32,
+}
+
+impl FixedTimestep {
+    /// Count up the steps owed for a frame that took this long, in seconds.
+    pub fn advance(&mut self, frame_time: f32) {
+        self.accumulator += frame_time * SIM_RATE;
+        let owed = self.accumulator.floor();
+        self.ticks = (owed as u32).min(MAX_TICKS_PER_FRAME);
+        // whatever's past the cap is dropped, instead of piling up
+        self.accumulator -= owed;
+    }
+
+    /// How many steps to run this frame
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    /// How far it is from the last step to the next one, from 0 to 1.
+    /// Things that move get drawn this far between where they were and where they are.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator
+    }
+}
+
+/// Keeps track of how long recent frames took.
+#[derive(Clone, Default)]
+pub struct FrameMonitor {
+    times: VecDeque<f32>,
+    total: f32,
+    /// Whether the player has already been told to turn the quality down
+    suggested: bool,
+}
+
+impl FrameMonitor {
+    pub fn record(&mut self, frame_time: f32) {
+        self.times.push_back(frame_time);
+        self.total += frame_time;
+        if self.times.len() > WINDOW {
+            self.total -= self.times.pop_front().unwrap();
+        }
+    }
+
+    /// Whether frames have been slow for the whole window.
+    ///
+    /// A single hitch doesn't count; this is for hardware that's too weak all the time.
+    pub fn is_struggling(&self) -> bool {
+        self.times.len() >= WINDOW && self.total / WINDOW as f32 > SLOW_FRAME_TIME
+    }
+
+    /// Whether to suggest lower graphics quality now. This only says yes once a session.
+    pub fn should_suggest(&mut self) -> bool {
+        if !self.suggested && self.is_struggling() {
+            self.suggested = true;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,307 @@
+#![feature(hash_drain_filter)]
+
+mod assets;
+mod audio;
+mod cursor;
+mod drawutils;
+mod framerate;
+mod modes;
+mod music;
+mod particles;
+mod profile;
+mod random;
+mod save;
+mod score;
+mod seasonal;
+mod settings;
+mod stats;
+mod ui;
+
+use assets::AssetHandle;
+use audio::AudioPlayer;
+use cursor::CursorIcon;
+use framerate::{FixedTimestep, FrameMonitor};
+use modes::{
+    poll_pointer_events, BlockKind, GameType, InputFrame, ModeAssist, ModeDaily, ModeDenoument,
+    ModeLevelSelect, ModeLoading, ModeNameEntry, ModeOptions, ModePlaying, ModeReplayPicker,
+    ModeRules, ModeSelect, ModeStats, ModeTitle, ModeTutorial, ModeVersus, ModeVersusResult,
+    NameEntryPurpose, Objective, Replay, RunConfig, RunSnapshot, SavePolicy,
+};
+use music::{MusicManager, Track};
+use profile::Profile;
+use score::{DailyBests, HighScores};
+use seasonal::{Season, Theme};
+use settings::{RenderStyle, Settings};
+use stats::Stats;
+
+/// For `examples/stress.rs`, which isn't part of the game
+#[doc(hidden)]
+pub use modes::StressRun;
+
+use macroquad::prelude::*;
+
+const WIDTH: f32 = 320.0;
+const HEIGHT: f32 = 240.0;
+const ASPECT_RATIO: f32 = WIDTH / HEIGHT;
+
+/// The window the game wants, for `macroquad::main`.
+pub fn window_conf() -> Conf {
+    Conf {
+        window_title: if cfg!(debug_assertions) {
+            concat!(env!("CARGO_CRATE_NAME"), " v", env!("CARGO_PKG_VERSION"))
+        } else {
+            "Excavation Site Alpha"
+        }
+        .to_owned(),
+        // the window has to exist before the globals do, so this reads the settings itself
+        fullscreen: Settings::load().fullscreen,
+        sample_count: Settings::load().graphics.sample_count(),
+        ..Default::default()
+    }
+}
+
+/// Run the game until the window closes.
+pub async fn run() {
+    // Drawing must happen on the main thread (thanks macroquad...)
+    // so updating goes over here
+    let mut globals = Globals::new();
+    // the assets load behind the loading screen
+    let mut mode_stack: Vec<Box<dyn Gamemode>> = vec![Box::new(ModeLoading::new())];
+
+    let canvas = render_target(WIDTH as u32, HEIGHT as u32);
+    canvas.texture.set_filter(FilterMode::Nearest);
+    // the cursor gets drawn onto the canvas instead
+    show_mouse(false);
+    let mut missing_assets = ui::MissingAssets::default();
+    let mut volume_popup = ui::VolumePopup::default();
+    loop {
+        poll_pointer_events();
+        set_camera(&drawutils::pixel_camera(canvas));
+        clear_background(WHITE);
+        // Draw the state, and whatever's under it that it lets show through.
+        let bottom_drawn = mode_stack
+            .iter()
+            .rposition(|mode| !mode.translucent())
+            .unwrap_or(0);
+        for (idx, mode) in mode_stack.iter().enumerate().skip(bottom_drawn) {
+            if idx > bottom_drawn {
+                ui::draw_dim_overlay();
+            }
+            mode.draw(&globals);
+        }
+        // the list needs the font, so it waits for loading to finish
+        if globals.assets.is_loaded() {
+            missing_assets.draw(&globals);
+            volume_popup.draw(&globals);
+        }
+        let (mx, my) = drawutils::mouse_position_pixel();
+        let cursor = mode_stack.last().unwrap().cursor_icon(mx, my);
+        cursor::draw_cursor(cursor, mx, my);
+
+        // Done rendering to the canvas; go back to our normal camera
+        // to size the canvas
+        set_default_camera();
+        clear_background(BLACK);
+
+        // Figure out the drawbox.
+        // these are how much wider/taller the window is than the content
+        let (width_deficit, height_deficit) = wh_deficit();
+        let (shake_x, shake_y) = if globals.settings.reduced_motion {
+            (0.0, 0.0)
+        } else {
+            mode_stack.last().unwrap().screen_shake()
+        };
+        // the shake is in canvas pixels, so scale it up to the window
+        let scale = (screen_width() - width_deficit) / WIDTH;
+        draw_texture_ex(
+            canvas.texture,
+            width_deficit / 2.0 + shake_x * scale,
+            height_deficit / 2.0 + shake_y * scale,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(
+                    screen_width() - width_deficit,
+                    screen_height() - height_deficit,
+                )),
+                ..Default::default()
+            },
+        );
+        // Update the current state.
+        // To change state, return a non-None transition.
+        globals.timestep.advance(get_frame_time());
+        missing_assets.update();
+        volume_popup.update();
+        let top = mode_stack.last().unwrap();
+        if audio::volume_hotkeys(&mut globals.settings, |key| top.claims_key(key)) {
+            globals.settings.save();
+            volume_popup.show();
+        }
+        let transition = mode_stack.last_mut().unwrap().update(&mut globals);
+        if !matches!(transition, Transition::None) {
+            // runs add to the stats every step, so this saves them a lot less often than that
+            globals.stats.save_if_changed();
+        }
+        match transition {
+            Transition::None => {}
+            Transition::Push(new_mode) => mode_stack.push(new_mode),
+            Transition::Pop => {
+                if mode_stack.len() >= 2 {
+                    mode_stack.pop();
+                }
+            }
+            Transition::Swap(new_mode) => {
+                if !mode_stack.is_empty() {
+                    mode_stack.pop();
+                }
+                mode_stack.push(new_mode)
+            }
+        }
+
+        // the music goes with whichever mode is on top now
+        if globals.assets.is_loaded() {
+            match mode_stack.iter().rev().find_map(|mode| mode.music()) {
+                Some(track) => globals.music.play(track),
+                None => globals.music.stop(),
+            }
+            let gain = globals.settings.music_gain();
+            globals.music.update(&globals.assets.sounds, gain);
+        }
+        // everything the update queued, once each
+        let gain = globals.settings.sfx_gain();
+        globals.audio.play(gain);
+
+        globals.frames_ran += 1;
+        globals.frame_monitor.record(get_frame_time());
+        save::pump();
+
+        next_frame().await
+    }
+}
+
+/// Different modes the game can be in.
+///
+/// Add your states here by implementing this for them.
+pub trait Gamemode {
+    /// Run a frame of logic. To change state, return a non-None transition.
+    fn update(&mut self, globals: &mut Globals) -> Transition;
+
+    /// Draw the mode onto the canvas.
+    fn draw(&self, globals: &Globals);
+
+    /// What the mouse cursor should look like over this spot.
+    fn cursor_icon(&self, _mx: f32, _my: f32) -> CursorIcon {
+        CursorIcon::Pointer
+    }
+
+    /// How far to knock the whole screen this frame, in pixels, for screen shake.
+    fn screen_shake(&self) -> (f32, f32) {
+        (0.0, 0.0)
+    }
+
+    /// Whether this mode uses this key for something, so the global hotkeys should leave it be.
+    fn claims_key(&self, _key: KeyCode) -> bool {
+        false
+    }
+
+    /// The music that goes with this mode.
+    ///
+    /// With `None`, whatever goes with the mode under it keeps playing,
+    /// or it goes quiet if there's nothing under it.
+    fn music(&self) -> Option<Track> {
+        None
+    }
+
+    /// Whether the modes under this one should still show through, dimmed.
+    ///
+    /// Modes under the top one don't get updated, so they're drawn frozen.
+    fn translucent(&self) -> bool {
+        false
+    }
+}
+
+/// Ways modes can transition
+pub enum Transition {
+    /// Do nothing
+    None,
+    /// Push this mode onto the stack
+    Push(Box<dyn Gamemode>),
+    /// Pop the top mode off the stack
+    Pop,
+    /// Pop the top mode off and replace it with this
+    Swap(Box<dyn Gamemode>),
+}
+
+/// Global information useful for all modes
+#[derive(Clone)]
+pub struct Globals {
+    /// Only the loading screen runs before these are ready
+    assets: AssetHandle,
+    profile: Profile,
+    settings: Settings,
+    high_scores: HighScores,
+    stats: Stats,
+    daily_bests: DailyBests,
+    music: MusicManager,
+    /// Sound effects to play at the end of the frame.
+    /// Modes queue them up here in `update`, so they play once however often `draw` runs.
+    audio: AudioPlayer,
+    frame_monitor: FrameMonitor,
+    /// How many steps of the simulation are due this frame
+    timestep: FixedTimestep,
+    /// What time of year it was when the game started
+    season: Season,
+    // at 2^64 frames, this will run out about when the sun dies!
+    // 0.97 x expected sun lifetime!
+    // how exciting.
+    frames_ran: u64,
+}
+
+impl Globals {
+    fn new() -> Self {
+        Self {
+            assets: AssetHandle::default(),
+            profile: Profile::load(),
+            settings: Settings::load(),
+            high_scores: HighScores::load(),
+            stats: Stats::load(),
+            daily_bests: DailyBests::load(),
+            music: MusicManager::default(),
+            audio: AudioPlayer::new(),
+            frame_monitor: FrameMonitor::default(),
+            timestep: FixedTimestep::default(),
+            season: Season::today(),
+            frames_ran: 0,
+        }
+    }
+
+    /// How things should look right now
+    fn theme(&self) -> Theme {
+        if self.settings.seasonal {
+            Theme::for_season(self.season)
+        } else {
+            Theme::for_season(Season::Ordinary)
+        }
+    }
+
+    /// What extra hints to draw on blocks
+    fn render_style(&self) -> RenderStyle {
+        RenderStyle {
+            tint_connectors: self.settings.tint_connectors,
+            glyphs: self.settings.connector_glyphs,
+        }
+    }
+}
+
+fn wh_deficit() -> (f32, f32) {
+    if (screen_width() / screen_height()) > ASPECT_RATIO {
+        // it's too wide! put bars on the sides!
+        // the height becomes the authority on how wide to draw
+        let expected_width = screen_height() * ASPECT_RATIO;
+        (screen_width() - expected_width, 0.0f32)
+    } else {
+        // it's too tall! put bars on the ends!
+        // the width is the authority
+        let expected_height = screen_width() / ASPECT_RATIO;
+        (0.0f32, screen_height() - expected_height)
+    }
+}
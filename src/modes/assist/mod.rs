@@ -0,0 +1,102 @@
+use crate::{
+    drawutils::hexcolor,
+    settings::AssistSettings,
+    ui::{self, Button},
+    Gamemode, Globals, Transition,
+};
+
+use macroquad::prelude::*;
+
+/// How much each click changes the decay rate, in percent
+const DECAY_STEP: u8 = 10;
+
+/// Tweak the assist options.
+#[derive(Clone)]
+pub struct ModeAssist {
+    slower: Button,
+    faster: Button,
+    snap: Button,
+    back: Button,
+}
+
+impl ModeAssist {
+    pub fn new() -> Self {
+        Self {
+            slower: Button::new(200.0, 80.0, 16.0, 16.0, "-"),
+            faster: Button::new(220.0, 80.0, 16.0, 16.0, "+"),
+            snap: Button::new(200.0, 104.0, 36.0, 16.0, ""),
+            back: Button::new(128.0, 200.0, 64.0, 16.0, "BACK"),
+        }
+    }
+}
+
+impl Gamemode for ModeAssist {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        let assist = &mut globals.settings.assist;
+        let before = assist.clone();
+
+        if self.slower.clicked() {
+            assist.decay_percent = assist
+                .decay_percent
+                .saturating_sub(DECAY_STEP)
+                .max(AssistSettings::MIN_DECAY_PERCENT);
+        }
+        if self.faster.clicked() {
+            assist.decay_percent =
+                (assist.decay_percent + DECAY_STEP).min(AssistSettings::MAX_DECAY_PERCENT);
+        }
+        if self.snap.clicked() {
+            assist.snap_placement = !assist.snap_placement;
+        }
+
+        let changed = before.decay_percent != assist.decay_percent
+            || before.snap_placement != assist.snap_placement;
+        if changed {
+            globals.settings.save();
+        }
+
+        if self.back.clicked() || is_key_pressed(KeyCode::Escape) {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn translucent(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, globals: &Globals) {
+        use crate::drawutils::draw_text_px;
+
+        let fg = hexcolor(ui::FOREGROUND);
+        let assist = &globals.settings.assist;
+
+        ui::draw_centered_text("ASSIST OPTIONS", 40.0, fg, globals);
+        ui::draw_centered_text(
+            "RUNS PLAYED WITH ASSISTS ARE MARKED AS ASSISTED",
+            54.0,
+            hexcolor(ui::DIM),
+            globals,
+        );
+
+        draw_text_px(
+            &format!("DECAY RATE: {}%", assist.decay_percent),
+            84.0,
+            86.0,
+            fg,
+            globals,
+        );
+        self.slower.draw(false, globals);
+        self.faster.draw(false, globals);
+
+        draw_text_px("SNAP PLACEMENT:", 84.0, 110.0, fg, globals);
+        let snap = Button {
+            label: if assist.snap_placement { "ON" } else { "OFF" }.to_owned(),
+            ..self.snap.clone()
+        };
+        snap.draw(assist.snap_placement, globals);
+
+        self.back.draw(false, globals);
+    }
+}
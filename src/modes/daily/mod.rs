@@ -0,0 +1,93 @@
+use crate::{
+    drawutils::hexcolor,
+    score::DailyBest,
+    seasonal,
+    ui::{self, Button},
+    GameType, Gamemode, Globals, ModePlaying, ModeTitle, RunConfig, Transition,
+};
+
+use macroquad::prelude::*;
+
+/// Mixed into the day to make its seed, so day 1 and seed 1 aren't the same run
+const DAILY_SALT: u64 = 0x5eed_da11_c4a5_3000;
+
+/// The daily challenge: a classic run with a seed that comes from the date,
+/// so everyone gets the same blocks all day.
+#[derive(Clone)]
+pub struct ModeDaily {
+    /// Days since the Unix epoch, as of opening this
+    day: i64,
+    play: Button,
+    back: Button,
+}
+
+impl ModeDaily {
+    pub fn new() -> Self {
+        Self {
+            day: seasonal::today(),
+            play: Button::new(88.0, 180.0, 64.0, 16.0, "PLAY"),
+            back: Button::new(168.0, 180.0, 64.0, 16.0, "BACK"),
+        }
+    }
+}
+
+impl Gamemode for ModeDaily {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        if self.back.clicked() || is_key_pressed(KeyCode::Escape) {
+            return Transition::Swap(Box::new(ModeTitle::new()));
+        }
+        if self.play.clicked() {
+            let config = RunConfig::new(GameType::Daily)
+                .with_day(self.day)
+                .with_assists(&globals.settings.assist);
+            return Transition::Swap(Box::new(ModePlaying::with_seed(
+                config,
+                daily_seed(self.day),
+            )));
+        }
+        Transition::None
+    }
+
+    fn draw(&self, globals: &Globals) {
+        clear_background(hexcolor(ui::BACKGROUND));
+        let (year, month, day) = seasonal::date_from_days(self.day);
+        let lines = [
+            ("DAILY CHALLENGE".to_owned(), ui::FOREGROUND),
+            (format!("{}-{:02}-{:02}", year, month, day), ui::DIM),
+            (String::new(), ui::DIM),
+            ("THE SAME BLOCKS FOR EVERYONE,".to_owned(), ui::DIM),
+            ("ALL DAY. A NEW ONE AT MIDNIGHT UTC.".to_owned(), ui::DIM),
+            (String::new(), ui::DIM),
+            (
+                describe_best("TODAY", globals.daily_bests.best(self.day)),
+                ui::FOREGROUND,
+            ),
+            (
+                describe_best("YESTERDAY", globals.daily_bests.best(self.day - 1)),
+                ui::FOREGROUND,
+            ),
+        ];
+        for (idx, (line, color)) in lines.iter().enumerate() {
+            ui::draw_centered_text(line, 40.0 + idx as f32 * 12.0, hexcolor(*color), globals);
+        }
+        self.play.draw(false, globals);
+        self.back.draw(false, globals);
+    }
+}
+
+/// The seed everyone's daily run uses on this day.
+fn daily_seed(day: i64) -> u64 {
+    // splitmix64's finalizer, so neighboring days get nothing alike
+    let mut seed = (day as u64) ^ DAILY_SALT;
+    seed = (seed ^ (seed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    seed = (seed ^ (seed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    seed ^ (seed >> 31)
+}
+
+/// A line about how a day went, like "TODAY: 23 DEEP".
+fn describe_best(label: &str, best: Option<&DailyBest>) -> String {
+    match best {
+        Some(best) => format!("{}: {} DEEP", label, best.depth.round() as i32),
+        None => format!("{}: NOT PLAYED", label),
+    }
+}
@@ -1,29 +1,222 @@
 use crate::{
-    drawutils::{self, mouse_position_pixel},
-    Gamemode, Globals, Transition,
+    drawutils::{self, hexcolor, mouse_position_pixel},
+    music::Track,
+    score::RunResult,
+    ui::{self, Button},
+    Gamemode, Globals, RunConfig, Transition, HEIGHT,
 };
 
 use macroquad::prelude::*;
 
-use super::{ModePlaying, ModeTitle};
+use super::{Filmstrip, ModePlaying, ModeTitle, Photo, Replay};
+
+mod run_log;
+
+/// The photo of the structure is fit into this, down the left side
+const PHOTO_AREA: Rect = Rect {
+    x: 19.0,
+    y: 20.0,
+    w: 54.0,
+    h: 120.0,
+};
+
+/// How seeds are shown, and typed back in on the title screen
+fn format_seed(seed: u64) -> String {
+    format!("{:X}", seed)
+}
 
 #[derive(Clone)]
 pub struct ModeDenoument {
-    score: f32,
+    result: RunResult,
+    /// So "play again" plays the same way
+    config: RunConfig,
+    /// Where the run's randomness came from, so it can be played again
+    seed: u64,
+    /// Snapshots from the depth milestones of the run
+    filmstrip: Filmstrip,
+    /// The whole structure, as it was left
+    photo: Photo,
+    /// Whether the photo has been exported yet
+    photo_saved: bool,
+    /// The recording of the run, if it was recorded
+    replay: Option<Replay>,
+    /// Whether the replay has been saved yet
+    replay_saved: bool,
+    /// Whether the run log has been exported yet
+    log_saved: bool,
+    /// Whether the run has been put on the high score table yet
+    submitted: bool,
+    /// Where the run landed on the high score table, if it made it on
+    high_score_rank: Option<usize>,
 }
 
 impl ModeDenoument {
-    pub fn new(score: f32) -> Self {
-        Self { score }
+    pub fn new(
+        result: RunResult,
+        config: RunConfig,
+        seed: u64,
+        filmstrip: Filmstrip,
+        photo: Photo,
+        replay: Option<Replay>,
+    ) -> Self {
+        Self {
+            result,
+            config,
+            seed,
+            filmstrip,
+            photo,
+            photo_saved: false,
+            replay,
+            replay_saved: false,
+            log_saved: false,
+            submitted: false,
+            high_score_rank: None,
+        }
+    }
+
+    fn save_replay_button(&self) -> Button {
+        let label = if self.replay_saved {
+            "SAVED"
+        } else {
+            "SAVE REPLAY"
+        };
+        Button::new(210.0, 161.0, 68.0, 19.0, label)
+    }
+
+    fn export_log_button(&self) -> Button {
+        let label = if self.log_saved {
+            "LOG SAVED"
+        } else {
+            "EXPORT LOG"
+        };
+        Button::new(210.0, 137.0, 68.0, 19.0, label)
     }
 
-    pub fn update(&mut self, globals: &mut Globals) -> Transition {
+    /// How much the photo gets shrunk to fit, and where its corner goes
+    fn photo_placement(&self) -> (f32, Vec2) {
+        let scale = (PHOTO_AREA.w / self.photo.width()).min(PHOTO_AREA.h / self.photo.height());
+        let x = PHOTO_AREA.x + (PHOTO_AREA.w - self.photo.width() * scale) / 2.0;
+        (scale, vec2(x.round(), PHOTO_AREA.y))
+    }
+
+    /// Just under the photo, whatever shape it is
+    fn photo_bottom(&self) -> f32 {
+        let (scale, corner) = self.photo_placement();
+        (corner.y + self.photo.height() * scale).round()
+    }
+
+    fn export_photo_button(&self) -> Button {
+        let label = if self.photo_saved {
+            "PNG SAVED"
+        } else {
+            "EXPORT PNG"
+        };
+        Button::new(
+            PHOTO_AREA.x,
+            self.photo_bottom() + 20.0,
+            PHOTO_AREA.w,
+            19.0,
+            label,
+        )
+    }
+
+    /// The photo, with the numbers that go with it under it
+    fn draw_photo(&self, globals: &Globals) {
+        let (scale, corner) = self.photo_placement();
+        self.photo.draw(corner.x, corner.y, scale);
+        draw_rectangle_lines(
+            corner.x - 1.0,
+            corner.y - 1.0,
+            self.photo.width() * scale + 2.0,
+            self.photo.height() * scale + 2.0,
+            1.0,
+            hexcolor(ui::BACKGROUND),
+        );
+
+        let bottom = self.photo_bottom();
+        let lines = [
+            format!("PLACED {}", self.result.blocks_placed),
+            format!("COLLAPSE {}", self.result.biggest_collapse),
+        ];
+        for (idx, line) in lines.iter().enumerate() {
+            drawutils::draw_text_px(
+                line,
+                PHOTO_AREA.x,
+                bottom + 4.0 + idx as f32 * 8.0,
+                hexcolor(ui::BACKGROUND),
+                globals,
+            );
+        }
+        self.export_photo_button().draw(self.photo_saved, globals);
+    }
+}
+
+impl Gamemode for ModeDenoument {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        if !self.submitted {
+            self.submitted = true;
+            self.high_score_rank = globals
+                .high_scores
+                .submit(&self.result, &globals.profile.name);
+            if self.high_score_rank.is_some() {
+                globals.high_scores.save();
+            }
+            // a new best is the one to race against from now on
+            if let (Some(0), Some(replay)) = (self.high_score_rank, &self.replay) {
+                replay.keep_as_ghost();
+            }
+            if let Some(day) = self.config.daily {
+                if globals.daily_bests.submit(day, &self.result) {
+                    globals.daily_bests.save();
+                }
+            }
+            if let (Some(level), true) = (self.config.level, self.result.objective_met) {
+                let profile = &mut globals.profile;
+                if level + 1 > profile.levels_cleared {
+                    profile.levels_cleared = level + 1;
+                    profile.save();
+                }
+            }
+        }
+
+        if !self.log_saved && self.export_log_button().clicked() {
+            let log = run_log::run_log(
+                &self.result,
+                &self.config,
+                &globals.profile.name,
+                self.seed,
+                self.replay
+                    .as_ref()
+                    .map_or(&[], |replay| replay.events.as_slice()),
+            );
+            run_log::export(log);
+            self.log_saved = true;
+            return Transition::None;
+        }
+        if !self.photo_saved && self.export_photo_button().clicked() {
+            self.photo.export();
+            self.photo_saved = true;
+            return Transition::None;
+        }
         let mouse = mouse_position_pixel().into();
+        if let (Some(replay), false) = (&self.replay, self.replay_saved) {
+            if self.save_replay_button().clicked() {
+                replay.export();
+                self.replay_saved = true;
+                return Transition::None;
+            }
+        }
         if is_mouse_button_pressed(MouseButton::Left) {
             if Rect::new(77.0, 137.0, 123.0, 19.0).contains(mouse) {
-                Transition::Swap(Gamemode::Playing(ModePlaying::new()))
+                // a daily run is only ever played with that day's seed
+                let again = if self.config.daily.is_some() {
+                    ModePlaying::with_seed(self.config.clone(), self.seed)
+                } else {
+                    ModePlaying::new(self.config.clone())
+                };
+                Transition::Swap(Box::new(again))
             } else if Rect::new(77.0, 161.0, 51.0, 19.0).contains(mouse) {
-                Transition::Swap(Gamemode::Title(ModeTitle::new()))
+                Transition::Swap(Box::new(ModeTitle::new()))
             } else {
                 Transition::None
             }
@@ -32,9 +225,77 @@ impl ModeDenoument {
         }
     }
 
-    pub fn draw(&self, globals: &Globals) {
+    fn draw(&self, globals: &Globals) {
         clear_background(WHITE);
         draw_texture(globals.assets.textures.denoument, 0.0, 0.0, WHITE);
-        drawutils::draw_number(self.score.round() as i32, 177.0, 92.0, globals);
+        drawutils::draw_number(self.result.depth.round() as i32, 177.0, 92.0, globals);
+        let mut lines = vec![
+            format!("BONUS POINTS: {}", self.result.points),
+            format!(
+                "EFFICIENCY: {:.2} DEPTH PER BLOCK",
+                self.result.efficiency()
+            ),
+        ];
+        if self.result.tower_height > 0 {
+            lines.push(format!(
+                "TOWER: {} HIGH, {} TOTAL",
+                self.result.tower_height,
+                self.result.total().round() as i32
+            ));
+        }
+        if self.result.artifacts > 0 {
+            lines.push(format!("ARTIFACTS FOUND: {}", self.result.artifacts));
+        }
+        if let (Some(objective), true) = (self.config.objective, self.result.objective_met) {
+            lines.push(format!("GOAL MET: {}", objective.describe()));
+        } else if let Some(objective) = self.config.objective {
+            lines.push(format!("GOAL MISSED: {}", objective.describe()));
+        }
+        if self.result.assisted {
+            lines.push("ASSISTED RUN".to_owned());
+        }
+        if let Some(blueprint) = &self.result.blueprint {
+            let seconds = blueprint.frames / 60;
+            lines.push(format!(
+                "{}: {}% IN {}:{:02}, SCORE {}",
+                blueprint.name,
+                (blueprint.accuracy * 100.0).round() as i32,
+                seconds / 60,
+                seconds % 60,
+                blueprint.score
+            ));
+        }
+        for (idx, line) in lines.iter().enumerate() {
+            drawutils::draw_text_px(
+                line,
+                78.0,
+                108.0 + idx as f32 * 8.0,
+                hexcolor(ui::BACKGROUND),
+                globals,
+            );
+        }
+        self.draw_photo(globals);
+        if !self.filmstrip.is_empty() {
+            self.filmstrip.draw(192.0, globals);
+        }
+        if self.replay.is_some() {
+            self.save_replay_button().draw(self.replay_saved, globals);
+        }
+        self.export_log_button().draw(self.log_saved, globals);
+        drawutils::draw_text_px(
+            &format!("SEED {}", format_seed(self.seed)),
+            4.0,
+            HEIGHT - 9.0,
+            hexcolor(ui::DIM),
+            globals,
+        );
+        if self.result.blueprint.is_none() && self.config.level.is_none() {
+            let table = globals.high_scores.table(self.result.game_type);
+            ui::draw_high_scores(250.0, 20.0, &table, self.high_score_rank, globals);
+        }
+    }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Denoument)
     }
 }
@@ -0,0 +1,81 @@
+//! Writing up how a run went, to share or keep.
+
+use crate::{modes::playing::RunEvent, save, score::RunResult, RunConfig};
+
+use std::fmt::Write;
+
+/// Run logs are saved under this prefix
+const LOG_DIR: &str = "logs/";
+
+/// Everything there is to know about a finished run, as Markdown.
+///
+/// With the seed, anyone can play the same run; with the replay too,
+/// they can check the result for themselves.
+pub fn run_log(
+    result: &RunResult,
+    config: &RunConfig,
+    player: &str,
+    seed: u64,
+    events: &[RunEvent],
+) -> String {
+    // writing to a String can't fail, so the results are ignored throughout
+    let mut out = String::new();
+    let _ = writeln!(out, "# {} run by {}", config.game_type.name(), player);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "| | |");
+    let _ = writeln!(out, "|---|---|");
+    let _ = writeln!(out, "| Depth | {:.2} |", result.depth);
+    if result.tower_height > 0 {
+        let _ = writeln!(out, "| Tower height | {} |", result.tower_height);
+        let _ = writeln!(out, "| Total | {:.2} |", result.total());
+    }
+    let _ = writeln!(out, "| Bonus points | {} |", result.points);
+    let _ = writeln!(out, "| Blocks placed | {} |", result.blocks_placed);
+    let _ = writeln!(
+        out,
+        "| Efficiency | {:.3} depth per block |",
+        result.efficiency()
+    );
+    let _ = writeln!(
+        out,
+        "| Assisted | {} |",
+        if result.assisted { "yes" } else { "no" }
+    );
+    if let Some(blueprint) = &result.blueprint {
+        let _ = writeln!(out, "| Blueprint | {} |", blueprint.name);
+        let _ = writeln!(out, "| Accuracy | {:.1}% |", blueprint.accuracy * 100.0);
+        let _ = writeln!(out, "| Time | {} |", timestamp(blueprint.frames));
+        let _ = writeln!(out, "| Blueprint score | {} |", blueprint.score);
+    }
+    let _ = writeln!(out, "| Seed | {} |", super::format_seed(seed));
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "## Events");
+    let _ = writeln!(out);
+    if events.is_empty() {
+        let _ = writeln!(out, "Nothing was recorded for this run.");
+    }
+    for event in events {
+        let _ = writeln!(
+            out,
+            "- `{}` {}",
+            timestamp(event.frame),
+            event.kind.describe().to_lowercase()
+        );
+    }
+    out
+}
+
+/// Save a run log with a fresh name, and return the name.
+pub fn export(log: String) -> String {
+    let timestamp = macroquad::miniquad::date::now() as u64;
+    let name = format!("{}{}.md", LOG_DIR, timestamp);
+    save::store_text(&name, log);
+    name
+}
+
+/// Minutes and seconds into the run, at 60 frames a second
+fn timestamp(frames: u64) -> String {
+    let seconds = frames / 60;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
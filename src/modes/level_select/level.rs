@@ -0,0 +1,66 @@
+//! Campaign levels, as written in the files in `assets/levels`.
+
+use crate::{BlockKind, GameType, Objective, RunConfig};
+
+use serde::Deserialize;
+
+/// Narrowest a level's chasm can be
+const MIN_CHASM_WIDTH: isize = 3;
+/// Widest a level's chasm can be. Any wider and the conveyor covers the far wall.
+const MAX_CHASM_WIDTH: isize = 9;
+
+/// One handmade level.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Level {
+    pub name: String,
+    /// How many columns across the chasm is. Must be odd.
+    pub chasm_width: isize,
+    /// How many blocks come down the conveyor after the first batch
+    pub blocks: usize,
+    /// What kinds of block the conveyor brings, besides the odd anchor.
+    /// Leave it out for the usual mix.
+    #[serde(default)]
+    pub kinds: Vec<BlockKind>,
+    /// Multiplies how often blocks wear out
+    #[serde(default = "normal_decay")]
+    pub decay: f64,
+    /// What it takes to clear the level
+    pub objective: Objective,
+}
+
+fn normal_decay() -> f64 {
+    1.0
+}
+
+impl Level {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let level: Level = toml::from_str(text).map_err(|err| err.to_string())?;
+        if level.chasm_width % 2 == 0
+            || !(MIN_CHASM_WIDTH..=MAX_CHASM_WIDTH).contains(&level.chasm_width)
+        {
+            return Err(format!(
+                "chasm_width must be odd and from {} to {}, not {}",
+                MIN_CHASM_WIDTH, MAX_CHASM_WIDTH, level.chasm_width
+            ));
+        }
+        if level.kinds.contains(&BlockKind::Anchor) {
+            return Err(
+                "anchors come down the conveyor anyways, so leave them out of kinds".to_owned(),
+            );
+        }
+        Ok(level)
+    }
+
+    /// The rules to play this level under; it's level number `idx`, counting from 0.
+    pub fn config(&self, idx: usize) -> RunConfig {
+        RunConfig {
+            chasm_width: self.chasm_width,
+            block_allowance: Some(self.blocks),
+            block_kinds: self.kinds.clone(),
+            decay_multiplier: self.decay,
+            objective: Some(self.objective),
+            level: Some(idx),
+            ..RunConfig::new(GameType::Campaign)
+        }
+    }
+}
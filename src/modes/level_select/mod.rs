@@ -0,0 +1,117 @@
+use crate::{
+    drawutils::hexcolor,
+    ui::{self, Button},
+    Gamemode, Globals, ModePlaying, ModeSelect, Transition, WIDTH,
+};
+
+use macroquad::prelude::*;
+
+mod level;
+pub use level::Level;
+
+const GRID_COLUMNS: usize = 4;
+const CELL_WIDTH: f32 = 40.0;
+const CELL_HEIGHT: f32 = 24.0;
+const CELL_GAP: f32 = 8.0;
+const GRID_TOP: f32 = 48.0;
+
+/// Pick a campaign level to play.
+///
+/// Each level unlocks once the one before it has been cleared.
+/// Cleared ones get a star.
+#[derive(Clone)]
+pub struct ModeLevelSelect {
+    back: Button,
+}
+
+impl ModeLevelSelect {
+    pub fn new() -> Self {
+        Self {
+            back: Button::new(128.0, 210.0, 64.0, 16.0, "BACK"),
+        }
+    }
+}
+
+impl Gamemode for ModeLevelSelect {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        if self.back.clicked() || is_key_pressed(KeyCode::Escape) {
+            return Transition::Swap(Box::new(ModeSelect::new()));
+        }
+        let unlocked = globals.profile.levels_cleared;
+        for (idx, level) in globals.assets.levels.iter().enumerate() {
+            if idx <= unlocked && level_button(idx).clicked() {
+                let config = level.config(idx).with_assists(&globals.settings.assist);
+                return Transition::Swap(Box::new(ModePlaying::new(config)));
+            }
+        }
+        Transition::None
+    }
+
+    fn draw(&self, globals: &Globals) {
+        clear_background(hexcolor(ui::BACKGROUND));
+        ui::draw_centered_text("CAMPAIGN", 24.0, hexcolor(ui::FOREGROUND), globals);
+
+        let cleared = globals.profile.levels_cleared;
+        let mut hovered = None;
+        for idx in 0..globals.assets.levels.len() {
+            let mut button = level_button(idx);
+            if idx > cleared {
+                button.label = "--".to_owned();
+            } else {
+                if idx < cleared {
+                    button.label.push('*');
+                }
+                if button.hovered() {
+                    hovered = Some(idx);
+                }
+            }
+            button.draw(false, globals);
+        }
+
+        match hovered {
+            Some(idx) => {
+                let level = &globals.assets.levels[idx];
+                let lines = [
+                    level.name.clone(),
+                    format!("GOAL: {}", level.objective.describe()),
+                    format!(
+                        "{} BLOCKS, {} COLUMNS ACROSS",
+                        level.blocks, level.chasm_width
+                    ),
+                ];
+                for (line_idx, line) in lines.iter().enumerate() {
+                    ui::draw_centered_text(
+                        line,
+                        160.0 + line_idx as f32 * 9.0,
+                        hexcolor(ui::FOREGROUND),
+                        globals,
+                    );
+                }
+            }
+            None => {
+                let text = format!(
+                    "{}/{} CLEARED",
+                    cleared.min(globals.assets.levels.len()),
+                    globals.assets.levels.len()
+                );
+                ui::draw_centered_text(&text, 160.0, hexcolor(ui::DIM), globals);
+            }
+        }
+        self.back.draw(false, globals);
+    }
+}
+
+/// The button for the level at this index, laid out in a grid
+fn level_button(idx: usize) -> Button {
+    let grid_width = GRID_COLUMNS as f32 * (CELL_WIDTH + CELL_GAP) - CELL_GAP;
+    let left = ((WIDTH - grid_width) / 2.0).round();
+    let col = (idx % GRID_COLUMNS) as f32;
+    let row = (idx / GRID_COLUMNS) as f32;
+    Button::new(
+        left + col * (CELL_WIDTH + CELL_GAP),
+        GRID_TOP + row * (CELL_HEIGHT + CELL_GAP),
+        CELL_WIDTH,
+        CELL_HEIGHT,
+        (idx + 1).to_string(),
+    )
+}
@@ -0,0 +1,55 @@
+use crate::{
+    assets::AssetLoader, drawutils::hexcolor, ui, Gamemode, Globals, Transition, HEIGHT, WIDTH,
+};
+
+use super::ModeLogo;
+
+use macroquad::prelude::*;
+
+const BAR_WIDTH: f32 = 160.0;
+const BAR_HEIGHT: f32 = 8.0;
+
+/// Shows how far along the assets are while they load.
+///
+/// The font is one of the assets, so this can't draw any text.
+pub struct ModeLoading {
+    loader: AssetLoader,
+}
+
+impl ModeLoading {
+    // shut up clippy
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            loader: AssetLoader::start(),
+        }
+    }
+}
+
+impl Gamemode for ModeLoading {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        match self.loader.take() {
+            Some(assets) => {
+                globals.assets.set(assets);
+                Transition::Swap(Box::new(ModeLogo::new()))
+            }
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self, _globals: &Globals) {
+        clear_background(hexcolor(ui::BACKGROUND));
+
+        let x = ((WIDTH - BAR_WIDTH) / 2.0).round();
+        let y = ((HEIGHT - BAR_HEIGHT) / 2.0).round();
+        draw_rectangle_lines(x, y, BAR_WIDTH, BAR_HEIGHT, 1.0, hexcolor(ui::FOREGROUND));
+        let filled = ((BAR_WIDTH - 4.0) * self.loader.progress()).round();
+        draw_rectangle(
+            x + 2.0,
+            y + 2.0,
+            filled,
+            BAR_HEIGHT - 4.0,
+            hexcolor(ui::FOREGROUND),
+        );
+    }
+}
@@ -1,13 +1,10 @@
-use macroquad::{
-    audio::stop_sound,
-    prelude::{is_mouse_button_down, MouseButton},
-};
+use macroquad::prelude::{is_mouse_button_down, MouseButton};
 
-use crate::{drawutils, Gamemode, Globals, Transition, HEIGHT, WIDTH};
+use crate::{drawutils, music::Track, Gamemode, Globals, Transition, HEIGHT, WIDTH};
 
 use std::f32::consts::TAU;
 
-use super::{ModePlaying, ModeTitle};
+use super::{ModeNameEntry, ModePlaying, ModeTitle, NameEntryPurpose};
 
 const ROTATION_SPEED: f32 = 0.03;
 /// Number of "blades" of the starburst
@@ -27,25 +24,28 @@ impl ModeLogo {
     pub fn new() -> Self {
         Self { frames_ran: 0 }
     }
+}
 
-    pub fn update(&mut self, globals: &mut Globals) -> Transition {
+impl Gamemode for ModeLogo {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
         let trans = if self.frames_ran < 300 && !is_mouse_button_down(MouseButton::Left) {
             Transition::None
+        } else if globals.profile.first_launch {
+            // Show new players the ropes before dumping them on the title
+            Transition::Swap(Box::new(ModeNameEntry::new(
+                NameEntryPurpose::FirstLaunch,
+                globals,
+            )))
         } else {
-            stop_sound(globals.assets.sounds.title_jingle);
-            Transition::Swap(Gamemode::Title(ModeTitle::new()))
+            Transition::Swap(Box::new(ModeTitle::new()))
         };
 
         self.frames_ran += 1;
         trans
     }
 
-    pub fn draw(&self, globals: &Globals) {
-        use macroquad::{audio::*, prelude::*};
-
-        if self.frames_ran == 0 {
-            play_sound_once(globals.assets.sounds.title_jingle);
-        }
+    fn draw(&self, globals: &Globals) {
+        use macroquad::prelude::*;
 
         let bg_color = if self.frames_ran < 40 {
             drawutils::hexcolor(0x21181bff)
@@ -89,4 +89,8 @@ impl ModeLogo {
             },
         );
     }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Title)
+    }
 }
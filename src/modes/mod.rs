@@ -1,10 +1,39 @@
+mod loading;
+pub use loading::ModeLoading;
 mod logo;
 pub use logo::ModeLogo;
 mod playing;
-pub use playing::ModePlaying;
+#[cfg(not(target_arch = "wasm32"))]
+pub use playing::StressRun;
+pub use playing::{
+    poll_pointer_events, BlockKind, Blueprint, Filmstrip, GameType, InputFrame, ModePlaying,
+    Objective, Photo, Replay, RunConfig, RunSnapshot, SavePolicy,
+};
 mod title;
 pub use title::ModeTitle;
 mod rules;
 pub use rules::ModeRules;
+mod tutorial;
+pub use tutorial::ModeTutorial;
 mod denoument;
 pub use denoument::ModeDenoument;
+mod name_entry;
+pub use name_entry::{ModeNameEntry, NameEntryPurpose};
+mod assist;
+pub use assist::ModeAssist;
+mod options;
+pub use options::ModeOptions;
+mod stats;
+pub use stats::ModeStats;
+mod replay;
+pub use replay::ModeReplayPicker;
+mod mode_select;
+pub use mode_select::ModeSelect;
+mod level_select;
+pub use level_select::{Level, ModeLevelSelect};
+mod daily;
+pub use daily::ModeDaily;
+mod versus_result;
+pub use versus_result::ModeVersusResult;
+mod versus;
+pub use versus::ModeVersus;
@@ -0,0 +1,141 @@
+use crate::{
+    drawutils::{self, hexcolor},
+    ui::{self, Button},
+    GameType, Gamemode, Globals, ModeLevelSelect, ModePlaying, ModeTitle, ModeVersus, RunConfig,
+    Transition,
+};
+
+use macroquad::prelude::*;
+
+/// The modes on offer besides the classic one, and what each is about
+const MODES: [(GameType, &str); 12] = [
+    (GameType::Zen, "NO DECAY AND ENDLESS BLOCKS, BUT A COOLDOWN"),
+    (GameType::Hardcore, "AUTOSAVED, AND NO TAKE-BACKS"),
+    (GameType::Practice, "ENDLESS BLOCKS. PAUSE WITH P TO REWIND"),
+    (GameType::Coop, "P2 USES ARROWS, Q/E, Z/X, SPACE AND C"),
+    (GameType::Versus, "TAKE TURNS. DON'T MAKE IT FALL"),
+    (GameType::Race, "SPLIT SCREEN. P2 PLAYS WITH THE KEYBOARD"),
+    (
+        GameType::ScoreAttack,
+        "ONLY 50 BLOCKS. HOW DEEP CAN YOU GO?",
+    ),
+    (GameType::Cavern, "BUILD UP FROM THE FLOOR TO THE CEILING"),
+    (GameType::Shaft, "BUILD SIDEWAYS. HOW FAR CAN YOU REACH?"),
+    (GameType::Tower, "BRIDGE THE CHASM, THEN BUILD UP TOO"),
+    (GameType::Flooded, "WATER WEARS BLOCKS AND FLOATS SCAFFOLDS"),
+    (GameType::Blueprint, "BUILD WHAT'S OUTLINED, QUICK"),
+];
+
+/// How deep practice runs can start, already built down to
+const PRACTICE_DEPTHS: [isize; 5] = [0, 10, 20, 30, 40];
+
+/// Pick one of the other ways to play.
+#[derive(Clone)]
+pub struct ModeSelect {
+    back: Button,
+    campaign: Button,
+    /// Index of the blueprint to build in blueprint challenges
+    blueprint: usize,
+    /// Index in `PRACTICE_DEPTHS` of how deep practice runs start
+    practice_depth: usize,
+}
+
+impl ModeSelect {
+    pub fn new() -> Self {
+        Self {
+            back: Button::new(128.0, 210.0, 64.0, 16.0, "BACK"),
+            campaign: Button::new(216.0, 210.0, 72.0, 16.0, "CAMPAIGN"),
+            blueprint: 0,
+            practice_depth: 0,
+        }
+    }
+
+    /// Shows which blueprint is picked; click it for the next one.
+    fn next_blueprint_button(&self, globals: &Globals) -> Button {
+        let name = globals
+            .assets
+            .blueprints
+            .get(self.blueprint)
+            .map_or("NONE", |blueprint| blueprint.name.as_str());
+        let label = format!("{} >", name);
+        let row = mode_button(MODES.len() - 1, GameType::Blueprint).rect;
+        let width = drawutils::text_width_px(&label) + 8.0;
+        Button::new(row.x + 8.0, row.bottom() + 2.0, width, 11.0, label)
+    }
+
+    /// Shows how deep practice runs start; click it to go deeper.
+    /// It sits at the end of the practice row, past the blurb.
+    fn practice_depth_button(&self) -> Button {
+        let idx = MODES
+            .iter()
+            .position(|(game_type, _)| *game_type == GameType::Practice)
+            .unwrap();
+        let row = mode_button(idx, GameType::Practice).rect;
+        let label = format!("DEPTH {} >", PRACTICE_DEPTHS[self.practice_depth]);
+        let x = row.right() + 8.0 + drawutils::text_width_px(MODES[idx].1) + 8.0;
+        let width = drawutils::text_width_px(&label) + 8.0;
+        Button::new(x, row.y + 2.0, width, 11.0, label)
+    }
+}
+
+impl Gamemode for ModeSelect {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        if self.back.clicked() || is_key_pressed(KeyCode::Escape) {
+            return Transition::Swap(Box::new(ModeTitle::new()));
+        }
+        if self.campaign.clicked() {
+            return Transition::Swap(Box::new(ModeLevelSelect::new()));
+        }
+        let blueprints = &globals.assets.blueprints;
+        if self.next_blueprint_button(globals).clicked() && !blueprints.is_empty() {
+            self.blueprint = (self.blueprint + 1) % blueprints.len();
+        }
+        if self.practice_depth_button().clicked() {
+            self.practice_depth = (self.practice_depth + 1) % PRACTICE_DEPTHS.len();
+        }
+        for (idx, (game_type, _)) in MODES.iter().enumerate() {
+            if mode_button(idx, *game_type).clicked() {
+                let mut config = RunConfig::new(*game_type).with_assists(&globals.settings.assist);
+                if *game_type == GameType::Blueprint {
+                    match blueprints.get(self.blueprint) {
+                        Some(blueprint) => config = config.with_blueprint(blueprint.clone()),
+                        None => continue,
+                    }
+                }
+                if *game_type == GameType::Practice {
+                    config.start_depth = PRACTICE_DEPTHS[self.practice_depth];
+                }
+                if *game_type == GameType::Race {
+                    return Transition::Swap(Box::new(ModeVersus::new(config)));
+                }
+                return Transition::Swap(Box::new(ModePlaying::new(config)));
+            }
+        }
+        Transition::None
+    }
+
+    fn draw(&self, globals: &Globals) {
+        clear_background(hexcolor(ui::BACKGROUND));
+        ui::draw_centered_text("GAME MODES", 24.0, hexcolor(ui::FOREGROUND), globals);
+
+        for (idx, (game_type, blurb)) in MODES.iter().enumerate() {
+            let button = mode_button(idx, *game_type);
+            button.draw(false, globals);
+            drawutils::draw_text_px(
+                blurb,
+                button.rect.right() + 8.0,
+                button.rect.y + 5.0,
+                hexcolor(ui::DIM),
+                globals,
+            );
+        }
+        self.next_blueprint_button(globals).draw(false, globals);
+        self.practice_depth_button().draw(false, globals);
+        self.back.draw(false, globals);
+        self.campaign.draw(false, globals);
+    }
+}
+
+fn mode_button(idx: usize, game_type: GameType) -> Button {
+    Button::new(16.0, 32.0 + idx as f32 * 14.0, 64.0, 13.0, game_type.name())
+}
@@ -0,0 +1,294 @@
+use crate::{
+    drawutils::{self, hexcolor},
+    ui::{self, Button},
+    GameType, Gamemode, Globals, ModePlaying, ModeTitle, ModeTutorial, RunConfig, Transition,
+    WIDTH,
+};
+
+use macroquad::prelude::*;
+
+const MAX_NAME_LEN: usize = 12;
+/// Seeds are typed in hex, and a u64 is 16 hex digits
+const MAX_SEED_LEN: usize = 16;
+
+const KEY_ROWS: [&str; 3] = ["ABCDEFGHIJ", "KLMNOPQRST", "UVWXYZ-.'!"];
+const SEED_KEY_ROWS: [&str; 2] = ["0123456789", "ABCDEF"];
+const KEY_SIZE: f32 = 18.0;
+const KEY_GAP: f32 = 2.0;
+const KEYBOARD_Y: f32 = 96.0;
+
+/// What the entered name is for
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NameEntryPurpose {
+    /// Naming a brand new profile, as part of onboarding
+    FirstLaunch,
+    /// Renaming the profile
+    ProfileName,
+    /// Picking the seed for a classic run, to play one again
+    Seed,
+}
+
+impl NameEntryPurpose {
+    fn key_rows(self) -> &'static [&'static str] {
+        match self {
+            NameEntryPurpose::Seed => &SEED_KEY_ROWS,
+            _ => &KEY_ROWS,
+        }
+    }
+
+    fn max_len(self) -> usize {
+        match self {
+            NameEntryPurpose::Seed => MAX_SEED_LEN,
+            _ => MAX_NAME_LEN,
+        }
+    }
+
+    /// Whether this can go in the text
+    fn allows(self, c: char) -> bool {
+        match self {
+            NameEntryPurpose::Seed => c.is_ascii_hexdigit(),
+            _ => c.is_ascii_graphic() || c == ' ',
+        }
+    }
+}
+
+/// Typing in some text.
+///
+/// You can use the keyboard directly, or the on-screen keyboard
+/// with the mouse, touchscreen, or arrow keys.
+#[derive(Clone)]
+pub struct ModeNameEntry {
+    purpose: NameEntryPurpose,
+    text: String,
+
+    keys: Vec<(Button, KeyAction)>,
+    /// Key on the on-screen keyboard picked with the arrow keys, if any
+    selected: Option<usize>,
+
+    frames_ran: u64,
+}
+
+#[derive(Clone, Copy)]
+enum KeyAction {
+    Type(char),
+    Delete,
+    Confirm,
+    /// Leave without changing anything
+    Cancel,
+}
+
+impl ModeNameEntry {
+    pub fn new(purpose: NameEntryPurpose, globals: &Globals) -> Self {
+        let key_rows = purpose.key_rows();
+        let row_width = key_rows[0].len() as f32 * (KEY_SIZE + KEY_GAP) - KEY_GAP;
+        let left = ((WIDTH - row_width) / 2.0).round();
+
+        let mut keys = Vec::new();
+        for (row_idx, row) in key_rows.iter().enumerate() {
+            for (col_idx, c) in row.chars().enumerate() {
+                let x = left + col_idx as f32 * (KEY_SIZE + KEY_GAP);
+                let y = KEYBOARD_Y + row_idx as f32 * (KEY_SIZE + KEY_GAP);
+                let button = Button::new(x, y, KEY_SIZE, KEY_SIZE, c.to_string());
+                keys.push((button, KeyAction::Type(c)));
+            }
+        }
+        // And the bottom row of wide keys
+        let y = KEYBOARD_Y + key_rows.len() as f32 * (KEY_SIZE + KEY_GAP);
+        let third = ((row_width - KEY_GAP * 2.0) / 3.0).floor();
+        // seeds don't have spaces, so that key goes back instead
+        let first_special = match purpose {
+            NameEntryPurpose::Seed => ("BACK", KeyAction::Cancel),
+            _ => ("SPACE", KeyAction::Type(' ')),
+        };
+        let specials = [
+            first_special,
+            ("DEL", KeyAction::Delete),
+            ("OK", KeyAction::Confirm),
+        ];
+        for (idx, (label, action)) in specials.iter().enumerate() {
+            let x = left + idx as f32 * (third + KEY_GAP);
+            keys.push((Button::new(x, y, third, KEY_SIZE, *label), *action));
+        }
+
+        let text = match purpose {
+            NameEntryPurpose::FirstLaunch | NameEntryPurpose::Seed => String::new(),
+            NameEntryPurpose::ProfileName => globals.profile.name.clone(),
+        };
+
+        Self {
+            purpose,
+            text,
+            keys,
+            selected: None,
+            frames_ran: 0,
+        }
+    }
+
+    fn type_char(&mut self, c: char) {
+        if self.text.len() < self.purpose.max_len() {
+            self.text.push(c.to_ascii_uppercase());
+        }
+    }
+
+    /// Find the next key over from this one in the given direction.
+    fn neighbor(&self, idx: usize, dx: f32, dy: f32) -> usize {
+        let center = |rect: Rect| rect.point() + rect.size() / 2.0;
+        let here = center(self.keys[idx].0.rect);
+        self.keys
+            .iter()
+            .enumerate()
+            .filter_map(|(other_idx, (button, _))| {
+                let delta = center(button.rect) - here;
+                // Only look at keys in that direction
+                let along = delta.x * dx + delta.y * dy;
+                if along <= 0.0 {
+                    return None;
+                }
+                let across = (delta.x * dy).abs() + (delta.y * dx).abs();
+                Some((other_idx, along + across * 2.0))
+            })
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(other_idx, _)| other_idx)
+            .unwrap_or(idx)
+    }
+
+    fn confirm(&mut self, globals: &mut Globals) -> Transition {
+        if self.purpose == NameEntryPurpose::Seed {
+            // only hex digits get typed, and not too many of them, so this can't fail
+            let seed = u64::from_str_radix(&self.text, 16).unwrap_or_default();
+            let config = RunConfig::new(GameType::Classic).with_assists(&globals.settings.assist);
+            return Transition::Swap(Box::new(ModePlaying::with_seed(config, seed)));
+        }
+
+        globals.profile.name = self.text.trim().to_owned();
+        globals.profile.save();
+        match self.purpose {
+            NameEntryPurpose::FirstLaunch => {
+                Transition::Swap(Box::new(ModeTutorial::first_launch()))
+            }
+            NameEntryPurpose::ProfileName => Transition::Pop,
+            NameEntryPurpose::Seed => unreachable!(),
+        }
+    }
+}
+
+impl Gamemode for ModeNameEntry {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        // Macroquad never clears this queue, so there might be stale characters
+        // from way back when.
+        let mut typed = Vec::new();
+        while let Some(c) = get_char_pressed() {
+            typed.push(c);
+        }
+        self.frames_ran += 1;
+        if self.frames_ran == 1 {
+            return Transition::None;
+        }
+
+        // the queue pops the newest first
+        for c in typed.into_iter().rev() {
+            if self.purpose.allows(c) {
+                self.selected = None;
+                self.type_char(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.text.pop();
+        }
+
+        for &(key, dx, dy) in &[
+            (KeyCode::Left, -1.0, 0.0),
+            (KeyCode::Right, 1.0, 0.0),
+            (KeyCode::Up, 0.0, -1.0),
+            (KeyCode::Down, 0.0, 1.0),
+        ] {
+            if is_key_pressed(key) {
+                self.selected = Some(match self.selected {
+                    Some(idx) => self.neighbor(idx, dx, dy),
+                    None => 0,
+                });
+            }
+        }
+
+        let mut action = None;
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            action = Some(match self.selected {
+                Some(idx) => self.keys[idx].1,
+                None => KeyAction::Confirm,
+            });
+        }
+        if is_key_pressed(KeyCode::Escape) && self.purpose == NameEntryPurpose::Seed {
+            action = Some(KeyAction::Cancel);
+        }
+        if let Some((_, clicked)) = self.keys.iter().find(|(button, _)| button.clicked()) {
+            action = Some(*clicked);
+        }
+
+        match action {
+            Some(KeyAction::Type(c)) => self.type_char(c),
+            Some(KeyAction::Delete) => {
+                self.text.pop();
+            }
+            Some(KeyAction::Confirm) if !self.text.trim().is_empty() => {
+                return self.confirm(globals);
+            }
+            Some(KeyAction::Cancel) => {
+                return Transition::Swap(Box::new(ModeTitle::new()));
+            }
+            _ => {}
+        }
+        Transition::None
+    }
+
+    fn draw(&self, globals: &Globals) {
+        clear_background(hexcolor(ui::BACKGROUND));
+
+        let prompt = match self.purpose {
+            NameEntryPurpose::FirstLaunch => "WELCOME TO THE SITE! WHAT'S YOUR NAME?",
+            NameEntryPurpose::ProfileName => "WHAT SHOULD WE CALL YOU?",
+            NameEntryPurpose::Seed => "WHICH SEED? IT'S ON THE END SCREEN OF A RUN.",
+        };
+        ui::draw_centered_text(prompt, 40.0, hexcolor(ui::FOREGROUND), globals);
+
+        // The text field
+        let max_len = self.purpose.max_len();
+        let field_width = max_len as f32 * 4.0 + 8.0;
+        let field_x = ((WIDTH - field_width) / 2.0).round();
+        draw_rectangle(field_x, 60.0, field_width, 13.0, hexcolor(ui::BUTTON));
+        draw_rectangle_lines(
+            field_x,
+            60.0,
+            field_width,
+            13.0,
+            1.0,
+            hexcolor(ui::FOREGROUND),
+        );
+        drawutils::draw_text_px(
+            &self.text,
+            field_x + 4.0,
+            64.0,
+            hexcolor(ui::FOREGROUND),
+            globals,
+        );
+        if self.text.len() < max_len && (self.frames_ran / 30) % 2 == 0 {
+            let cursor_x = field_x + 4.0 + self.text.len() as f32 * 4.0;
+            drawutils::draw_text_px("_", cursor_x, 65.0, hexcolor(ui::FOREGROUND), globals);
+        }
+
+        for (idx, (button, _)) in self.keys.iter().enumerate() {
+            button.draw(self.selected == Some(idx), globals);
+        }
+
+        ui::draw_centered_text(
+            "TYPE OR CLICK. ENTER TO FINISH.",
+            220.0,
+            hexcolor(ui::DIM),
+            globals,
+        );
+    }
+
+    /// Everything's for typing
+    fn claims_key(&self, _key: KeyCode) -> bool {
+        true
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    drawutils::{draw_text_px, hexcolor},
+    settings::{RenderStyle, Settings},
+    ui::{self, Button},
+    Gamemode, Globals, Transition,
+};
+
+use macroquad::prelude::*;
+
+/// How much each click changes a volume, in percent
+const VOLUME_STEP: u8 = 10;
+/// How much each click changes the scroll speed, in percent
+const SCROLL_SPEED_STEP: u8 = 25;
+/// Where the top row of options goes
+const FIRST_ROW_Y: f32 = 32.0;
+/// Space from the top of one row of options to the next
+const ROW_SPACING: f32 = 17.0;
+
+/// Where the top of this row of options goes
+fn row_y(row: usize) -> f32 {
+    FIRST_ROW_Y + row as f32 * ROW_SPACING
+}
+
+/// Change the settings that aren't assists.
+#[derive(Clone)]
+pub struct ModeOptions {
+    music_down: Button,
+    music_up: Button,
+    sfx_down: Button,
+    sfx_up: Button,
+    scroll_down: Button,
+    scroll_up: Button,
+    fullscreen: Button,
+    graphics: Button,
+    seasonal: Button,
+    reduced_motion: Button,
+    render_style: Button,
+    ghost: Button,
+    zoomed: Button,
+    back: Button,
+}
+
+impl ModeOptions {
+    pub fn new() -> Self {
+        Self {
+            music_down: Button::new(200.0, row_y(0), 16.0, 16.0, "-"),
+            music_up: Button::new(220.0, row_y(0), 16.0, 16.0, "+"),
+            sfx_down: Button::new(200.0, row_y(1), 16.0, 16.0, "-"),
+            sfx_up: Button::new(220.0, row_y(1), 16.0, 16.0, "+"),
+            scroll_down: Button::new(200.0, row_y(2), 16.0, 16.0, "-"),
+            scroll_up: Button::new(220.0, row_y(2), 16.0, 16.0, "+"),
+            fullscreen: Button::new(200.0, row_y(3), 36.0, 16.0, ""),
+            graphics: Button::new(200.0, row_y(4), 36.0, 16.0, ""),
+            seasonal: Button::new(200.0, row_y(5), 36.0, 16.0, ""),
+            reduced_motion: Button::new(200.0, row_y(6), 36.0, 16.0, ""),
+            render_style: Button::new(200.0, row_y(7), 36.0, 16.0, ""),
+            ghost: Button::new(200.0, row_y(8), 36.0, 16.0, ""),
+            zoomed: Button::new(200.0, row_y(9), 36.0, 16.0, ""),
+            back: Button::new(128.0, 218.0, 64.0, 16.0, "BACK"),
+        }
+    }
+}
+
+impl Gamemode for ModeOptions {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        let render_style = globals.render_style();
+        let settings = &mut globals.settings;
+        let mut changed = false;
+
+        if self.music_down.clicked() {
+            settings.music_volume = settings.music_volume.saturating_sub(VOLUME_STEP);
+            changed = true;
+        }
+        if self.music_up.clicked() {
+            settings.music_volume = (settings.music_volume + VOLUME_STEP).min(Settings::MAX_VOLUME);
+            changed = true;
+        }
+        if self.sfx_down.clicked() {
+            settings.sfx_volume = settings.sfx_volume.saturating_sub(VOLUME_STEP);
+            changed = true;
+        }
+        if self.sfx_up.clicked() {
+            settings.sfx_volume = (settings.sfx_volume + VOLUME_STEP).min(Settings::MAX_VOLUME);
+            changed = true;
+        }
+        if self.scroll_down.clicked() {
+            settings.scroll_speed = settings
+                .scroll_speed
+                .saturating_sub(SCROLL_SPEED_STEP)
+                .max(Settings::MIN_SCROLL_SPEED);
+            changed = true;
+        }
+        if self.scroll_up.clicked() {
+            settings.scroll_speed =
+                (settings.scroll_speed + SCROLL_SPEED_STEP).min(Settings::MAX_SCROLL_SPEED);
+            changed = true;
+        }
+        if self.fullscreen.clicked() {
+            settings.fullscreen = !settings.fullscreen;
+            // some platforms can't do this while running, so it's also set at launch
+            unsafe { get_internal_gl() }
+                .quad_context
+                .set_fullscreen(settings.fullscreen);
+            changed = true;
+        }
+        if self.graphics.clicked() {
+            settings.graphics = settings.graphics.next();
+            changed = true;
+        }
+        if self.seasonal.clicked() {
+            settings.seasonal = !settings.seasonal;
+            changed = true;
+        }
+        if self.reduced_motion.clicked() {
+            settings.reduced_motion = !settings.reduced_motion;
+            changed = true;
+        }
+        if self.render_style.clicked() {
+            let next = render_style.next();
+            settings.tint_connectors = next.tint_connectors;
+            settings.connector_glyphs = next.glyphs;
+            changed = true;
+        }
+        if self.ghost.clicked() {
+            settings.ghost = !settings.ghost;
+            changed = true;
+        }
+        if self.zoomed.clicked() {
+            settings.zoomed = !settings.zoomed;
+            changed = true;
+        }
+
+        if changed {
+            globals.settings.save();
+        }
+
+        if self.back.clicked() || is_key_pressed(KeyCode::Escape) {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn translucent(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, globals: &Globals) {
+        let fg = hexcolor(ui::FOREGROUND);
+        let settings = &globals.settings;
+
+        ui::draw_centered_text("OPTIONS", 22.0, fg, globals);
+
+        draw_text_px(
+            &format!("MUSIC VOLUME: {}%", settings.music_volume),
+            84.0,
+            row_y(0) + 6.0,
+            fg,
+            globals,
+        );
+        self.music_down.draw(false, globals);
+        self.music_up.draw(false, globals);
+
+        draw_text_px(
+            &format!("SFX VOLUME: {}%", settings.sfx_volume),
+            84.0,
+            row_y(1) + 6.0,
+            fg,
+            globals,
+        );
+        self.sfx_down.draw(false, globals);
+        self.sfx_up.draw(false, globals);
+
+        draw_text_px(
+            &format!("SCROLL SPEED: {}%", settings.scroll_speed),
+            84.0,
+            row_y(2) + 6.0,
+            fg,
+            globals,
+        );
+        self.scroll_down.draw(false, globals);
+        self.scroll_up.draw(false, globals);
+
+        draw_text_px("FULLSCREEN:", 84.0, row_y(3) + 6.0, fg, globals);
+        let fullscreen = Button {
+            label: if settings.fullscreen { "ON" } else { "OFF" }.to_owned(),
+            ..self.fullscreen.clone()
+        };
+        fullscreen.draw(settings.fullscreen, globals);
+
+        draw_text_px("GRAPHICS:", 84.0, row_y(4) + 6.0, fg, globals);
+        let graphics = Button {
+            label: settings.graphics.label().to_owned(),
+            ..self.graphics.clone()
+        };
+        graphics.draw(false, globals);
+
+        draw_text_px("SEASONAL EXTRAS:", 84.0, row_y(5) + 6.0, fg, globals);
+        let seasonal = Button {
+            label: if settings.seasonal { "ON" } else { "OFF" }.to_owned(),
+            ..self.seasonal.clone()
+        };
+        seasonal.draw(settings.seasonal, globals);
+
+        draw_text_px("REDUCED MOTION:", 84.0, row_y(6) + 6.0, fg, globals);
+        let reduced_motion = Button {
+            label: if settings.reduced_motion { "ON" } else { "OFF" }.to_owned(),
+            ..self.reduced_motion.clone()
+        };
+        reduced_motion.draw(settings.reduced_motion, globals);
+
+        draw_text_px("CONNECTOR HINTS:", 84.0, row_y(7) + 6.0, fg, globals);
+        let render_style = globals.render_style();
+        let render_style_button = Button {
+            label: render_style.label().to_owned(),
+            ..self.render_style.clone()
+        };
+        render_style_button.draw(render_style != RenderStyle::default(), globals);
+
+        draw_text_px("GHOST OF BEST RUN:", 84.0, row_y(8) + 6.0, fg, globals);
+        let ghost = Button {
+            label: if settings.ghost { "ON" } else { "OFF" }.to_owned(),
+            ..self.ghost.clone()
+        };
+        ghost.draw(settings.ghost, globals);
+
+        draw_text_px("START ZOOMED IN:", 84.0, row_y(9) + 6.0, fg, globals);
+        let zoomed = Button {
+            label: if settings.zoomed { "2X" } else { "1X" }.to_owned(),
+            ..self.zoomed.clone()
+        };
+        zoomed.draw(settings.zoomed, globals);
+
+        ui::draw_centered_text(
+            "ANTIALIASING CHANGES ON THE NEXT LAUNCH",
+            204.0,
+            hexcolor(ui::DIM),
+            globals,
+        );
+
+        self.back.draw(false, globals);
+    }
+}
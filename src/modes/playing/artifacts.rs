@@ -0,0 +1,165 @@
+//! Things buried in the chasm, worth points to whoever builds up next to them.
+//!
+//! They sit at set depths, in columns picked from the run's seed, and are in the way
+//! until they're dug out. Like the water, they get their own randomness from the seed.
+
+use super::{
+    chasm::ChasmProfile, events::PlayEvent, geometry::Viewport, ModePlaying, RunConfig, ScrollAxis,
+    BLOCK_SIZE,
+};
+use crate::{assets::Textures, random, Globals};
+
+use cogs_gamedev::int_coords::ICoord;
+use macroquad::prelude::{Texture2D, WHITE};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// The first artifact is this deep, and there's another every this many rows after
+const ARTIFACT_SPACING: isize = 15;
+/// How many artifacts a run has buried in it
+const ARTIFACT_COUNT: isize = 20;
+/// Every this many artifacts is an idol instead of a fossil
+const IDOL_EVERY: isize = 4;
+/// For [`random::derived_seed`]
+const SEED_SALT: u64 = 0x4152_5446_4354;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArtifactKind {
+    Fossil,
+    /// Rarer, and worth more
+    Idol,
+}
+
+impl ArtifactKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ArtifactKind::Fossil => "FOSSIL",
+            ArtifactKind::Idol => "IDOL",
+        }
+    }
+
+    /// How many bonus points digging it out is worth
+    pub fn points(self) -> u32 {
+        match self {
+            ArtifactKind::Fossil => 30,
+            ArtifactKind::Idol => 100,
+        }
+    }
+
+    fn texture(self, textures: &Textures) -> Texture2D {
+        match self {
+            ArtifactKind::Fossil => textures.fossil,
+            ArtifactKind::Idol => textures.idol,
+        }
+    }
+}
+
+/// `ICoord` can't be serialized, so the position is stored as plain numbers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Artifact {
+    x: isize,
+    y: isize,
+    kind: ArtifactKind,
+    found: bool,
+}
+
+impl Artifact {
+    fn pos(&self) -> ICoord {
+        ICoord::new(self.x, self.y)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Artifacts {
+    buried: Vec<Artifact>,
+}
+
+impl Artifacts {
+    /// Bury the artifacts for a run with this seed, in a chasm this shape.
+    /// Only chasms get them, and not ones where there's something else to build.
+    pub fn generate(seed: u64, config: &RunConfig, profile: &ChasmProfile) -> Self {
+        if config.scroll_axis != ScrollAxis::Vertical
+            || config.blueprint.is_some()
+            || config.sandbox
+        {
+            return Self::default();
+        }
+        let mut rng = SmallRng::seed_from_u64(random::derived_seed(seed, SEED_SALT));
+        let half = config.chasm_width / 2;
+        let buried = (1..=ARTIFACT_COUNT)
+            .map(|idx| {
+                let kind = if idx % IDOL_EVERY == 0 {
+                    ArtifactKind::Idol
+                } else {
+                    ArtifactKind::Fossil
+                };
+                let y = idx * ARTIFACT_SPACING;
+                // somewhere the walls have come in, it gets pushed back out into the open
+                let (left, right) = profile.span(y);
+                Artifact {
+                    x: rng.gen_range(-half..=half).clamp(left, right),
+                    y,
+                    kind,
+                    found: false,
+                }
+            })
+            // a head start would have built right over them
+            .filter(|artifact| artifact.y > config.start_depth)
+            .collect();
+        Self { buried }
+    }
+
+    /// Whether there's an artifact here that hasn't been dug out yet
+    pub fn is_buried_at(&self, pos: ICoord) -> bool {
+        self.buried
+            .iter()
+            .any(|artifact| !artifact.found && artifact.pos() == pos)
+    }
+
+    pub fn found_count(&self) -> usize {
+        self.buried.iter().filter(|artifact| artifact.found).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.buried.len()
+    }
+}
+
+impl ModePlaying {
+    /// Dig out anything right next to a block that was just put here, for points.
+    pub(super) fn collect_artifacts(&mut self, pos: ICoord) {
+        let mut found = Vec::new();
+        for artifact in self.artifacts.buried.iter_mut() {
+            let distance = (artifact.x - pos.x).abs() + (artifact.y - pos.y).abs();
+            if !artifact.found && distance <= 1 {
+                artifact.found = true;
+                found.push((artifact.pos(), artifact.kind));
+            }
+        }
+        for (at, kind) in found {
+            let gained = self.award_points(kind.points());
+            self.toasts
+                .push(format!("{} FOUND! +{}", kind.name(), gained));
+            self.emit(PlayEvent::ArtifactFound { pos: at });
+        }
+    }
+
+    /// Draw the artifacts still waiting to be dug out, behind the structure.
+    pub(super) fn draw_artifacts(&self, view: &Viewport, globals: &Globals) {
+        use macroquad::prelude::draw_texture;
+
+        let rows = view.visible_rows();
+        for artifact in &self.artifacts.buried {
+            if artifact.found || !rows.contains(&artifact.y) {
+                continue;
+            }
+            let (cx, cy) = view.block_to_pixel(artifact.pos());
+            draw_texture(
+                artifact.kind.texture(&globals.assets.textures),
+                cx - BLOCK_SIZE / 2.0,
+                cy - BLOCK_SIZE / 2.0,
+                WHITE,
+            );
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Hooks for `examples/stress.rs`, which times the parts of a step that get slow
+//! on huge structures, so changes meant to speed things up have numbers to back them.
+//!
+//! The timing lives in the example; this only reaches into the run for it.
+
+use super::{GameType, ModePlaying, RunConfig, CHASM_WIDTH, START_DENSITY};
+
+use std::hint::black_box;
+
+/// A run with a big generated structure, to time things on.
+pub struct StressRun {
+    mode: ModePlaying,
+}
+
+impl StressRun {
+    /// A structure of about this many blocks, looking at the middle of it.
+    ///
+    /// It's the same structure every time, so numbers from different builds compare.
+    pub fn generate(size: usize) -> Self {
+        let per_row = CHASM_WIDTH as f64 * START_DENSITY;
+        let mut depth = (size as f64 / per_row).ceil();
+        let mut tries = 0;
+        loop {
+            let config = RunConfig {
+                start_depth: depth as isize,
+                ..RunConfig::new(GameType::Zen)
+            };
+            let mut mode = ModePlaying::with_seed(config, 0);
+            mode.scroll_depth = depth as f32 / 2.0;
+            // blocks that wouldn't be held up don't get generated, so it comes out short
+            let short = size as f64 / mode.stable_blocks.len().max(1) as f64;
+            tries += 1;
+            if short < 1.1 || tries == 4 {
+                return Self { mode };
+            }
+            depth *= short;
+        }
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.mode.stable_blocks.len()
+    }
+
+    /// Work out what the anchors hold up, like a step does after blocks break.
+    /// Returns how many blocks are held up.
+    pub fn stability_pass(&self) -> usize {
+        ModePlaying::held_up(&self.mode.stable_blocks, self.mode.config.gravity).len()
+    }
+
+    /// Work out every block's chance to take damage, like a step does before rolling for wear.
+    /// Returns all the chances added up.
+    pub fn damage_pass(&self) -> f64 {
+        let mode = &self.mode;
+        let loads = ModePlaying::loads(&mode.stable_blocks, mode.config.gravity);
+        mode.stable_blocks
+            .iter()
+            .map(|(pos, block)| {
+                let load = loads.get(pos).copied().unwrap_or_else(|| block.mass());
+                mode.break_chance(*pos, block, load)
+            })
+            .sum()
+    }
+
+    /// Find the blocks on screen and where they go, like drawing does before it draws anything.
+    /// Returns how many there are.
+    pub fn draw_prep(&self) -> usize {
+        let view = self.mode.viewport();
+        let on_screen = self
+            .mode
+            .stable_blocks
+            .in_area(view.visible_cols(), view.visible_rows());
+        let mut count = 0;
+        for (&pos, block) in on_screen {
+            black_box((view.block_to_pixel(pos), block));
+            count += 1;
+        }
+        count
+    }
+}
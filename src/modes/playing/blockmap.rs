@@ -0,0 +1,302 @@
+//! Where blocks are, split up into chunks so things that only care about
+//! one part of the world can skip the rest of it.
+
+use super::blocks::Block;
+
+use cogs_gamedev::int_coords::ICoord;
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    hash::BuildHasherDefault,
+    iter::FromIterator,
+    ops::{Index, Range},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// How many cells across and down a chunk is
+const CHUNK_SIZE: isize = 16;
+
+/// The next layout stamp to hand out. It's shared between all maps,
+/// so two maps with the same stamp are copies of each other.
+static NEXT_LAYOUT: AtomicU64 = AtomicU64::new(1);
+
+/// The blocks in one chunk.
+///
+/// The hasher has fixed keys so that iteration order, and with it the order dice
+/// get rolled in, is the same every time. Otherwise replays would drift.
+type Chunk = HashMap<ICoord, Block, BuildHasherDefault<DefaultHasher>>;
+
+/// Maps coordinates to whatever block is there, like a `HashMap` would.
+///
+/// The chunks are kept sorted by row and then column, so iterating goes
+/// through the world in bands, and the same way every time.
+#[derive(Clone, Debug, Default)]
+pub struct BlockMap {
+    /// Keyed by which row of chunks and then which column of chunks
+    chunks: BTreeMap<(isize, isize), Chunk>,
+    len: usize,
+    layout: u64,
+}
+
+impl BlockMap {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// A stamp for which blocks are where, for caching things worked out from that.
+    ///
+    /// Putting blocks in or taking them out gets a stamp no map has had before.
+    /// Changing a block through `get_mut` doesn't, so only use that for wear.
+    pub fn layout(&self) -> u64 {
+        self.layout
+    }
+
+    fn relayout(&mut self) {
+        self.layout = NEXT_LAYOUT.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, pos: &ICoord) -> Option<&Block> {
+        self.chunks.get(&chunk_of(*pos))?.get(pos)
+    }
+
+    pub fn get_mut(&mut self, pos: &ICoord) -> Option<&mut Block> {
+        self.chunks.get_mut(&chunk_of(*pos))?.get_mut(pos)
+    }
+
+    pub fn contains_key(&self, pos: &ICoord) -> bool {
+        self.get(pos).is_some()
+    }
+
+    /// Put a block here, handing back whatever was there before.
+    pub fn insert(&mut self, pos: ICoord, block: Block) -> Option<Block> {
+        let old = self
+            .chunks
+            .entry(chunk_of(pos))
+            .or_default()
+            .insert(pos, block);
+        if old.is_none() {
+            self.len += 1;
+        }
+        self.relayout();
+        old
+    }
+
+    pub fn remove(&mut self, pos: &ICoord) -> Option<Block> {
+        let key = chunk_of(*pos);
+        let chunk = self.chunks.get_mut(&key)?;
+        let removed = chunk.remove(pos)?;
+        if chunk.is_empty() {
+            self.chunks.remove(&key);
+        }
+        self.len -= 1;
+        self.relayout();
+        Some(removed)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ICoord, &Block)> {
+        self.chunks.values().flat_map(|chunk| chunk.iter())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &ICoord> {
+        self.iter().map(|(pos, _)| pos)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Block> {
+        self.iter().map(|(_, block)| block)
+    }
+
+    /// Just the blocks in these columns and rows.
+    /// Only the chunks overlapping them get looked at.
+    pub fn in_area(
+        &self,
+        cols: Range<isize>,
+        rows: Range<isize>,
+    ) -> impl Iterator<Item = (&ICoord, &Block)> {
+        let chunk_cols = cols.start.div_euclid(CHUNK_SIZE)..=(cols.end - 1).div_euclid(CHUNK_SIZE);
+        let first_row = rows.start.div_euclid(CHUNK_SIZE);
+        let last_row = (rows.end - 1).div_euclid(CHUNK_SIZE);
+        self.chunks
+            .range((first_row, isize::MIN)..=(last_row, isize::MAX))
+            .filter(move |((_, col), _)| chunk_cols.contains(col))
+            .flat_map(|(_, chunk)| chunk.iter())
+            .filter(move |(pos, _)| cols.contains(&pos.x) && rows.contains(&pos.y))
+    }
+
+    /// Take out every block this says to, and hand them back.
+    pub fn drain_where(
+        &mut self,
+        mut pred: impl FnMut(&ICoord, &mut Block) -> bool,
+    ) -> Vec<(ICoord, Block)> {
+        let mut drained = Vec::new();
+        for chunk in self.chunks.values_mut() {
+            drained.extend(chunk.drain_filter(|pos, block| pred(pos, block)));
+        }
+        self.chunks.retain(|_, chunk| !chunk.is_empty());
+        self.len -= drained.len();
+        if !drained.is_empty() {
+            self.relayout();
+        }
+        drained
+    }
+
+    /// How many blocks there's room for without allocating
+    pub fn capacity(&self) -> usize {
+        self.chunks.values().map(|chunk| chunk.capacity()).sum()
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            chunk.shrink_to_fit();
+        }
+    }
+}
+
+impl Index<&ICoord> for BlockMap {
+    type Output = Block;
+
+    fn index(&self, pos: &ICoord) -> &Block {
+        self.get(pos).expect("no block there")
+    }
+}
+
+impl FromIterator<(ICoord, Block)> for BlockMap {
+    fn from_iter<I: IntoIterator<Item = (ICoord, Block)>>(iter: I) -> Self {
+        let mut map = BlockMap::default();
+        for (pos, block) in iter {
+            map.insert(pos, block);
+        }
+        map
+    }
+}
+
+/// Which row of chunks and which column of chunks this cell is in
+fn chunk_of(pos: ICoord) -> (isize, isize) {
+    (pos.y.div_euclid(CHUNK_SIZE), pos.x.div_euclid(CHUNK_SIZE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modes::playing::blocks::BlockKind;
+
+    /// A plain block, told apart from the others by its damage
+    fn block(tag: u8) -> Block {
+        Block {
+            connectors: [None, None, None, None],
+            kind: BlockKind::Solid,
+            damage: tag,
+        }
+    }
+
+    /// A scattering of blocks on both sides of zero and across chunk edges
+    fn scattered() -> Vec<(ICoord, Block)> {
+        (-40..40)
+            .flat_map(|x| (-40..40).map(move |y| ICoord::new(x, y)))
+            .filter(|pos| (pos.x * 7 + pos.y * 13).rem_euclid(5) < 2)
+            .map(|pos| (pos, block((pos.x - pos.y).rem_euclid(200) as u8)))
+            .collect()
+    }
+
+    fn sorted_keys<'a>(iter: impl Iterator<Item = (&'a ICoord, &'a Block)>) -> Vec<(isize, isize)> {
+        let mut keys = iter.map(|(pos, _)| (pos.x, pos.y)).collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn len_keeps_count() {
+        let mut map = BlockMap::default();
+        let blocks = scattered();
+        for (pos, block) in blocks.iter().cloned() {
+            assert!(map.insert(pos, block).is_none());
+        }
+        assert_eq!(map.len(), blocks.len());
+        assert_eq!(map.iter().count(), blocks.len());
+
+        // replacing doesn't add one
+        let (pos, _) = blocks[0];
+        assert!(map.insert(pos, block(99)).is_some());
+        assert_eq!(map.len(), blocks.len());
+
+        assert!(map.remove(&pos).is_some());
+        assert!(map.remove(&pos).is_none());
+        assert!(map.remove(&ICoord::new(1000, -1000)).is_none());
+        assert_eq!(map.len(), blocks.len() - 1);
+
+        let drained = map.drain_where(|pos, _| pos.x < 0);
+        assert!(!drained.is_empty());
+        assert!(drained.iter().all(|(pos, _)| pos.x < 0));
+        assert_eq!(map.len(), blocks.len() - 1 - drained.len());
+        assert_eq!(map.iter().count(), map.len());
+
+        let rest = map.drain_where(|_, _| true);
+        assert_eq!(map.len(), 0);
+        assert_eq!(map.iter().count(), 0);
+        assert_eq!(rest.len(), blocks.len() - 1 - drained.len());
+    }
+
+    #[test]
+    fn in_area_is_everything_in_the_area() {
+        let map = scattered().into_iter().collect::<BlockMap>();
+        let edges = [
+            -40, -33, -32, -31, -17, -16, -15, -1, 0, 1, 15, 16, 17, 31, 32, 40,
+        ];
+        for &x0 in &edges {
+            for &x1 in edges.iter().filter(|&&x1| x1 > x0) {
+                for &(y0, y1) in &[(-40, 40), (-16, 0), (-17, -15), (15, 17), (0, 1), (-1, 0)] {
+                    let (cols, rows) = (x0..x1, y0..y1);
+                    let everything = map
+                        .iter()
+                        .filter(|(pos, _)| cols.contains(&pos.x) && rows.contains(&pos.y));
+                    assert_eq!(
+                        sorted_keys(map.in_area(cols.clone(), rows.clone())),
+                        sorted_keys(everything),
+                        "cols {:?}, rows {:?}",
+                        cols,
+                        rows
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_order_every_time() {
+        let build = || {
+            let mut map = scattered().into_iter().collect::<BlockMap>();
+            map.drain_where(|pos, _| pos.y % 3 == 0);
+            map.remove(&ICoord::new(-1, -1));
+            map.insert(ICoord::new(-100, 3), block(7));
+            map
+        };
+        let order = |map: &BlockMap| map.keys().map(|pos| (pos.x, pos.y)).collect::<Vec<_>>();
+        let first = build();
+        assert_eq!(order(&first), order(&build()));
+        assert_eq!(order(&first), order(&first.clone()));
+
+        // and in bands, a row of chunks at a time
+        let bands = first.keys().map(|pos| chunk_of(*pos)).collect::<Vec<_>>();
+        let mut sorted = bands.clone();
+        sorted.sort_unstable();
+        assert_eq!(bands, sorted);
+    }
+
+    #[test]
+    fn layout_changes_with_blocks() {
+        let mut map = scattered().into_iter().collect::<BlockMap>();
+        let copy = map.clone();
+        assert_eq!(map.layout(), copy.layout());
+
+        let pos = ICoord::new(5, 5);
+        map.insert(pos, block(1));
+        assert_ne!(map.layout(), copy.layout());
+
+        let before = map.layout();
+        map.get_mut(&pos).unwrap().damage += 1;
+        assert_eq!(map.layout(), before);
+        map.drain_where(|_, _| false);
+        assert_eq!(map.layout(), before);
+        map.remove(&pos);
+        assert_ne!(map.layout(), before);
+    }
+}
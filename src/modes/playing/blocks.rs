@@ -1,4 +1,4 @@
-use super::{BLOCK_SIZE, CHASM_WIDTH};
+use super::{chasm::ChasmProfile, Gravity, ScrollAxis, BLOCK_SIZE, SHAFT_HEIGHT};
 use crate::{assets::Textures, Globals};
 
 use cogs_gamedev::{directions::Direction4, int_coords::ICoord};
@@ -8,8 +8,22 @@ use rand::{
     prelude::{Distribution, SliceRandom},
     Rng,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+/// Chance a random block is an explosive one
+const EXPLOSIVE_CHANCE: f64 = 0.03;
+/// Chance a random block is a glue one
+const GLUE_CHANCE: f64 = 0.02;
+/// How far in from the edge of a block connector glyphs go, to their middle
+const GLYPH_INSET: f32 = 3.5;
+/// How wide connector glyphs are
+const GLYPH_SIZE: f32 = 4.0;
+/// Space between the hatching lines on worn blocks, with glyphs on
+const HATCH_SPACING: f32 = 4.0;
+/// What glyphs and hatching get drawn in. They only take the alpha from the block's color.
+const GLYPH_COLOR: u32 = 0x222034cc;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Block {
     /// Maps `Direction4 as usize` to the connector
     pub connectors: [Option<Connector>; 4],
@@ -23,6 +37,11 @@ impl Block {
             BlockKind::Scaffold => 1.0,
             BlockKind::Solid => 5.0,
             BlockKind::Anchor => 0.0,
+            BlockKind::Girder => 2.0,
+            BlockKind::Reinforced => 8.0,
+            BlockKind::Explosive => 3.0,
+            BlockKind::Glue => 2.0,
+            BlockKind::Crane => 2.0,
         }
     }
 
@@ -31,6 +50,11 @@ impl Block {
             BlockKind::Scaffold => true,
             BlockKind::Solid => false,
             BlockKind::Anchor => false,
+            BlockKind::Girder => true,
+            BlockKind::Reinforced => false,
+            BlockKind::Explosive => false,
+            BlockKind::Glue => false,
+            BlockKind::Crane => false,
         }
     }
 
@@ -40,16 +64,79 @@ impl Block {
             BlockKind::Scaffold => 8,
             BlockKind::Solid => 16,
             BlockKind::Anchor => 64,
+            BlockKind::Girder => 16,
+            BlockKind::Reinforced => 40,
+            BlockKind::Explosive => 8,
+            BlockKind::Glue => 16,
+            BlockKind::Crane => 24,
         }
     }
 
-    pub fn is_valid_pos(&self, pos: ICoord) -> bool {
-        let valid_x = match self.kind {
-            BlockKind::Anchor => pos.x.abs() == CHASM_WIDTH / 2 + 1,
-            _ => pos.x.abs() < CHASM_WIDTH / 2 + 1,
-        };
-        let valid_y = pos.y >= 0;
-        valid_x && valid_y
+    /// How much weight it can carry before it wears any faster than usual.
+    pub fn strength(&self) -> f32 {
+        match self.kind {
+            BlockKind::Scaffold => 4.0,
+            BlockKind::Solid => 12.0,
+            BlockKind::Anchor => 60.0,
+            BlockKind::Girder => 20.0,
+            BlockKind::Reinforced => 30.0,
+            BlockKind::Explosive => 6.0,
+            BlockKind::Glue => 10.0,
+            BlockKind::Crane => 16.0,
+        }
+    }
+
+    /// Make a fresh anchor with a single random connector.
+    pub fn anchor<R: Rng + ?Sized>(rng: &mut R) -> Block {
+        let mut connectors = [Some(rng.gen()), None, None, None];
+        connectors.shuffle(rng);
+
+        Block {
+            connectors,
+            kind: BlockKind::Anchor,
+            damage: 0,
+        }
+    }
+
+    /// Turn this block into an anchor.
+    /// Anchors only get one connector, so all but the first one it has are dropped.
+    pub fn make_anchor(&mut self) {
+        let mut kept_one = false;
+        for conn in self.connectors.iter_mut().filter(|conn| conn.is_some()) {
+            if kept_one {
+                *conn = None;
+            }
+            kept_one = true;
+        }
+        self.kind = BlockKind::Anchor;
+        self.damage = 0;
+    }
+
+    /// Whether this block can go here at all, in a chasm this shape if it's a chasm.
+    pub fn is_valid_pos(&self, pos: ICoord, axis: ScrollAxis, profile: &ChasmProfile) -> bool {
+        match axis {
+            ScrollAxis::Vertical => {
+                let valid_x = match self.kind {
+                    BlockKind::Anchor => profile.is_wall_face(pos),
+                    // in the chasm, or up against the wall like an anchor
+                    BlockKind::Crane => profile.is_open(pos) || profile.is_wall_face(pos),
+                    _ => profile.is_open(pos),
+                };
+                let valid_y = pos.y >= 0;
+                valid_x && valid_y
+            }
+            ScrollAxis::Horizontal => {
+                let in_tunnel = pos.x > 0 && (0..SHAFT_HEIGHT).contains(&pos.y);
+                // in the end wall or the ceiling
+                let in_rock = (pos.x == 0 && (0..SHAFT_HEIGHT).contains(&pos.y))
+                    || (pos.x > 0 && pos.y == -1);
+                match self.kind {
+                    BlockKind::Anchor => in_rock,
+                    BlockKind::Crane => in_tunnel || in_rock,
+                    _ => in_tunnel,
+                }
+            }
+        }
     }
 
     pub fn draw_absolute(&self, cx: f32, cy: f32, globals: &Globals) {
@@ -57,8 +144,30 @@ impl Block {
     }
 
     pub fn draw_absolute_color(&self, cx: f32, cy: f32, color: Color, globals: &Globals) {
+        self.draw_absolute_rotated(cx, cy, color, 0.0, globals);
+    }
+
+    /// Draw the block with its connectors swung clockwise around it by `rotation` radians,
+    /// for animating rotations.
+    pub fn draw_absolute_rotated(
+        &self,
+        cx: f32,
+        cy: f32,
+        color: Color,
+        rotation: f32,
+        globals: &Globals,
+    ) {
         use macroquad::prelude::*;
 
+        let style = globals.render_style();
+        let block_cx = cx;
+        let block_cy = cy;
+        let (sin, cos) = rotation.sin_cos();
+        let glyph_color = {
+            let base = crate::drawutils::hexcolor(GLYPH_COLOR);
+            Color::new(base.r, base.g, base.b, base.a * color.a)
+        };
+
         let tex = self.kind.get_texture(&globals.assets.textures);
         let corner_x = cx - BLOCK_SIZE / 2.0;
         let corner_y = cy - BLOCK_SIZE / 2.0;
@@ -83,68 +192,123 @@ impl Block {
                     ..Default::default()
                 },
             );
+            if style.glyphs {
+                draw_hatching(corner_x, corner_y, damage_amt as usize, glyph_color);
+            }
         }
 
         for (idx, conn) in self.connectors.iter().enumerate() {
             if let Some(conn) = conn {
                 let dir = Direction4::DIRECTIONS[idx];
+                let conn_color = if style.tint_connectors {
+                    let tint = conn.shape.tint();
+                    Color::new(
+                        color.r * tint.r,
+                        color.g * tint.g,
+                        color.b * tint.b,
+                        color.a * tint.a,
+                    )
+                } else {
+                    color
+                };
 
                 let slice_x = conn.shape as usize * 2 + !conn.sticks_out as usize;
                 let slice_x = slice_x as f32 * BLOCK_SIZE;
 
-                let target_x = corner_x
-                    + if !conn.sticks_out {
-                        dir.deltas().x as f32 * BLOCK_SIZE
-                    } else {
-                        0.0
-                    };
-                let target_y = corner_y
-                    + if !conn.sticks_out {
-                        dir.deltas().y as f32 * BLOCK_SIZE
-                    } else {
-                        0.0
-                    };
+                let (offset_x, offset_y) = if !conn.sticks_out {
+                    (
+                        dir.deltas().x as f32 * BLOCK_SIZE,
+                        dir.deltas().y as f32 * BLOCK_SIZE,
+                    )
+                } else {
+                    (0.0, 0.0)
+                };
+                // swing the offset around the block
+                let (offset_x, offset_y) = (
+                    offset_x * cos - offset_y * sin,
+                    offset_x * sin + offset_y * cos,
+                );
 
                 // rotate about this center
-                let cx = target_x + BLOCK_SIZE / 2.0;
-                let cy = target_y + BLOCK_SIZE / 2.0;
+                let cx = block_cx + offset_x;
+                let cy = block_cy + offset_y;
+                let target_x = cx - BLOCK_SIZE / 2.0;
+                let target_y = cy - BLOCK_SIZE / 2.0;
 
                 draw_texture_ex(
                     globals.assets.textures.connector_atlas,
                     target_x,
                     target_y,
-                    color,
+                    conn_color,
                     DrawTextureParams {
                         source: Some(Rect::new(slice_x, 0.0, BLOCK_SIZE, BLOCK_SIZE)),
                         rotation: if dir == Direction4::East {
                             0.0
                         } else {
                             dir.radians()
-                        },
+                        } + rotation,
                         flip_y: dir == Direction4::East,
                         pivot: Some(vec2(cx, cy)),
                         ..Default::default()
                     },
                 );
+
+                if style.glyphs {
+                    let angle = dir.radians() + rotation;
+                    let reach = BLOCK_SIZE / 2.0 - GLYPH_INSET;
+                    let glyph = vec2(
+                        block_cx + angle.cos() * reach,
+                        block_cy + angle.sin() * reach,
+                    );
+                    conn.shape
+                        .draw_glyph(glyph, angle, conn.sticks_out, glyph_color);
+                }
             }
         }
     }
 }
 
+/// Hatch a block from its top left corner, one line for each level of wear,
+/// so wear shows without going by how dark the cracks are.
+fn draw_hatching(corner_x: f32, corner_y: f32, lines: usize, color: Color) {
+    use macroquad::prelude::draw_line;
+
+    for idx in 0..lines {
+        // how far along both edges from the corner this line crosses them
+        let along = (idx as f32 + 0.5) * HATCH_SPACING;
+        let (start, end) = if along <= BLOCK_SIZE {
+            ((0.0, along), (along, 0.0))
+        } else {
+            // past the middle it runs from the bottom edge to the right edge
+            let past = along - BLOCK_SIZE;
+            ((past, BLOCK_SIZE), (BLOCK_SIZE, past))
+        };
+        draw_line(
+            corner_x + start.0,
+            corner_y + start.1,
+            corner_x + end.0,
+            corner_y + end.1,
+            1.0,
+            color,
+        );
+    }
+}
+
 impl Distribution<Block> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Block {
         if rng.gen_bool(0.05) {
             // small chance to make an anchor
-            let mut connectors = [Some(rng.gen()), None, None, None];
-            connectors.shuffle(rng);
-
-            Block {
-                connectors,
-                kind: BlockKind::Anchor,
-                damage: 0,
-            }
+            Block::anchor(rng)
         } else {
             let kind = rng.gen();
+            if kind == BlockKind::Glue {
+                // it sticks without any
+                return Block {
+                    connectors: [None, None, None, None],
+                    kind,
+                    damage: 0,
+                };
+            }
             // The connector must have at least two non-None value
             let mut connectors = [Some(rng.gen()), Some(rng.gen()), None, None];
             for item in connectors.iter_mut().skip(2) {
@@ -166,10 +330,25 @@ pub struct FallingBlockChunk {
     /// Has the original coordinates
     pub blocks: Vec<(ICoord, Block)>,
     pub dy: f32,
+    /// What `dy` was a step ago, to draw it partway between
+    pub prev_dy: f32,
     pub time_alive: u64,
+    /// Scaffolding loose in water floats up instead, as if gravity was the other way
+    pub floating: bool,
 }
 
-#[derive(Clone, Debug)]
+impl FallingBlockChunk {
+    /// Which way this chunk goes, in a run with this gravity
+    pub fn gravity(&self, run_gravity: Gravity) -> Gravity {
+        if self.floating {
+            run_gravity.flipped()
+        } else {
+            run_gravity
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct Connector {
     pub shape: ConnectorShape,
     pub sticks_out: bool,
@@ -191,13 +370,66 @@ impl Distribution<Connector> for Standard {
 }
 
 /// The shape of the connector on the side of the block
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum ConnectorShape {
     Square,
     Round,
     Pointy,
 }
 
+impl ConnectorShape {
+    /// What to multiply its sprite by when connectors are tinted by shape.
+    /// They're light, so the art still shows through.
+    pub fn tint(self) -> macroquad::color::Color {
+        let code = match self {
+            ConnectorShape::Square => 0x8fb4ffff,
+            ConnectorShape::Round => 0xfff07aff,
+            ConnectorShape::Pointy => 0xff8a8aff,
+        };
+        crate::drawutils::hexcolor(code)
+    }
+
+    /// Draw the glyph for this shape centered at `at`, pointing `angle` radians
+    /// clockwise from east. Ones that stick out are filled in.
+    fn draw_glyph(self, at: macroquad::math::Vec2, angle: f32, filled: bool, color: Color) {
+        use macroquad::prelude::*;
+
+        let half = GLYPH_SIZE / 2.0;
+        match self {
+            ConnectorShape::Square => {
+                let (x, y) = (at.x - half, at.y - half);
+                if filled {
+                    draw_rectangle(x, y, GLYPH_SIZE, GLYPH_SIZE, color);
+                } else {
+                    draw_rectangle_lines(x, y, GLYPH_SIZE, GLYPH_SIZE, 1.0, color);
+                }
+            }
+            ConnectorShape::Round => {
+                if filled {
+                    draw_circle(at.x, at.y, half, color);
+                } else {
+                    draw_circle_lines(at.x, at.y, half, 1.0, color);
+                }
+            }
+            ConnectorShape::Pointy => {
+                // pointing out of the block, like the connector
+                let corner = |turn: f32| {
+                    let (sin, cos) = (angle + turn).sin_cos();
+                    at + vec2(cos, sin) * half
+                };
+                let tip = corner(0.0);
+                let left = corner(std::f32::consts::TAU / 3.0);
+                let right = corner(-std::f32::consts::TAU / 3.0);
+                if filled {
+                    draw_triangle(tip, left, right, color);
+                } else {
+                    draw_triangle_lines(tip, left, right, 1.0, color);
+                }
+            }
+        }
+    }
+}
+
 impl Distribution<ConnectorShape> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ConnectorShape {
         let options = [
@@ -212,26 +444,61 @@ impl Distribution<ConnectorShape> for Standard {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum BlockKind {
     Scaffold,
     Solid,
     /// Special blocks that hold the whole structure in place from the top
     Anchor,
+    /// Light but sturdy. Shows up once you've dug a ways down.
+    Girder,
+    /// Heavy, but takes a beating. Shows up deeper still.
+    Reinforced,
+    /// Takes everything around it along when it breaks.
+    Explosive,
+    /// Sticks to every block next to it, whatever their connectors are.
+    Glue,
+    /// Counts as an anchor as long as it links back to one, through other cranes if it has to.
+    /// Shows up deepest of all.
+    Crane,
 }
 
 impl BlockKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockKind::Scaffold => "SCAFFOLD",
+            BlockKind::Solid => "SOLID",
+            BlockKind::Anchor => "ANCHOR",
+            BlockKind::Girder => "GIRDER",
+            BlockKind::Reinforced => "REINFORCED",
+            BlockKind::Explosive => "EXPLOSIVE",
+            BlockKind::Glue => "GLUE",
+            BlockKind::Crane => "CRANE",
+        }
+    }
+
     pub fn get_texture(&self, textures: &Textures) -> Texture2D {
         match self {
             BlockKind::Scaffold => textures.scaffold,
             BlockKind::Solid => textures.solid,
             BlockKind::Anchor => textures.anchor,
+            BlockKind::Girder => textures.girder,
+            BlockKind::Reinforced => textures.reinforced,
+            BlockKind::Explosive => textures.explosive,
+            BlockKind::Glue => textures.glue,
+            BlockKind::Crane => textures.crane,
         }
     }
 }
 
 impl Distribution<BlockKind> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> BlockKind {
+        if rng.gen_bool(EXPLOSIVE_CHANCE) {
+            return BlockKind::Explosive;
+        }
+        if rng.gen_bool(GLUE_CHANCE) {
+            return BlockKind::Glue;
+        }
         let options = [BlockKind::Scaffold, BlockKind::Scaffold, BlockKind::Solid];
         options[rng.gen_range(0..options.len())].clone()
     }
@@ -0,0 +1,135 @@
+//! Blueprint challenges: build a given structure as closely and quickly as you can.
+
+use super::{blocks::BlockKind, geometry::Viewport, BlockMap, ModePlaying, CHASM_WIDTH};
+use crate::{drawutils::hexcolor, ui, Globals};
+
+use cogs_gamedev::int_coords::ICoord;
+use serde::{Deserialize, Serialize};
+
+/// A structure to recreate.
+///
+/// `ICoord` can't be serialized, so cells are plain `(x, y)` pairs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Blueprint {
+    pub name: String,
+    pub cells: Vec<((isize, isize), BlockKind)>,
+}
+
+/// How blueprints are written in the files in `assets/blueprints`.
+#[derive(Deserialize)]
+struct BlueprintFile {
+    name: String,
+    /// Depth of the first row
+    top: isize,
+    /// One string per row, one character per column across the chasm
+    rows: Vec<String>,
+}
+
+impl Blueprint {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: BlueprintFile = toml::from_str(text).map_err(|err| err.to_string())?;
+        let mut cells = Vec::new();
+        for (row_idx, row) in file.rows.iter().enumerate() {
+            if row.chars().count() != CHASM_WIDTH as usize {
+                return Err(format!(
+                    "row {} is {} wide but the chasm is {}",
+                    row_idx,
+                    row.chars().count(),
+                    CHASM_WIDTH
+                ));
+            }
+            for (col_idx, c) in row.chars().enumerate() {
+                let kind = match c {
+                    '.' => continue,
+                    's' => BlockKind::Scaffold,
+                    '#' => BlockKind::Solid,
+                    'g' => BlockKind::Girder,
+                    'r' => BlockKind::Reinforced,
+                    'x' => BlockKind::Explosive,
+                    '+' => BlockKind::Glue,
+                    'c' => BlockKind::Crane,
+                    other => return Err(format!("unknown block {:?} in row {}", other, row_idx)),
+                };
+                let x = col_idx as isize - CHASM_WIDTH / 2;
+                let y = file.top + row_idx as isize;
+                cells.push(((x, y), kind));
+            }
+        }
+        Ok(Self {
+            name: file.name,
+            cells,
+        })
+    }
+
+    /// How closely the structure matches, from 0 to 1.
+    ///
+    /// Each cell with the right kind of block counts fully, and with the wrong kind half.
+    /// Blocks where the blueprint has nothing count against it by half.
+    pub fn accuracy(&self, stable_blocks: &BlockMap) -> f32 {
+        if self.cells.is_empty() {
+            return 1.0;
+        }
+        let mut matched = 0.0;
+        for &((x, y), ref kind) in &self.cells {
+            match stable_blocks.get(&ICoord::new(x, y)) {
+                Some(block) if &block.kind == kind => matched += 1.0,
+                Some(_) => matched += 0.5,
+                None => {}
+            }
+        }
+        let extra = stable_blocks
+            .iter()
+            .filter(|(pos, block)| {
+                block.kind != BlockKind::Anchor
+                    && !self
+                        .cells
+                        .iter()
+                        .any(|&((x, y), _)| pos.x == x && pos.y == y)
+            })
+            .count();
+        ((matched - extra as f32 * 0.5) / self.cells.len() as f32).clamp(0.0, 1.0)
+    }
+}
+
+/// Score for a blueprint run: accuracy, with the slow runs taking a cut.
+pub fn blueprint_score(accuracy: f32, frames: u64) -> u32 {
+    let seconds = frames as f32 / 60.0;
+    // lose a bit for every second, but never more than half
+    let speed = (1.0 - seconds / 1200.0).max(0.5);
+    (accuracy * 1000.0 * speed).round() as u32
+}
+
+impl ModePlaying {
+    /// Draw the cells of the blueprint that aren't built right yet.
+    pub(super) fn draw_blueprint(&self, view: &Viewport, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let blueprint = match &self.config.blueprint {
+            Some(it) => it,
+            None => return,
+        };
+        for ((x, y), kind) in &blueprint.cells {
+            let pos = ICoord::new(*x, *y);
+            let (cx, cy) = view.block_to_pixel(pos);
+            let corner_x = cx - super::BLOCK_SIZE / 2.0;
+            let corner_y = cy - super::BLOCK_SIZE / 2.0;
+            match self.stable_blocks.get(&pos) {
+                Some(block) if &block.kind == kind => {}
+                Some(_) => draw_rectangle_lines(
+                    corner_x,
+                    corner_y,
+                    super::BLOCK_SIZE,
+                    super::BLOCK_SIZE,
+                    1.0,
+                    hexcolor(ui::ALERT),
+                ),
+                None => draw_texture(
+                    kind.get_texture(&globals.assets.textures),
+                    corner_x,
+                    corner_y,
+                    Color::new(1.0, 1.0, 1.0, 0.35),
+                ),
+            }
+        }
+    }
+}
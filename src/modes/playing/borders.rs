@@ -0,0 +1,65 @@
+//! The edges of the world: where falling blocks are gone for good,
+//! and how far ahead the camera can look.
+//!
+//! Both move as the structure gets deeper, so they're drawn fresh every frame.
+
+use super::{
+    geometry::Viewport, Gravity, ModePlaying, ScrollAxis, BLOCK_SIZE, BOTTOM_VIEW_SIZE,
+    SCREEN_HEIGHT, SHAFT_HEIGHT,
+};
+use crate::{drawutils::hexcolor, HEIGHT, WIDTH};
+
+use macroquad::prelude::*;
+
+/// Falling blocks that pass this line vanish
+const DESPAWN_COLOR: u32 = 0xd9576380;
+/// The camera can't see past this line
+const LOOKAHEAD_COLOR: u32 = 0xa3a7c260;
+/// How long each dash is, and the gap after it
+const DASH: f32 = 4.0;
+
+impl ModePlaying {
+    /// The row falling blocks are gone for good at, if they don't link up first
+    pub(super) fn despawn_row(&self) -> isize {
+        match self.config.scroll_axis {
+            ScrollAxis::Vertical => self.report.max_depth + BOTTOM_VIEW_SIZE * 2,
+            ScrollAxis::Horizontal => SHAFT_HEIGHT + SCREEN_HEIGHT,
+        }
+    }
+
+    /// How far down the camera can go
+    pub(super) fn max_scroll(&self) -> f32 {
+        (self.report.max_depth + BOTTOM_VIEW_SIZE) as f32
+    }
+
+    /// Mark the despawn row and the furthest the camera can look.
+    pub(super) fn draw_borders(&self, view: &Viewport) {
+        // upside-down, things smash on the cavern floor, which you can already see
+        if self.config.gravity == Gravity::Down {
+            // between the last row blocks survive in and the first they don't
+            let (_, y) = view.world_to_pixel(0.0, self.despawn_row() as f32 - 0.5);
+            draw_dashes(y, ScrollAxis::Vertical, hexcolor(DESPAWN_COLOR));
+        }
+
+        // the far edge of the screen, with the camera as far as it goes
+        let half_view = match self.config.scroll_axis {
+            ScrollAxis::Vertical => HEIGHT / 2.0,
+            ScrollAxis::Horizontal => WIDTH / 2.0,
+        } / BLOCK_SIZE;
+        let edge = view.depth_to_pixel(self.max_scroll() + half_view);
+        draw_dashes(edge, self.config.scroll_axis, hexcolor(LOOKAHEAD_COLOR));
+    }
+}
+
+/// A dashed line across the view at this distance down the scroll axis.
+/// Zoomed out views show more than the screen, so it runs well past the edges.
+fn draw_dashes(at: f32, axis: ScrollAxis, color: Color) {
+    let mut along = -WIDTH;
+    while along < WIDTH * 2.0 {
+        match axis {
+            ScrollAxis::Vertical => draw_line(along, at, along + DASH, at, 1.0, color),
+            ScrollAxis::Horizontal => draw_line(at, along, at, along + DASH, 1.0, color),
+        }
+        along += DASH * 2.0;
+    }
+}
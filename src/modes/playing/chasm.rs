@@ -0,0 +1,143 @@
+//! The shape of the chasm: which columns are open at each depth.
+//!
+//! Straight chasms are the same width all the way down. Runs with shaped walls have them
+//! wander in and out as it goes down, into narrow shafts, wide caverns and little alcoves
+//! off to one side.
+//!
+//! The shape only depends on the run's seed, and every band of rows is worked out from it
+//! on its own when it's asked about, so there's nothing to save and no bottom to it.
+
+use super::{RunConfig, ScrollAxis};
+use crate::random;
+
+use cogs_gamedev::int_coords::ICoord;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+/// The chasm is split into bands this many rows tall, each with at most one feature
+const BAND_HEIGHT: isize = 16;
+/// Furthest a wall goes out past where it usually is
+const MAX_BULGE: isize = 2;
+/// The narrowest a shaft gets, in open columns either side of the middle one
+const MIN_HALF_WIDTH: isize = 1;
+/// For [`random::derived_seed`]
+const SEED_SALT: u64 = 0x5741_4c4c_5321;
+
+/// Which columns are open at each depth.
+#[derive(Clone, Copy, Debug)]
+pub struct ChasmProfile {
+    /// Open columns either side of the middle one, where the walls haven't moved
+    half_width: isize,
+    /// Where the walls come from, if they move at all
+    seed: Option<u64>,
+}
+
+/// What happens to the walls in one band.
+#[derive(Clone, Copy, Debug)]
+enum Feature {
+    Straight,
+    /// Both walls come in, into a narrow shaft
+    Shaft,
+    /// Both walls go out, into a wide cavern
+    Cavern,
+    /// One wall goes out for a few rows. `true` for the right one.
+    Alcove(bool),
+}
+
+impl ChasmProfile {
+    /// A chasm this many columns across all the way down.
+    pub fn straight(width: isize) -> Self {
+        Self {
+            half_width: width / 2,
+            seed: None,
+        }
+    }
+
+    /// The chasm for a run with this seed.
+    pub fn generate(seed: u64, config: &RunConfig) -> Self {
+        let shaped = config.shaped_walls && config.scroll_axis == ScrollAxis::Vertical;
+        Self {
+            seed: if shaped {
+                Some(random::derived_seed(seed, SEED_SALT))
+            } else {
+                None
+            },
+            ..Self::straight(config.chasm_width)
+        }
+    }
+
+    /// The leftmost and rightmost open columns at this row. Both ends are inclusive.
+    ///
+    /// Above the rim, it's as wide as the rim.
+    pub fn span(&self, row: isize) -> (isize, isize) {
+        let (left_out, right_out) = self.bulges(row);
+        (-(self.half_width + left_out), self.half_width + right_out)
+    }
+
+    /// How many columns are open at this row
+    pub fn width(&self, row: isize) -> isize {
+        let (left, right) = self.span(row);
+        right - left + 1
+    }
+
+    /// Whether this is in the chasm, and not the rock around it.
+    /// Everything above the rim counts as long as it's over the rim.
+    pub fn is_open(&self, pos: ICoord) -> bool {
+        let (left, right) = self.span(pos.y);
+        (left..=right).contains(&pos.x)
+    }
+
+    /// Whether this is in the rock right up against the side of the chasm, where anchors go
+    pub fn is_wall_face(&self, pos: ICoord) -> bool {
+        let (left, right) = self.span(pos.y);
+        pos.x == left - 1 || pos.x == right + 1
+    }
+
+    /// The leftmost and rightmost columns open at any depth
+    pub fn widest(&self) -> (isize, isize) {
+        let bulge = if self.seed.is_some() { MAX_BULGE } else { 0 };
+        (-(self.half_width + bulge), self.half_width + bulge)
+    }
+
+    /// How far out each wall is pushed at this row: left, then right.
+    /// Negative is in.
+    fn bulges(&self, row: isize) -> (isize, isize) {
+        let seed = match self.seed {
+            Some(it) if row >= BAND_HEIGHT => it,
+            // the rim and the first band are always straight, so runs start the same
+            _ => return (0, 0),
+        };
+        let band = row / BAND_HEIGHT;
+        let into = row % BAND_HEIGHT;
+        let mut rng = SmallRng::seed_from_u64(seed.wrapping_add(band as u64));
+        let feature = match rng.gen_range(0..6) {
+            0 | 1 => Feature::Straight,
+            2 => Feature::Shaft,
+            3 => Feature::Cavern,
+            _ => Feature::Alcove(rng.gen()),
+        };
+        match feature {
+            Feature::Straight => (0, 0),
+            Feature::Shaft if (3..BAND_HEIGHT - 3).contains(&into) => {
+                let squeeze = (self.half_width - MIN_HALF_WIDTH).min(MAX_BULGE);
+                (-squeeze, -squeeze)
+            }
+            Feature::Cavern if (2..BAND_HEIGHT - 2).contains(&into) => {
+                // widest in the middle
+                let out = if (5..BAND_HEIGHT - 5).contains(&into) {
+                    MAX_BULGE
+                } else {
+                    1
+                };
+                (out, out)
+            }
+            Feature::Alcove(right) if (6..10).contains(&into) => {
+                if right {
+                    (0, MAX_BULGE)
+                } else {
+                    (MAX_BULGE, 0)
+                }
+            }
+            _ => (0, 0),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! A little panel over the chasm showing how far down each column goes,
+//! and how many holes it has on the way.
+//!
+//! A row only fills in once every column reaches it, so it's handy for
+//! spotting the column that's been left behind. Tab brings it up.
+
+use super::{geometry::Viewport, ModePlaying, ScrollAxis};
+use crate::{
+    drawutils::{self, hexcolor},
+    ui, Globals,
+};
+
+use cogs_gamedev::int_coords::ICoord;
+
+/// How tall the bar for the deepest column is
+const BAR_MAX_HEIGHT: f32 = 20.0;
+const BAR_WIDTH: f32 = 6.0;
+const PANEL_TOP: f32 = 2.0;
+
+/// How one column of the chasm is doing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ColumnStats {
+    /// Row of the deepest block in the column, if it has any below the rim
+    pub deepest: Option<isize>,
+    /// Empty spots between the rim and the deepest block
+    pub gaps: usize,
+}
+
+impl ModePlaying {
+    /// Draw the panel, in runs that have columns to show.
+    pub(super) fn draw_column_stats(&self, view: &Viewport, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        if self.config.scroll_axis != ScrollAxis::Vertical {
+            return;
+        }
+        let stats = &self.report.columns;
+        let deepest = stats
+            .iter()
+            .filter_map(|column| column.deepest)
+            .max()
+            .unwrap_or(0);
+        let shallowest = stats
+            .iter()
+            .map(|column| column.deepest.unwrap_or(-1))
+            .min()
+            .unwrap_or(-1);
+
+        let (left, _) = self.profile.span(0);
+        let mut bg = hexcolor(ui::BACKGROUND);
+        bg.a = 0.6;
+        for (idx, column) in stats.iter().enumerate() {
+            let (cx, _) = view.block_to_screen(ICoord::new(left + idx as isize, 0));
+            let x = cx - BAR_WIDTH / 2.0;
+            draw_rectangle(
+                x - 1.0,
+                PANEL_TOP,
+                BAR_WIDTH + 2.0,
+                BAR_MAX_HEIGHT + 10.0,
+                bg,
+            );
+
+            let reach = column.deepest.map_or(0.0, |row| (row + 1) as f32);
+            let height = (BAR_MAX_HEIGHT * reach / (deepest + 1) as f32).round();
+            // the column everything's waiting on stands out
+            let color = if column.deepest.unwrap_or(-1) == shallowest && shallowest < deepest {
+                hexcolor(ui::ALERT)
+            } else {
+                hexcolor(ui::FOREGROUND)
+            };
+            draw_rectangle(x, PANEL_TOP + 1.0, BAR_WIDTH, height, color);
+
+            if column.gaps > 0 {
+                let label = column.gaps.min(9).to_string();
+                drawutils::draw_text_px(
+                    &label,
+                    cx - 1.0,
+                    PANEL_TOP + BAR_MAX_HEIGHT + 3.0,
+                    hexcolor(ui::DIM),
+                    globals,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Keeping a streak going by placing blocks that link up on more than one side.
+//!
+//! It hears about placements and breakages through the frame's events, like the sounds do,
+//! and the streak multiplies whatever points are earned while it lasts.
+
+use super::{events::PlayEvent, geometry::Viewport, ModePlaying};
+use crate::{
+    drawutils::{self, hexcolor},
+    ui, Globals,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// A placement has to link up on this many sides to keep the streak going
+const COMBO_LINKS: usize = 2;
+/// Every this many placements in a row adds one to the multiplier
+const COMBO_PER_MULTIPLIER: u32 = 3;
+const MAX_MULTIPLIER: u32 = 5;
+/// How long the popup stays up after the streak grows
+const POPUP_FRAMES: u32 = 60;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Combo {
+    /// Good placements in a row
+    streak: u32,
+    /// Frames left to show the popup for
+    #[serde(skip)]
+    popup: u32,
+}
+
+impl Combo {
+    /// Keep the streak going, or break it off.
+    pub fn hear(&mut self, event: &PlayEvent) {
+        match *event {
+            PlayEvent::BlockPlaced { links, .. } if links >= COMBO_LINKS => {
+                self.streak += 1;
+                self.popup = POPUP_FRAMES;
+            }
+            PlayEvent::BlockBroken { .. }
+            | PlayEvent::ChunkFell { .. }
+            | PlayEvent::AnchorLost
+            | PlayEvent::Exploded { .. } => {
+                self.streak = 0;
+                self.popup = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Count down the popup.
+    pub fn tick(&mut self) {
+        self.popup = self.popup.saturating_sub(1);
+    }
+
+    /// What points earned right now get multiplied by
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.streak / COMBO_PER_MULTIPLIER).min(MAX_MULTIPLIER)
+    }
+}
+
+impl ModePlaying {
+    /// Add some points, multiplied by the combo. Returns how many that came to.
+    pub(super) fn award_points(&mut self, base: u32) -> u32 {
+        let gained = base * self.combo.multiplier();
+        self.points += gained;
+        gained
+    }
+
+    /// Pop up the streak by the last block placed, while it's fresh.
+    pub(super) fn draw_combo(&self, view: &Viewport, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let combo = &self.combo;
+        let pos = match self.last_placed {
+            Some(it) if combo.popup > 0 && combo.streak >= 2 => it,
+            _ => return,
+        };
+        let (cx, cy) = view.block_to_screen(pos);
+        let alpha = (combo.popup as f32 / (POPUP_FRAMES / 3) as f32).min(1.0);
+        // drift up as it fades
+        let y = (cy - 16.0 - (POPUP_FRAMES - combo.popup) as f32 / 6.0).round();
+        let label = format!("COMBO X{}", combo.multiplier());
+        let width = drawutils::text_width_px(&label) + 16.0;
+        let x = (cx - width / 2.0).round();
+
+        let mut bg = hexcolor(ui::BACKGROUND);
+        bg.a = alpha * 0.8;
+        let mut fg = hexcolor(ui::FOREGROUND);
+        fg.a = alpha;
+        draw_rectangle(x, y, width, 9.0, bg);
+        drawutils::draw_text_px(&label, x + 2.0, y + 2.0, fg, globals);
+        drawutils::draw_number(combo.streak as i32, x + width - 1.0, y + 2.0, globals);
+    }
+}
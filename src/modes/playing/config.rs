@@ -0,0 +1,467 @@
+use cogs_gamedev::{directions::Direction4, int_coords::ICoord};
+use serde::{Deserialize, Serialize};
+
+use super::{blocks::BlockKind, Blueprint, Objective, CHASM_WIDTH, CONVEYOR_MAX_SIZE, RACE_DEPTH};
+use crate::settings::AssistSettings;
+
+/// The rules a run is played under.
+///
+/// Snapshots and replays are packed with this in them, so adding a field means bumping
+/// `SNAPSHOT_VERSION` and `REPLAY_VERSION`. The `#[serde(default)]`s are for JSON saves.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunConfig {
+    pub game_type: GameType,
+    /// Multiplies the chance of blocks taking damage from wear.
+    pub decay_multiplier: f64,
+    /// Frames you have to wait after placing a block before you can pick up another.
+    pub placement_cooldown: u64,
+    /// How many blocks come down the conveyor after the first batch.
+    /// `None` means they never stop.
+    pub block_allowance: Option<usize>,
+    /// Whether the run can be ended at any time, instead of only once the blocks run out.
+    pub finish_anytime: bool,
+    pub save_policy: SavePolicy,
+    /// Nudge placements that are one cell off onto a cell where they link up
+    #[serde(default)]
+    pub snap_placement: bool,
+    /// Whether any assist options were on for this run
+    #[serde(default)]
+    pub assisted: bool,
+    /// Whether the run keeps a timeline you can scrub back through while paused
+    #[serde(default)]
+    pub rewindable: bool,
+    /// Whether a second player joins in on the keyboard
+    #[serde(default)]
+    pub coop: bool,
+    /// Whether two players take turns, trying not to be the one who makes it collapse
+    #[serde(default)]
+    pub versus: bool,
+    /// Whether this is one side of a split-screen race, lost by bringing down a big chunk
+    #[serde(default)]
+    pub race: bool,
+    /// Exactly how many blocks the whole run gets, counting the first batch.
+    /// The run ends on its own once they're all placed.
+    #[serde(default)]
+    pub block_budget: Option<usize>,
+    /// The structure to recreate, in blueprint challenges
+    #[serde(default)]
+    pub blueprint: Option<Blueprint>,
+    /// Which way blocks fall, relative to the anchors
+    #[serde(default)]
+    pub gravity: Gravity,
+    /// How high the cavern goes before the ceiling, when building upward.
+    /// Reach it and the run is over.
+    #[serde(default)]
+    pub ceiling: Option<isize>,
+    /// Which way the structure grows out from the anchors
+    #[serde(default)]
+    pub scroll_axis: ScrollAxis,
+    /// Whether blocks can go up above the rim once the chasm has been bridged
+    #[serde(default)]
+    pub tower: bool,
+    /// Whether the simulation can be sped up and slowed down, for studying how things break.
+    /// Only for runs that aren't recorded, since replays play back at one speed.
+    #[serde(default)]
+    pub sim_speed_control: bool,
+    /// What has to be done to win, if there's anything
+    #[serde(default)]
+    pub objective: Option<Objective>,
+    /// How many columns across the chasm is
+    #[serde(default = "default_chasm_width")]
+    pub chasm_width: isize,
+    /// What kinds of block the conveyor brings, besides the odd anchor.
+    /// Empty means the usual mix.
+    #[serde(default)]
+    pub block_kinds: Vec<BlockKind>,
+    /// Which campaign level this is, in campaign runs
+    #[serde(default)]
+    pub level: Option<usize>,
+    /// How deep the structure already goes when the run starts, for practicing the deep game
+    /// without building all the way down first. Only for chasms; tunnels ignore it.
+    #[serde(default)]
+    pub start_depth: isize,
+    /// Which day's challenge this is, in days since the Unix epoch, in daily runs
+    #[serde(default)]
+    pub daily: Option<i64>,
+    /// How many blocks the conveyor holds at once
+    #[serde(default = "default_conveyor_size")]
+    pub conveyor_size: usize,
+    /// A run just for trying things out: nothing gets saved or scored,
+    /// and leaving goes back to wherever it was started from
+    #[serde(default)]
+    pub sandbox: bool,
+    /// Whether there are pockets of water underground,
+    /// which wear blocks down faster and float loose scaffolding back up
+    #[serde(default)]
+    pub water: bool,
+    /// Whether the walls of the chasm wander in and out on the way down, instead of running straight
+    #[serde(default)]
+    pub shaped_walls: bool,
+}
+
+fn default_chasm_width() -> isize {
+    CHASM_WIDTH
+}
+
+fn default_conveyor_size() -> usize {
+    CONVEYOR_MAX_SIZE
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameType {
+    /// The original: 100 blocks, get as deep as you can
+    Classic,
+    /// No decay, endless blocks, but you have to take your time
+    Zen,
+    /// Classic, but the run is saved as you go and there's no going back
+    Hardcore,
+    /// Endless blocks, and you can rewind to try things differently
+    Practice,
+    /// Classic, with a second player on the keyboard
+    Coop,
+    /// Two players take turns; don't be the one who brings it down
+    Versus,
+    /// A fixed number of blocks for the whole run; get as deep as you can with them
+    ScoreAttack,
+    /// Build a given structure as closely and quickly as you can
+    Blueprint,
+    /// Anchors on the floor of a cavern; build up to the ceiling
+    Cavern,
+    /// Build sideways out from the wall of a tunnel; get as far as you can
+    Shaft,
+    /// Classic, but once the chasm is bridged you can build up above it too
+    Tower,
+    /// One of the handmade levels, with a goal to meet
+    Campaign,
+    /// Classic, with the same blocks for everyone that day
+    Daily,
+    /// A tiny chasm to practice in, off the rules screen
+    Sandbox,
+    /// Classic, with pockets of water underground
+    Flooded,
+    /// Two players side by side, each in their own chasm, racing to get deep enough first
+    Race,
+}
+
+/// How many blocks a score attack run gets
+pub const SCORE_ATTACK_BUDGET: usize = 50;
+/// How far up the ceiling of a cavern is, give or take its bumps
+pub const CAVERN_HEIGHT: isize = 30;
+/// How many columns across the sandbox chasm is
+const SANDBOX_CHASM_WIDTH: isize = 5;
+/// How many blocks the sandbox conveyor holds
+const SANDBOX_CONVEYOR_SIZE: usize = 3;
+
+impl GameType {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameType::Classic => "CLASSIC",
+            GameType::Zen => "ZEN",
+            GameType::Hardcore => "HARDCORE",
+            GameType::Practice => "PRACTICE",
+            GameType::Coop => "CO-OP",
+            GameType::Versus => "VERSUS",
+            GameType::ScoreAttack => "SCORE ATTACK",
+            GameType::Blueprint => "BLUEPRINT",
+            GameType::Cavern => "CAVERN",
+            GameType::Shaft => "SHAFT",
+            GameType::Tower => "TOWER",
+            GameType::Campaign => "CAMPAIGN",
+            GameType::Daily => "DAILY",
+            GameType::Sandbox => "SANDBOX",
+            GameType::Flooded => "FLOODED",
+            GameType::Race => "RACE",
+        }
+    }
+}
+
+/// Which way things fall.
+///
+/// The world is always laid out with the anchors at y 0 to 3 and the structure
+/// growing towards +y; this is whether it hangs down from them or stands up on them.
+/// Runs with things falling up are drawn upside down, so they still fall down on screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Gravity {
+    /// Towards +y, away from the anchors
+    Down,
+    /// Towards -y, onto the anchors
+    Up,
+}
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Gravity::Down
+    }
+}
+
+impl Gravity {
+    /// The way blocks fall
+    pub fn down(self) -> Direction4 {
+        match self {
+            Gravity::Down => Direction4::South,
+            Gravity::Up => Direction4::North,
+        }
+    }
+
+    /// The way blocks get held up from
+    pub fn up(self) -> Direction4 {
+        self.down().flip()
+    }
+
+    /// The other way around
+    pub fn flipped(self) -> Gravity {
+        match self {
+            Gravity::Down => Gravity::Up,
+            Gravity::Up => Gravity::Down,
+        }
+    }
+
+    /// How much y changes by for every block fallen
+    pub fn sign(self) -> isize {
+        match self {
+            Gravity::Down => 1,
+            Gravity::Up => -1,
+        }
+    }
+}
+
+/// Which way the structure grows, and so which way "depth" and the camera go.
+/// Gravity is down either way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScrollAxis {
+    /// Down a chasm, hanging between its two walls
+    Vertical,
+    /// Along a tunnel, sticking out from the wall at its end
+    Horizontal,
+}
+
+impl Default for ScrollAxis {
+    fn default() -> Self {
+        ScrollAxis::Vertical
+    }
+}
+
+impl ScrollAxis {
+    /// How far along the axis this position is
+    pub fn reach(self, pos: ICoord) -> isize {
+        match self {
+            ScrollAxis::Vertical => pos.y,
+            ScrollAxis::Horizontal => pos.x,
+        }
+    }
+}
+
+/// What happens to the run on disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SavePolicy {
+    /// Nothing gets saved
+    Never,
+    /// Autosave as you play. You can save and quit, but only resume once,
+    /// and the save is deleted when the structure dies.
+    Hardcore,
+    /// Only saved when you save and quit. Resuming takes the save back off the disk,
+    /// so the same run can't be picked up twice.
+    Suspend,
+}
+
+impl SavePolicy {
+    /// Name of the autosave for runs with this policy, if they have one
+    pub fn save_name(&self) -> Option<&'static str> {
+        match self {
+            SavePolicy::Never => None,
+            // it's packed now, but keeps its old name so runs saved as JSON still get found
+            SavePolicy::Hardcore => Some("hardcore_run.json"),
+            SavePolicy::Suspend => Some("suspended_run"),
+        }
+    }
+
+    /// Whether runs with this policy save themselves every so often,
+    /// and so have a save to clean up once they're over
+    pub fn autosaves(&self) -> bool {
+        match self {
+            SavePolicy::Never | SavePolicy::Suspend => false,
+            SavePolicy::Hardcore => true,
+        }
+    }
+}
+
+impl RunConfig {
+    pub fn new(game_type: GameType) -> Self {
+        match game_type {
+            GameType::Classic => Self {
+                game_type,
+                decay_multiplier: 1.0,
+                placement_cooldown: 0,
+                block_allowance: Some(100),
+                finish_anytime: false,
+                save_policy: SavePolicy::Suspend,
+                snap_placement: false,
+                assisted: false,
+                rewindable: false,
+                coop: false,
+                versus: false,
+                race: false,
+                block_budget: None,
+                blueprint: None,
+                gravity: Gravity::Down,
+                ceiling: None,
+                scroll_axis: ScrollAxis::Vertical,
+                tower: false,
+                sim_speed_control: false,
+                objective: None,
+                chasm_width: CHASM_WIDTH,
+                block_kinds: Vec::new(),
+                level: None,
+                start_depth: 0,
+                daily: None,
+                conveyor_size: CONVEYOR_MAX_SIZE,
+                sandbox: false,
+                water: false,
+                shaped_walls: true,
+            },
+            GameType::Zen => Self {
+                game_type,
+                decay_multiplier: 0.0,
+                placement_cooldown: 90,
+                block_allowance: None,
+                finish_anytime: true,
+                save_policy: SavePolicy::Suspend,
+                snap_placement: false,
+                assisted: false,
+                rewindable: false,
+                coop: false,
+                versus: false,
+                race: false,
+                block_budget: None,
+                blueprint: None,
+                gravity: Gravity::Down,
+                ceiling: None,
+                scroll_axis: ScrollAxis::Vertical,
+                tower: false,
+                sim_speed_control: false,
+                objective: None,
+                chasm_width: CHASM_WIDTH,
+                block_kinds: Vec::new(),
+                level: None,
+                start_depth: 0,
+                daily: None,
+                conveyor_size: CONVEYOR_MAX_SIZE,
+                sandbox: false,
+                water: false,
+                shaped_walls: false,
+            },
+            GameType::Hardcore => Self {
+                game_type,
+                save_policy: SavePolicy::Hardcore,
+                ..Self::new(GameType::Classic)
+            },
+            GameType::Practice => Self {
+                game_type,
+                block_allowance: None,
+                finish_anytime: true,
+                rewindable: true,
+                sim_speed_control: true,
+                // the timeline doesn't get saved
+                save_policy: SavePolicy::Never,
+                ..Self::new(GameType::Classic)
+            },
+            GameType::Coop => Self {
+                game_type,
+                coop: true,
+                save_policy: SavePolicy::Never,
+                ..Self::new(GameType::Classic)
+            },
+            GameType::Versus => Self {
+                game_type,
+                versus: true,
+                save_policy: SavePolicy::Never,
+                ..Self::new(GameType::Classic)
+            },
+            GameType::ScoreAttack => Self {
+                game_type,
+                block_budget: Some(SCORE_ATTACK_BUDGET),
+                ..Self::new(GameType::Classic)
+            },
+            // Pick which one with `with_blueprint`
+            GameType::Blueprint => Self {
+                game_type,
+                block_allowance: None,
+                finish_anytime: true,
+                // blueprints are drawn for a straight chasm
+                shaped_walls: false,
+                ..Self::new(GameType::Classic)
+            },
+            GameType::Cavern => Self {
+                game_type,
+                gravity: Gravity::Up,
+                ceiling: Some(CAVERN_HEIGHT),
+                ..Self::new(GameType::Classic)
+            },
+            GameType::Shaft => Self {
+                game_type,
+                scroll_axis: ScrollAxis::Horizontal,
+                ..Self::new(GameType::Classic)
+            },
+            GameType::Tower => Self {
+                game_type,
+                tower: true,
+                ..Self::new(GameType::Classic)
+            },
+            // the level fills in the rest
+            GameType::Campaign => Self {
+                game_type,
+                shaped_walls: false,
+                ..Self::new(GameType::Classic)
+            },
+            // pick the day with `with_day`
+            GameType::Daily => Self {
+                game_type,
+                ..Self::new(GameType::Classic)
+            },
+            GameType::Sandbox => Self {
+                game_type,
+                chasm_width: SANDBOX_CHASM_WIDTH,
+                conveyor_size: SANDBOX_CONVEYOR_SIZE,
+                sandbox: true,
+                placement_cooldown: 0,
+                finish_anytime: false,
+                save_policy: SavePolicy::Never,
+                ..Self::new(GameType::Zen)
+            },
+            GameType::Flooded => Self {
+                game_type,
+                water: true,
+                ..Self::new(GameType::Classic)
+            },
+            // each side of the race is one of these
+            GameType::Race => Self {
+                game_type,
+                race: true,
+                objective: Some(Objective::ReachDepth(RACE_DEPTH)),
+                block_allowance: None,
+                save_policy: SavePolicy::Never,
+                ..Self::new(GameType::Classic)
+            },
+        }
+    }
+
+    /// Set the structure to build in a blueprint challenge.
+    pub fn with_blueprint(mut self, blueprint: Blueprint) -> Self {
+        self.blueprint = Some(blueprint);
+        self
+    }
+
+    /// Set which day's challenge a daily run is.
+    pub fn with_day(mut self, day: i64) -> Self {
+        self.daily = Some(day);
+        self
+    }
+
+    /// Apply the player's assist options to this run.
+    pub fn with_assists(mut self, assist: &AssistSettings) -> Self {
+        self.decay_multiplier *= assist.decay_percent as f64 / 100.0;
+        self.snap_placement = assist.snap_placement;
+        self.assisted = assist.is_active();
+        self
+    }
+}
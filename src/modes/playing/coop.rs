@@ -0,0 +1,278 @@
+//! The second player in co-op runs, who plays with the keyboard.
+//!
+//! They move a cursor around the grid with the arrow keys, pick a block on the
+//! conveyor with Q and E, grab and place it with space, turn it with Z and X,
+//! and put it back with C.
+
+use super::{
+    events::PlayEvent, geometry::Viewport, HoldInfo, ModePlaying, ScrollAxis, BLOCK_SIZE,
+    BOTTOM_VIEW_SIZE, SHAFT_HEIGHT,
+};
+use crate::{
+    drawutils::{self, hexcolor},
+    ui, Globals,
+};
+
+use cogs_gamedev::int_coords::ICoord;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Cursor color of the player with the mouse
+pub const PLAYER_ONE_COLOR: u32 = ui::FOREGROUND;
+/// Cursor color of the player with the keyboard
+pub const PLAYER_TWO_COLOR: u32 = 0x5fcde4ff;
+
+/// What the keyboard player did on one frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardInput {
+    /// Cursor movement, -1, 0, or 1 on each axis
+    pub dx: i8,
+    pub dy: i8,
+    /// Which way to move the conveyor selection; positive is up the belt
+    pub slot: i8,
+    /// Positive is counterclockwise
+    pub rotate: i8,
+    pub grab: bool,
+    pub cancel: bool,
+    /// Grab the block in this conveyor slot, when playing alone
+    #[serde(default)]
+    pub pick: Option<u8>,
+    /// Scroll the camera; positive is deeper. Only when playing alone.
+    #[serde(default)]
+    pub scroll: i8,
+    /// Buy an anchor, when playing alone
+    #[serde(default)]
+    pub buy_anchor: bool,
+    /// End the run, when playing alone
+    #[serde(default)]
+    pub finish: bool,
+    /// Start or stop recording a placement macro, when playing alone
+    #[serde(default)]
+    pub macro_record: bool,
+    /// Play back the placement macro, when playing alone
+    #[serde(default)]
+    pub macro_play: bool,
+}
+
+impl KeyboardInput {
+    pub fn capture() -> Self {
+        let axis =
+            |neg: KeyCode, pos: KeyCode| is_key_pressed(pos) as i8 - is_key_pressed(neg) as i8;
+        Self {
+            dx: axis(KeyCode::Left, KeyCode::Right),
+            dy: axis(KeyCode::Up, KeyCode::Down),
+            slot: axis(KeyCode::E, KeyCode::Q),
+            rotate: axis(KeyCode::X, KeyCode::Z),
+            grab: is_key_pressed(KeyCode::Space),
+            cancel: is_key_pressed(KeyCode::C),
+            ..Self::default()
+        }
+    }
+
+    /// Read the keyboard of someone playing on their own, who gets all of it.
+    /// See the `keyboard` module for what does what.
+    pub fn capture_solo() -> Self {
+        let either = |a: KeyCode, b: KeyCode| is_key_pressed(a) || is_key_pressed(b);
+        let axis = |neg: bool, pos: bool| pos as i8 - neg as i8;
+        const SLOT_KEYS: [KeyCode; 7] = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+        ];
+        Self {
+            dx: axis(
+                either(KeyCode::Left, KeyCode::A),
+                either(KeyCode::Right, KeyCode::D),
+            ),
+            dy: axis(
+                either(KeyCode::Up, KeyCode::W),
+                either(KeyCode::Down, KeyCode::S),
+            ),
+            slot: 0,
+            rotate: axis(is_key_pressed(KeyCode::E), is_key_pressed(KeyCode::Q)),
+            grab: either(KeyCode::Space, KeyCode::Enter),
+            cancel: is_key_pressed(KeyCode::Backspace),
+            pick: SLOT_KEYS
+                .iter()
+                .position(|&key| is_key_pressed(key))
+                .map(|idx| idx as u8),
+            scroll: axis(
+                is_key_pressed(KeyCode::PageUp),
+                is_key_pressed(KeyCode::PageDown),
+            ),
+            buy_anchor: is_key_pressed(KeyCode::B),
+            finish: is_key_pressed(KeyCode::F),
+            macro_record: is_key_pressed(KeyCode::K),
+            macro_play: is_key_pressed(KeyCode::N),
+        }
+    }
+}
+
+/// Where the keyboard player is and what they're holding.
+#[derive(Clone)]
+pub struct SecondPlayer {
+    pub cursor: ICoord,
+    /// Which conveyor slot they'd grab from
+    pub slot: usize,
+    pub held: Option<HoldInfo>,
+}
+
+impl SecondPlayer {
+    pub fn new() -> Self {
+        Self {
+            cursor: ICoord::new(0, 0),
+            slot: 0,
+            held: None,
+        }
+    }
+}
+
+impl ModePlaying {
+    pub(super) fn handle_second_player(&mut self, input: KeyboardInput) {
+        let cursor = match &self.second {
+            Some(second) => self.move_grid_cursor(second.cursor, input),
+            None => return,
+        };
+        let second = self.second.as_mut().unwrap();
+        second.cursor = cursor;
+
+        // second is borrowed out of self, so this has to wait
+        let mut event = None;
+        match &mut second.held {
+            None => {
+                if input.slot != 0 && !self.conveyor_blocks.is_empty() {
+                    let slot = second.slot as isize + input.slot as isize;
+                    second.slot = slot.clamp(0, self.conveyor_blocks.len() as isize - 1) as usize;
+                }
+                second.slot = second
+                    .slot
+                    .min(self.conveyor_blocks.len().saturating_sub(1));
+
+                let slot = second.slot;
+                let free = slot < self.conveyor_blocks.len()
+                    && self.held.as_ref().map_or(true, |held| held.idx != slot);
+                if input.grab && free && self.cooldown_left == 0 {
+                    second.held = Some(HoldInfo {
+                        idx: slot,
+                        rotation: 0.0,
+                    });
+                    event = Some(PlayEvent::PickedUp);
+                }
+            }
+            Some(info) => {
+                if input.rotate != 0 {
+                    info.rotate(&mut self.conveyor_blocks[info.idx], input.rotate > 0);
+                    event = Some(PlayEvent::Rotated);
+                }
+                info.swing();
+
+                if input.cancel {
+                    second.held = None;
+                    event = Some(PlayEvent::Returned);
+                } else if input.grab {
+                    let idx = info.idx;
+                    let pos = second.cursor;
+                    if self.can_place(&self.conveyor_blocks[idx], pos) {
+                        if let Some(second) = &mut self.second {
+                            second.held = None;
+                        }
+                        self.place_from_conveyor(idx, pos);
+                    } else {
+                        event = Some(PlayEvent::Dropped);
+                    }
+                }
+            }
+        }
+        if let Some(event) = event {
+            self.emit(event);
+        }
+    }
+
+    /// Move a keyboard cursor around the grid, keeping it where blocks can go.
+    pub(super) fn move_grid_cursor(&self, cursor: ICoord, input: KeyboardInput) -> ICoord {
+        let furthest = self.report.max_depth + BOTTOM_VIEW_SIZE;
+        // up the screen is up the structure in upside-down worlds
+        let dy = input.dy as isize * self.config.gravity.sign();
+        let (x_range, y_range) = match self.config.scroll_axis {
+            ScrollAxis::Vertical => {
+                let top = if self.config.tower {
+                    -(self.report.tower_height + 1)
+                } else {
+                    0
+                };
+                (self.profile.widest(), (top, furthest))
+            }
+            ScrollAxis::Horizontal => ((1, furthest), (0, SHAFT_HEIGHT - 1)),
+        };
+        ICoord::new(
+            (cursor.x + input.dx as isize).clamp(x_range.0, x_range.1),
+            (cursor.y + dy).clamp(y_range.0, y_range.1),
+        )
+    }
+
+    /// Draw the keyboard player's cursor and the block they're holding,
+    /// and mark where the mouse player is pointing so they can be told apart.
+    pub(super) fn draw_cursors(&self, view: &Viewport, globals: &Globals) {
+        let second = match &self.second {
+            Some(it) => it,
+            None => return,
+        };
+
+        let (mx, my) = self.last_input.mouse;
+        let (cx, cy) = view.block_to_screen(view.pixel_to_block(mx, my));
+        outline_cell(cx, cy, view.block_size(), hexcolor(PLAYER_ONE_COLOR));
+
+        let (cx, cy) = view.block_to_screen(second.cursor);
+        match &second.held {
+            Some(held) => {
+                let block = &self.conveyor_blocks[held.idx];
+                let alpha = if self.can_place(block, second.cursor) {
+                    0.8
+                } else {
+                    0.4
+                };
+                drawutils::draw_scaled(cx, cy, view.zoom, || {
+                    self.draw_block(
+                        block,
+                        cx,
+                        cy,
+                        Color::new(1.0, 1.0, 1.0, alpha),
+                        held.rotation,
+                        globals,
+                    )
+                });
+            }
+            None if !self.conveyor_blocks.is_empty() => {
+                // point out which block they'd grab
+                let slot_y =
+                    super::CONVEYOR_Y_BOTTOM - second.slot as f32 * super::CONVEYOR_SLOT_HEIGHT;
+                draw_rectangle_lines(
+                    crate::WIDTH - 70.0 + 23.0,
+                    slot_y - 1.0,
+                    BLOCK_SIZE + 2.0,
+                    BLOCK_SIZE + 2.0,
+                    1.0,
+                    hexcolor(PLAYER_TWO_COLOR),
+                );
+            }
+            None => {}
+        }
+        outline_cell(cx, cy, view.block_size(), hexcolor(PLAYER_TWO_COLOR));
+        drawutils::draw_text_px(
+            "P2",
+            cx - view.block_size() / 2.0,
+            cy - view.block_size() / 2.0 - 7.0,
+            hexcolor(PLAYER_TWO_COLOR),
+            globals,
+        );
+    }
+}
+
+/// Outline the grid cell centered here, `size` pixels across on the screen
+pub(super) fn outline_cell(cx: f32, cy: f32, size: f32, color: Color) {
+    draw_rectangle_lines(cx - size / 2.0, cy - size / 2.0, size, size, 1.0, color);
+}
@@ -0,0 +1,192 @@
+//! Things that happen during a frame of a run, for whatever wants to react to them.
+//!
+//! The simulation only says what happened. It doesn't play sounds or pop up
+//! toasts itself; those look through the frame's events afterwards.
+
+use super::{replay::RunEventKind, ModePlaying};
+use crate::{assets::Sounds, drawutils::hexcolor, Globals};
+
+use cogs_gamedev::int_coords::ICoord;
+use macroquad::audio::Sound;
+
+const DUST_COLOR: u32 = 0x9c7a5cc0;
+const BLAST_COLOR: u32 = 0xf2a03dd0;
+const SPARKLE_COLOR: u32 = 0xffee83e0;
+/// Fainter than the dust from actually getting hit
+const STRESS_DUST_COLOR: u32 = 0x9c7a5c70;
+/// How loud the creak before blocks might break is, next to everything else
+const STRESS_VOLUME: f32 = 0.25;
+
+#[derive(Clone, Debug)]
+pub enum PlayEvent {
+    /// Someone picked a block up off the conveyor
+    PickedUp,
+    /// A held block got turned
+    Rotated,
+    /// A held block was let go somewhere it can't go
+    Dropped,
+    /// A held block was put back on the conveyor on purpose
+    Returned,
+    /// A block went into the structure here, linking up on this many sides
+    BlockPlaced { pos: ICoord, links: usize },
+    /// The last placement was taken back
+    Undone,
+    /// Points were spent on an anchor
+    AnchorBought,
+    /// Points were spent putting a block in the hold slot
+    Stashed,
+    /// Points were spent swapping out everything on the conveyor
+    Rerolled,
+    /// This block took a point of damage, from wear or from being chipped at
+    BlockDamaged { pos: ICoord },
+    /// This block wore all the way out and broke
+    BlockBroken { pos: ICoord },
+    /// An anchor wore out
+    AnchorLost,
+    /// This many blocks came loose and started falling
+    ChunkFell { size: usize },
+    /// A row at this depth was filled all the way across
+    RowCompleted { depth: isize },
+    /// A new kind of block can show up on the conveyor now
+    KindUnlocked { name: &'static str },
+    /// An artifact buried here was dug out
+    ArtifactFound { pos: ICoord },
+    /// An explosive block went off here
+    Exploded { pos: ICoord },
+    /// Blocks are about to roll for damage. These are the likeliest to take it.
+    StressTick { at_risk: Vec<ICoord> },
+}
+
+impl PlayEvent {
+    /// What it sounds like, if anything
+    pub fn sound(&self, sounds: &Sounds) -> Option<Sound> {
+        match self {
+            PlayEvent::PickedUp
+            | PlayEvent::Undone
+            | PlayEvent::Returned
+            | PlayEvent::Rerolled
+            | PlayEvent::ArtifactFound { .. } => sounds.pickup,
+            PlayEvent::Rotated | PlayEvent::Dropped => sounds.rotate,
+            PlayEvent::BlockPlaced { .. } | PlayEvent::AnchorBought | PlayEvent::Stashed => {
+                sounds.putdown
+            }
+            PlayEvent::BlockDamaged { .. } => sounds.damage,
+            PlayEvent::ChunkFell { .. }
+            | PlayEvent::StressTick { .. }
+            | PlayEvent::Exploded { .. } => sounds.fall,
+            _ => None,
+        }
+    }
+
+    /// Where it happened, if it was anywhere in particular
+    pub fn positions(&self) -> &[ICoord] {
+        match self {
+            PlayEvent::BlockPlaced { pos, .. }
+            | PlayEvent::BlockDamaged { pos }
+            | PlayEvent::BlockBroken { pos }
+            | PlayEvent::ArtifactFound { pos }
+            | PlayEvent::Exploded { pos } => std::slice::from_ref(pos),
+            PlayEvent::StressTick { at_risk } => at_risk,
+            _ => &[],
+        }
+    }
+
+    /// How loud its sound is, next to the sound effects volume
+    pub fn volume(&self) -> f32 {
+        match self {
+            PlayEvent::StressTick { .. } => STRESS_VOLUME,
+            _ => 1.0,
+        }
+    }
+
+    /// How it goes down in the replay, if it's important enough to be there
+    fn run_event(&self) -> Option<RunEventKind> {
+        match *self {
+            PlayEvent::ChunkFell { size } => Some(RunEventKind::Collapse(size)),
+            PlayEvent::AnchorLost => Some(RunEventKind::AnchorLost),
+            PlayEvent::RowCompleted { depth } => Some(RunEventKind::RowCompleted(depth)),
+            _ => None,
+        }
+    }
+}
+
+impl ModePlaying {
+    /// Say something happened this frame.
+    ///
+    /// The replay log and the toasts hear about it right away, since the run might
+    /// end before the frame does. Everything else reads `frame_events` when it draws.
+    pub(super) fn emit(&mut self, event: PlayEvent) {
+        if let Some(kind) = event.run_event() {
+            self.log_event(kind);
+        }
+        if let PlayEvent::KindUnlocked { name } = event {
+            self.toasts.push(format!("{} UNLOCKED", name));
+        }
+        self.combo.hear(&event);
+        self.frame_events.push(event);
+    }
+
+    /// Kick up dust wherever blocks got knocked around this frame
+    pub(super) fn event_particles(&mut self) {
+        let view = self.viewport();
+        for event in &self.frame_events {
+            let (color, count) = match event {
+                PlayEvent::BlockPlaced { .. } => (DUST_COLOR, 6),
+                PlayEvent::BlockBroken { .. } => (DUST_COLOR, 8),
+                PlayEvent::BlockDamaged { .. } => (DUST_COLOR, 3),
+                PlayEvent::Exploded { .. } => (BLAST_COLOR, 24),
+                PlayEvent::ArtifactFound { .. } => (SPARKLE_COLOR, 12),
+                PlayEvent::StressTick { .. } => (STRESS_DUST_COLOR, 2),
+                _ => continue,
+            };
+            for &pos in event.positions() {
+                let (x, y) = view.block_to_screen(pos);
+                self.particles.burst(x, y, hexcolor(color), count);
+            }
+        }
+    }
+
+    /// Add the last step to the lifetime stats
+    pub(super) fn record_stats(&self, globals: &mut Globals) {
+        let first_step = self.frames_elapsed == 1;
+        let depth = self.report.center_of_mass;
+        globals.stats.update(|stats| {
+            stats.steps_played += 1;
+            if first_step {
+                stats.runs_started += 1;
+            }
+            stats.deepest = stats.deepest.max(depth);
+            for event in &self.frame_events {
+                match *event {
+                    PlayEvent::BlockPlaced { .. } => stats.blocks_placed += 1,
+                    PlayEvent::BlockBroken { .. } => stats.blocks_broken += 1,
+                    PlayEvent::ChunkFell { size } => {
+                        stats.collapses += 1;
+                        stats.blocks_lost += size as u64;
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    /// Queue up the sounds for the last step's events, quieter the further off screen they were
+    pub fn cue_event_sounds(&self, globals: &mut Globals) {
+        let (player, sounds) = (&mut globals.audio, &globals.assets.sounds);
+        let view = self.viewport();
+        for event in &self.frame_events {
+            let sound = match event.sound(sounds) {
+                Some(it) => it,
+                None => continue,
+            };
+            // anything that didn't happen anywhere in particular is heard in full
+            let offscreen = event
+                .positions()
+                .iter()
+                .map(|&pos| view.blocks_offscreen(pos))
+                .reduce(f32::min)
+                .unwrap_or(0.0);
+            player.cue(sound, event.volume(), offscreen);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    drawutils::{self, hexcolor, OffscreenTarget},
+    ui, Globals, HEIGHT, WIDTH,
+};
+
+use macroquad::prelude::*;
+
+use std::collections::VecDeque;
+
+/// How many snapshots are kept. Once it's full, the oldest one gets recycled.
+const MAX_SHOTS: usize = 6;
+/// How much smaller the thumbnails are than the screen
+const THUMBNAIL_SCALE: f32 = 8.0;
+/// Space between thumbnails
+const THUMBNAIL_GAP: f32 = 4.0;
+
+/// Snapshots of the viewport taken as a run crosses depth milestones.
+#[derive(Clone, Default)]
+pub struct Filmstrip {
+    /// The depth each was taken at, and what it looked like
+    shots: VecDeque<(i32, OffscreenTarget)>,
+}
+
+impl Filmstrip {
+    /// Take a snapshot of whatever `draw` draws.
+    pub fn capture(&mut self, depth: i32, draw: impl FnOnce()) {
+        let target = if self.shots.len() >= MAX_SHOTS {
+            // reuse the oldest one's render target instead of making another
+            self.shots.pop_front().unwrap().1
+        } else {
+            OffscreenTarget::screen()
+        };
+        drawutils::draw_offscreen(target.get(), draw);
+        self.shots.push_back((depth, target));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shots.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shots.len()
+    }
+
+    /// Draw the snapshots in a row, centered horizontally, with their depths under them.
+    pub fn draw(&self, y: f32, globals: &Globals) {
+        let thumb_w = WIDTH / THUMBNAIL_SCALE;
+        let thumb_h = HEIGHT / THUMBNAIL_SCALE;
+        let total_w = self.shots.len() as f32 * (thumb_w + THUMBNAIL_GAP) - THUMBNAIL_GAP;
+        let left = ((WIDTH - total_w) / 2.0).round();
+
+        for (idx, (depth, target)) in self.shots.iter().enumerate() {
+            let x = left + idx as f32 * (thumb_w + THUMBNAIL_GAP);
+            draw_texture_ex(
+                target.texture(),
+                x,
+                y,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(thumb_w, thumb_h)),
+                    ..Default::default()
+                },
+            );
+            draw_rectangle_lines(
+                x - 1.0,
+                y - 1.0,
+                thumb_w + 2.0,
+                thumb_h + 2.0,
+                1.0,
+                hexcolor(ui::BACKGROUND),
+            );
+
+            let label = depth.to_string();
+            let label_x = (x + (thumb_w - drawutils::text_width_px(&label)) / 2.0).round();
+            drawutils::draw_text_px(
+                &label,
+                label_x,
+                y + thumb_h + 3.0,
+                hexcolor(ui::BACKGROUND),
+                globals,
+            );
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Pointing out the row that's closest to being filled in,
+//! and which cells it's still missing.
+
+use super::{geometry::Viewport, ModePlaying, ScrollAxis};
+use crate::{
+    drawutils::{self, hexcolor},
+    ui, Globals, HEIGHT, WIDTH,
+};
+
+use cogs_gamedev::int_coords::ICoord;
+
+impl ModePlaying {
+    /// The row with the fewest cells left to fill, and those cells.
+    /// Ties go to the shallower row. Rows nothing's been put in yet don't count.
+    pub(super) fn nearest_row(&self) -> Option<(isize, Vec<ICoord>)> {
+        let report = &self.report;
+        let (&depth, _) = report
+            .line_counts
+            .iter()
+            .filter(|&(&depth, _)| depth >= 0 && report.fill(depth) < 1.0)
+            .min_by_key(|&(&depth, &count)| (report.line_width(depth) - count, depth))?;
+        Some((depth, self.line_gaps(depth)))
+    }
+
+    /// Draw how many cells the nearest row needs, with arrows at the ones on screen.
+    pub(super) fn draw_row_forecast(&self, view: &Viewport, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let (depth, gaps) = match self.nearest_row() {
+            Some(it) => it,
+            None => return,
+        };
+        let fg = hexcolor(ui::FOREGROUND);
+        for &gap in &gaps {
+            let (cx, cy) = view.block_to_screen(gap);
+            let tip = cy - view.block_size() / 2.0;
+            draw_triangle(
+                vec2(cx, tip),
+                vec2(cx - 3.0, tip - 4.0),
+                vec2(cx + 3.0, tip - 4.0),
+                fg,
+            );
+        }
+
+        let label = if gaps.len() == 1 {
+            "1 CELL REMAINING".to_owned()
+        } else {
+            format!("{} CELLS REMAINING", gaps.len())
+        };
+        let width = drawutils::text_width_px(&label) + 8.0;
+        // next to the row, but kept on screen so it still says something's there
+        let (x, y) = match self.config.scroll_axis {
+            ScrollAxis::Vertical => {
+                let (left, _) = self.profile.span(depth);
+                let (edge, cy) = view.block_to_screen(ICoord::new(left, depth));
+                let x = edge - view.block_size() / 2.0 - width - 2.0;
+                (x, (cy - 5.0).clamp(32.0, HEIGHT - 24.0))
+            }
+            ScrollAxis::Horizontal => {
+                let (cx, top) = view.block_to_screen(ICoord::new(depth, 0));
+                let x = (cx - width / 2.0).clamp(2.0, WIDTH - 72.0 - width);
+                (x, top - view.block_size() / 2.0 - 16.0)
+            }
+        };
+        let mut bg = hexcolor(ui::BACKGROUND);
+        bg.a = 0.6;
+        draw_rectangle(x, y, width, 11.0, bg);
+        drawutils::draw_text_px(&label, x + 4.0, y + 3.0, fg, globals);
+    }
+}
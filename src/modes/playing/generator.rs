@@ -0,0 +1,149 @@
+//! Building structures without anyone placing them, so practice runs can start deep.
+//!
+//! It goes row by row out from the anchors, only putting a block where the
+//! placement rules would allow it and it links up with what's already there.
+//! So the whole thing stays up once the run starts, at least until it wears out.
+
+use super::{
+    blocks::{Block, BlockKind, Connector},
+    chasm::ChasmProfile,
+    BlockMap, Gravity, ModePlaying, ScrollAxis, CHASM_WIDTH,
+};
+
+use cogs_gamedev::{directions::Direction4, int_coords::ICoord};
+use itertools::Itertools;
+use rand::{prelude::SliceRandom, Rng};
+
+/// Chance a side with nothing next to it yet gets a connector anyway
+const LOOSE_CONNECTOR_CHANCE: f64 = 0.75;
+
+/// Builds a structure in a chasm, hanging from its anchors or standing on them.
+///
+/// Only chasms for now; tunnels grow sideways and would need their own rules.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    /// How many rows down it goes
+    depth: isize,
+    /// Chance each cell that could hold a block gets one
+    density: f64,
+    profile: ChasmProfile,
+    gravity: Gravity,
+    /// If there are any, the only kinds of block to use
+    kinds: Vec<BlockKind>,
+}
+
+impl Generator {
+    pub fn new(depth: isize, density: f64) -> Self {
+        Self {
+            depth,
+            density: density.clamp(0.0, 1.0),
+            profile: ChasmProfile::straight(CHASM_WIDTH),
+            gravity: Gravity::Down,
+            kinds: Vec::new(),
+        }
+    }
+
+    pub fn with_profile(mut self, profile: ChasmProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_gravity(mut self, gravity: Gravity) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    pub fn with_kinds(mut self, kinds: Vec<BlockKind>) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    /// Fill in the structure around the anchors that are already there.
+    /// Returns how many blocks went in.
+    ///
+    /// Every row gets at least one block if it can, so with any density it
+    /// reaches the full depth unless there was nothing to link to.
+    pub fn build<R: Rng + ?Sized>(&self, stable_blocks: &mut BlockMap, rng: &mut R) -> usize {
+        let mut placed = 0;
+        for y in 0..self.depth {
+            let (left, right) = self.profile.span(y);
+            let mut columns = (left..=right).collect_vec();
+            // in a random order, so the links don't all lean the same way
+            columns.shuffle(rng);
+            let mut row_empty = true;
+            for x in columns {
+                let pos = ICoord::new(x, y);
+                let block = self.linked_block(stable_blocks, pos, rng);
+                // always put at least one in, so the rows after have something to go on
+                let wanted = row_empty || rng.gen_bool(self.density);
+                if wanted && self.can_place(stable_blocks, pos, &block) {
+                    stable_blocks.insert(pos, block);
+                    row_empty = false;
+                    placed += 1;
+                }
+            }
+            if row_empty {
+                // nothing in the row before to link to, so it can't go any further
+                break;
+            }
+        }
+        debug_assert!(
+            is_held_up(stable_blocks, self.gravity),
+            "generated a structure that would fall"
+        );
+        placed
+    }
+
+    /// Whether a player could put this block here and have it stay
+    fn can_place(&self, stable_blocks: &BlockMap, pos: ICoord, block: &Block) -> bool {
+        block.is_valid_pos(pos, ScrollAxis::Vertical, &self.profile)
+            && !stable_blocks.contains_key(&pos)
+            && ModePlaying::is_stable(stable_blocks, pos, block, self.gravity)
+            // it has to be linked in, not just resting on something
+            && ModePlaying::link_count(stable_blocks, pos, block) > 0
+    }
+
+    /// Make a block to go here, linking to every neighbor that has a connector facing it.
+    fn linked_block<R: Rng + ?Sized>(
+        &self,
+        stable_blocks: &BlockMap,
+        pos: ICoord,
+        rng: &mut R,
+    ) -> Block {
+        let mut connectors = [None, None, None, None];
+        for &dir in Direction4::DIRECTIONS.iter() {
+            connectors[dir as usize] = match stable_blocks.get(&(pos + dir.deltas())) {
+                Some(neighbor) => neighbor.connectors[dir.flip() as usize]
+                    .as_ref()
+                    .map(|conn| Connector {
+                        shape: conn.shape,
+                        sticks_out: !conn.sticks_out,
+                    }),
+                // the next row needs something to link to
+                None if dir == Direction4::South || rng.gen_bool(LOOSE_CONNECTOR_CHANCE) => {
+                    Some(rng.gen())
+                }
+                None => None,
+            };
+        }
+        let kind = match self.kinds.choose(rng) {
+            Some(kind) => kind.clone(),
+            None => rng.gen(),
+        };
+        let mut block = Block {
+            connectors,
+            kind,
+            damage: 0,
+        };
+        // it's been there a while
+        block.damage = rng.gen_range(0..=block.resilience() / 2);
+        block
+    }
+}
+
+/// Whether every block is held up by the anchors, through links or by resting on something.
+/// The same rules the run uses to decide what falls.
+pub fn is_held_up(stable_blocks: &BlockMap, gravity: Gravity) -> bool {
+    let held = ModePlaying::held_up(stable_blocks, gravity);
+    stable_blocks.keys().all(|pos| held.contains(pos))
+}
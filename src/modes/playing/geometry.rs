@@ -0,0 +1,278 @@
+//! Going between where things are in the world and where they are on the screen.
+//!
+//! The world is measured in blocks, with the anchors around row 0.
+//! Anything that needs to know where a block gets drawn, or which cell the
+//! mouse is over, should ask a [`Viewport`] instead of redoing the math.
+//!
+//! Positions come out in the viewport's own pixels, as if it filled the screen
+//! at 1x. Drawing inside [`Viewport::draw_in`] puts them where the zoom and
+//! offset say, so the same world can be drawn with more than one camera.
+
+use super::{Gravity, ScrollAxis, BLOCK_SIZE, SCREEN_HEIGHT, SCREEN_WIDTH, SHAFT_HEIGHT};
+use crate::{HEIGHT, WIDTH};
+
+use cogs_gamedev::int_coords::ICoord;
+use macroquad::prelude::*;
+
+use std::ops::Range;
+
+/// How the world is laid out on the screen right now.
+#[derive(Clone, Copy, Debug)]
+pub struct Viewport {
+    /// Which way the camera scrolls
+    pub axis: ScrollAxis,
+    /// Upside-down worlds are drawn mirrored, so things still fall down the screen
+    pub gravity: Gravity,
+    /// How far along the scroll axis the middle of the view is, in blocks.
+    /// At 0, block (0, 0) is dead center.
+    pub scroll_depth: f32,
+    /// How big things are drawn. 1 is one block to 16 pixels.
+    pub zoom: f32,
+    /// Where on the screen the middle of the view goes
+    pub offset: Vec2,
+}
+
+impl Viewport {
+    /// The whole screen at 1x
+    pub fn new(axis: ScrollAxis, gravity: Gravity, scroll_depth: f32) -> Self {
+        Self {
+            axis,
+            gravity,
+            scroll_depth,
+            zoom: 1.0,
+            offset: vec2(WIDTH / 2.0, HEIGHT / 2.0),
+        }
+    }
+
+    /// Draw something in this view's pixels, zoomed and moved to where the view goes.
+    pub fn draw_in(&self, draw: impl FnOnce()) {
+        let transform = Mat4::from_translation(vec3(self.offset.x, self.offset.y, 0.0))
+            * Mat4::from_scale(vec3(self.zoom, self.zoom, 1.0))
+            * Mat4::from_translation(vec3(-WIDTH / 2.0, -HEIGHT / 2.0, 0.0));
+        unsafe { get_internal_gl() }
+            .quad_gl
+            .push_model_matrix(transform);
+        draw();
+        unsafe { get_internal_gl() }.quad_gl.pop_model_matrix();
+    }
+
+    /// Where a point in this view's pixels ends up on the screen
+    pub fn pixel_to_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            (x - WIDTH / 2.0) * self.zoom + self.offset.x,
+            (y - HEIGHT / 2.0) * self.zoom + self.offset.y,
+        )
+    }
+
+    /// Which point in this view's pixels is at this point on the screen
+    pub fn screen_to_pixel(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            (x - self.offset.x) / self.zoom + WIDTH / 2.0,
+            (y - self.offset.y) / self.zoom + HEIGHT / 2.0,
+        )
+    }
+
+    /// Where in the view this depth is, along the scroll axis
+    pub fn depth_to_pixel(&self, depth: f32) -> f32 {
+        let offset = (depth - self.scroll_depth) * self.gravity.sign() as f32;
+        match self.axis {
+            ScrollAxis::Vertical => offset * BLOCK_SIZE + HEIGHT / 2.0,
+            ScrollAxis::Horizontal => offset * BLOCK_SIZE + WIDTH / 2.0,
+        }
+    }
+
+    /// Where in the view the center of this (maybe fractional) block position is
+    pub fn world_to_pixel(&self, x: f32, y: f32) -> (f32, f32) {
+        match self.axis {
+            ScrollAxis::Vertical => (x * BLOCK_SIZE + WIDTH / 2.0, self.depth_to_pixel(y)),
+            ScrollAxis::Horizontal => {
+                // the tunnel runs through the middle of the screen
+                let cy = (y - shaft_middle()) * BLOCK_SIZE + HEIGHT / 2.0;
+                (self.depth_to_pixel(x), cy)
+            }
+        }
+    }
+
+    /// Where in the view the center of this block is
+    pub fn block_to_pixel(&self, pos: ICoord) -> (f32, f32) {
+        self.world_to_pixel(pos.x as f32, pos.y as f32)
+    }
+
+    /// How many blocks past the edge of the view this block is, or 0 if it's in it
+    pub fn blocks_offscreen(&self, pos: ICoord) -> f32 {
+        let (x, y) = self.block_to_pixel(pos);
+        let past = |at: f32, size: f32| (-at).max(at - size).max(0.0);
+        past(x, WIDTH).max(past(y, HEIGHT)) / BLOCK_SIZE
+    }
+
+    /// How big a block comes out on the screen, for drawing outside [`Viewport::draw_in`]
+    pub fn block_size(&self) -> f32 {
+        BLOCK_SIZE * self.zoom
+    }
+
+    /// Where on the screen the center of this block is, for drawing outside [`Viewport::draw_in`]
+    pub fn block_to_screen(&self, pos: ICoord) -> (f32, f32) {
+        let (x, y) = self.block_to_pixel(pos);
+        self.pixel_to_screen(x, y)
+    }
+
+    /// Which block this point on the screen is over.
+    ///
+    /// This is exactly the other way around from [`Viewport::block_to_pixel`],
+    /// whatever the scroll, so a block's center always maps back to that block.
+    pub fn pixel_to_block(&self, x: f32, y: f32) -> ICoord {
+        let (x, y) = self.screen_to_pixel(x, y);
+        let from_middle_x = (x - WIDTH / 2.0) / BLOCK_SIZE;
+        let from_middle_y = (y - HEIGHT / 2.0) / BLOCK_SIZE;
+        match self.axis {
+            ScrollAxis::Vertical => {
+                ICoord::new(nearest_cell(from_middle_x), self.depth_at(from_middle_y))
+            }
+            ScrollAxis::Horizontal => ICoord::new(
+                self.depth_at(from_middle_x),
+                nearest_cell(from_middle_y + shaft_middle()),
+            ),
+        }
+    }
+
+    /// The depth of the cell this many blocks from the middle of the view, along the scroll axis
+    fn depth_at(&self, from_middle: f32) -> isize {
+        // upside-down worlds count depth up the screen
+        let sign = self.gravity.sign();
+        sign * nearest_cell(from_middle + sign as f32 * self.scroll_depth)
+    }
+
+    /// The rows with any of them in the view, plus one more each way,
+    /// counting as if gravity was down.
+    pub fn visible_rows(&self) -> Range<isize> {
+        let across = self.zoomed(SCREEN_HEIGHT);
+        match self.axis {
+            ScrollAxis::Vertical => around(self.scroll_depth.floor() as isize, across),
+            ScrollAxis::Horizontal => around(SHAFT_HEIGHT / 2, across),
+        }
+    }
+
+    /// The columns with any of them in the view, plus one more each way.
+    pub fn visible_cols(&self) -> Range<isize> {
+        let across = self.zoomed(SCREEN_WIDTH);
+        match self.axis {
+            ScrollAxis::Vertical => around(0, across),
+            ScrollAxis::Horizontal => around(self.scroll_depth.floor() as isize, across),
+        }
+    }
+
+    /// How many cells fit across the view at this zoom, if that many fit at 1x
+    fn zoomed(&self, cells: isize) -> isize {
+        (cells as f32 / self.zoom).ceil() as isize
+    }
+}
+
+/// The row the middle of a tunnel is at, between two rows since it's an even number high
+fn shaft_middle() -> f32 {
+    (SHAFT_HEIGHT - 1) as f32 / 2.0
+}
+
+/// The cell whose center is closest to this position, counted in blocks.
+/// Right on an edge goes to the cell further along.
+fn nearest_cell(blocks: f32) -> isize {
+    (blocks + 0.5).floor() as isize
+}
+
+/// Cells spanning a screen this many cells across, centered on this one, with one spare each way
+fn around(middle: isize, across: isize) -> Range<isize> {
+    let first = middle - across / 2;
+    first - 1..first + across + 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every kind of viewport, at scrolls on and between rows
+    fn viewports() -> Vec<Viewport> {
+        let scrolls = (-40..=400)
+            .map(|quarter| quarter as f32 / 4.0)
+            .chain([0.1, 0.37, 0.5, 12.49, 12.51, 99.99]);
+        let mut out = Vec::new();
+        for scroll_depth in scrolls {
+            for &axis in &[ScrollAxis::Vertical, ScrollAxis::Horizontal] {
+                for &gravity in &[Gravity::Down, Gravity::Up] {
+                    let view = Viewport::new(axis, gravity, scroll_depth);
+                    out.push(view);
+                    out.push(Viewport {
+                        zoom: 2.0,
+                        offset: vec2(80.0, 60.0),
+                        ..view
+                    });
+                    out.push(Viewport { zoom: 0.5, ..view });
+                }
+            }
+        }
+        out
+    }
+
+    /// Every cell with its center on the screen
+    fn on_screen(view: &Viewport) -> Vec<ICoord> {
+        let mut out = Vec::new();
+        for x in view.visible_cols() {
+            for y in view.visible_rows() {
+                let pos = ICoord::new(x, y);
+                let (sx, sy) = view.block_to_screen(pos);
+                if (0.0..WIDTH).contains(&sx) && (0.0..HEIGHT).contains(&sy) {
+                    out.push(pos);
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn block_centers_round_trip() {
+        for view in viewports() {
+            for pos in on_screen(&view) {
+                let (sx, sy) = view.block_to_screen(pos);
+                assert_eq!(
+                    view.pixel_to_block(sx, sy),
+                    pos,
+                    "center of {:?} in {:?}",
+                    pos,
+                    view
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn whole_cell_maps_to_its_block() {
+        for view in viewports() {
+            // just inside the edges, where the old rounding went wrong
+            let reach = (BLOCK_SIZE / 2.0 - 0.01) * view.zoom;
+            for pos in on_screen(&view) {
+                let (sx, sy) = view.block_to_screen(pos);
+                for &(dx, dy) in &[(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+                    let (px, py) = (sx + dx * reach, sy + dy * reach);
+                    assert_eq!(
+                        view.pixel_to_block(px, py),
+                        pos,
+                        "({}, {}) near {:?} in {:?}",
+                        px,
+                        py,
+                        pos,
+                        view
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cells_are_visible() {
+        for view in viewports() {
+            assert!(
+                !on_screen(&view).is_empty(),
+                "nothing on screen in {:?}",
+                view
+            );
+        }
+    }
+}
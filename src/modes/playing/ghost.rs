@@ -0,0 +1,84 @@
+//! A see-through copy of the best run of the same kind, built up alongside the run
+//! so the player can race themselves.
+//!
+//! It's that run's replay, played back a step for every step of this one.
+//! It can't be touched; it's only drawn.
+
+use super::{geometry::Viewport, GameType, InputFrame, ModePlaying, Replay, ScrollAxis};
+use crate::{drawutils::hexcolor, Globals, HEIGHT, WIDTH};
+
+use macroquad::prelude::*;
+
+/// Tint of the ghost's blocks
+const GHOST_COLOR: u32 = 0xcbdbfc50;
+/// Color of the line for how deep the ghost has gotten
+const GHOST_DEPTH_COLOR: u32 = 0xcbdbfc90;
+
+/// The best run, playing back.
+#[derive(Clone)]
+pub struct Ghost {
+    run: Box<ModePlaying>,
+    /// Input for every step, in order
+    inputs: Vec<InputFrame>,
+    /// Index of the next input to play
+    cursor: usize,
+}
+
+impl Ghost {
+    /// The ghost of the best run of this kind, if there is one
+    pub fn load(game_type: GameType) -> Option<Self> {
+        let replay = Replay::load_ghost(game_type)?;
+        Some(Self {
+            run: Box::new(ModePlaying::for_replay(&replay.header)),
+            inputs: replay.inputs.frames().collect(),
+            cursor: 0,
+        })
+    }
+
+    /// Play the ghost's next step.
+    ///
+    /// It stops one short of the end, so it sits there with its structure up
+    /// instead of wrapping up and going off to its results.
+    pub fn step(&mut self, globals: &mut Globals) {
+        if self.cursor + 1 >= self.inputs.len() {
+            self.run.idle();
+            return;
+        }
+        let input = self.inputs[self.cursor];
+        self.cursor += 1;
+        // where it ends up is already decided; there's nowhere for it to go
+        let _ = self.run.step(input, globals);
+    }
+}
+
+impl ModePlaying {
+    /// Bring up the ghost at the start of a fresh run, if the player wants one and there is one.
+    pub(super) fn summon_ghost(&mut self, globals: &Globals) {
+        if self.just_started && !self.resumed && self.ghost.is_none() && globals.settings.ghost {
+            self.ghost = Ghost::load(self.config.game_type).map(Box::new);
+        }
+    }
+
+    /// Draw the ghost's blocks where this run doesn't have any, and how deep it's gotten.
+    pub(super) fn draw_ghost(&self, view: &Viewport, globals: &Globals) {
+        let ghost = match &self.ghost {
+            Some(it) => &it.run,
+            None => return,
+        };
+        let on_screen = ghost
+            .stable_blocks
+            .in_area(view.visible_cols(), view.visible_rows())
+            .filter(|(pos, _)| !self.stable_blocks.contains_key(pos));
+        for (&pos, block) in on_screen {
+            let (cx, cy) = view.block_to_pixel(pos);
+            self.draw_block(block, cx, cy, hexcolor(GHOST_COLOR), 0.0, globals);
+        }
+
+        let pixel_depth = view.depth_to_pixel(ghost.report.center_of_mass).round() + 0.5;
+        let color = hexcolor(GHOST_DEPTH_COLOR);
+        match self.config.scroll_axis {
+            ScrollAxis::Vertical => draw_line(0.0, pixel_depth, WIDTH, pixel_depth, 1.0, color),
+            ScrollAxis::Horizontal => draw_line(pixel_depth, 0.0, pixel_depth, HEIGHT, 1.0, color),
+        }
+    }
+}
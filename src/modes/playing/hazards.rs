@@ -0,0 +1,87 @@
+//! Pockets of water underground, in flooded runs.
+//!
+//! Blocks under water wear down faster, and loose scaffolding floats up instead of falling.
+//!
+//! Where the pockets are only depends on the run's seed, so they're worked out again
+//! instead of being saved. They get their own randomness from it, so having them
+//! doesn't change anything else about the run.
+
+use super::RunConfig;
+use crate::random;
+
+use cogs_gamedev::int_coords::ICoord;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+/// The chasm is split into bands this many rows tall, with at most one pocket each
+const BAND_HEIGHT: isize = 16;
+/// How many bands down from the surface the pockets start
+const FIRST_BAND: isize = 1;
+/// How many bands have a chance at a pocket
+const BAND_COUNT: isize = 30;
+/// Chance any one band has a pocket in it
+const POCKET_CHANCE: f64 = 0.6;
+/// For [`random::derived_seed`]
+const SEED_SALT: u64 = 0x5741_5445_5221;
+
+/// A rectangle of water, in block coordinates. Both ends are inclusive.
+#[derive(Clone, Copy, Debug)]
+pub struct WaterPocket {
+    pub left: isize,
+    pub right: isize,
+    pub top: isize,
+    pub bottom: isize,
+}
+
+impl WaterPocket {
+    pub fn contains(&self, pos: ICoord) -> bool {
+        (self.left..=self.right).contains(&pos.x) && (self.top..=self.bottom).contains(&pos.y)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Hazards {
+    pockets: Vec<WaterPocket>,
+}
+
+impl Hazards {
+    /// Lay out the hazards for a run with this seed. Runs without water get none.
+    pub fn generate(seed: u64, config: &RunConfig) -> Self {
+        if !config.water {
+            return Self::default();
+        }
+        let mut rng = SmallRng::seed_from_u64(random::derived_seed(seed, SEED_SALT));
+        let width = config.chasm_width;
+        let pockets = (FIRST_BAND..FIRST_BAND + BAND_COUNT)
+            .filter_map(|band| {
+                // roll everything whether there's a pocket or not,
+                // so each band gets the same rolls whatever the ones before it did
+                let pocket_height = rng.gen_range(2..=4);
+                let pocket_width = rng.gen_range(3.min(width)..=width);
+                let top = band * BAND_HEIGHT + rng.gen_range(0..BAND_HEIGHT - pocket_height);
+                let left = -(width / 2) + rng.gen_range(0..=width - pocket_width);
+                if !rng.gen_bool(POCKET_CHANCE) {
+                    return None;
+                }
+                Some(WaterPocket {
+                    left,
+                    right: left + pocket_width - 1,
+                    top,
+                    bottom: top + pocket_height - 1,
+                })
+            })
+            .collect();
+        Self { pockets }
+    }
+
+    /// Whether this spot is under water
+    pub fn is_submerged(&self, pos: ICoord) -> bool {
+        self.pockets.iter().any(|pocket| pocket.contains(pos))
+    }
+
+    /// Whether this spot is in the top row of the water it's in, for drawing the surface
+    pub fn is_surface(&self, pos: ICoord) -> bool {
+        self.pockets
+            .iter()
+            .any(|pocket| pocket.contains(pos) && pos.y == pocket.top)
+    }
+}
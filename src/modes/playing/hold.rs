@@ -0,0 +1,161 @@
+//! Stashing a block off to the side of the conveyor, and rerolling everything on it.
+//!
+//! Both cost points, so they're for getting out of a bad spot,
+//! not for fishing around for the perfect block.
+
+use super::{blocks::Block, events::PlayEvent, ModePlaying, CONVEYOR_SLOT_HEIGHT};
+use crate::{
+    drawutils::{self, hexcolor},
+    ui, Globals, WIDTH,
+};
+
+use macroquad::math::{vec2, Rect};
+use serde::{Deserialize, Serialize};
+
+/// Points it takes to put a block in the hold slot
+const HOLD_COST: u32 = 10;
+/// Points it takes to reroll the conveyor
+const REROLL_COST: u32 = 40;
+/// How many times the conveyor can be rerolled in one run
+const REROLLS_PER_RUN: u32 = 3;
+
+/// The block stashed next to the conveyor, and how many rerolls have gone.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Hold {
+    stashed: Option<Block>,
+    rerolls_used: u32,
+}
+
+impl Hold {
+    pub fn rerolls_left(&self) -> u32 {
+        REROLLS_PER_RUN.saturating_sub(self.rerolls_used)
+    }
+}
+
+impl ModePlaying {
+    /// Put the block from this conveyor slot in the hold slot.
+    /// Whatever was in there already takes its place on the conveyor.
+    pub(super) fn stash(&mut self, idx: usize) {
+        if self.points < HOLD_COST {
+            self.toasts
+                .push(format!("NEED {} POINTS TO HOLD A BLOCK", HOLD_COST));
+            self.emit(PlayEvent::Returned);
+            return;
+        }
+        match self.hold.stashed.take() {
+            Some(swapped) => {
+                let block = std::mem::replace(&mut self.conveyor_blocks[idx], swapped);
+                self.hold.stashed = Some(block);
+            }
+            None => {
+                let block = self.take_from_conveyor(idx);
+                self.hold.stashed = Some(block);
+                self.top_up_conveyor();
+            }
+        }
+        self.points -= HOLD_COST;
+        // undoing from before this would put the block in two places at once
+        self.undo.clear();
+        self.emit(PlayEvent::Stashed);
+    }
+
+    /// Swap everything on the conveyor for fresh blocks.
+    pub(super) fn reroll(&mut self) {
+        if self.conveyor_blocks.is_empty() {
+            return;
+        }
+        if self.hold.rerolls_left() == 0 {
+            self.toasts.push("NO REROLLS LEFT");
+            return;
+        }
+        if self.points < REROLL_COST {
+            self.toasts
+                .push(format!("NEED {} POINTS TO REROLL", REROLL_COST));
+            return;
+        }
+        // the blocks are about to change under anyone's hand
+        self.held = None;
+        if let Some(second) = &mut self.second {
+            second.held = None;
+        }
+        for block in self.conveyor_blocks.iter_mut() {
+            *block = self.spawner.next();
+        }
+        // they come up fresh from the bottom of the belt
+        self.conveyor_offsets = vec![CONVEYOR_SLOT_HEIGHT; self.conveyor_blocks.len()];
+        self.points -= REROLL_COST;
+        self.hold.rerolls_used += 1;
+        self.undo.clear();
+        self.emit(PlayEvent::Rerolled);
+    }
+
+    /// Draw the hold slot and the reroll button, off the top corner of the conveyor.
+    pub(super) fn draw_hold(&self, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let (mx, my) = self.last_input.mouse;
+        let slot = hold_slot_rect();
+        draw_texture(globals.assets.textures.hold_slot, slot.x, slot.y, WHITE);
+        if let Some(block) = &self.hold.stashed {
+            let (cx, cy) = (slot.x + slot.w / 2.0, slot.y + slot.h / 2.0);
+            self.draw_block(block, cx, cy, WHITE, 0.0, globals);
+        }
+        if self.held.is_some() && slot.contains(vec2(mx, my)) {
+            // letting go here puts it in
+            draw_rectangle_lines(
+                slot.x - 1.0,
+                slot.y - 1.0,
+                slot.w + 2.0,
+                slot.h + 2.0,
+                1.0,
+                hexcolor(ui::FOREGROUND),
+            );
+        }
+
+        let reroll = reroll_rect();
+        let usable = self.hold.rerolls_left() > 0 && self.points >= REROLL_COST;
+        let alpha = if usable { 1.0 } else { 0.4 };
+        draw_texture(
+            globals.assets.textures.reroll,
+            reroll.x,
+            reroll.y,
+            Color::new(1.0, 1.0, 1.0, alpha),
+        );
+        drawutils::draw_number(
+            self.hold.rerolls_left() as i32,
+            reroll.x + reroll.w + 4.0,
+            reroll.y + reroll.h - 5.0,
+            globals,
+        );
+
+        let tip = if slot.contains(vec2(mx, my)) {
+            Some(format!("HOLD {}", HOLD_COST))
+        } else if reroll.contains(vec2(mx, my)) {
+            Some(format!("REROLL {}", REROLL_COST))
+        } else {
+            None
+        };
+        if let Some(tip) = tip {
+            let width = drawutils::text_width_px(&tip) + 8.0;
+            let x = slot.x - width - 2.0;
+            ui::draw_panel(Rect::new(x, my - 5.0, width, 11.0));
+            drawutils::draw_text_px(&tip, x + 4.0, my - 3.0, hexcolor(ui::FOREGROUND), globals);
+        }
+    }
+}
+
+/// Letting go of a block here stashes it.
+pub(super) fn hold_slot_rect() -> Rect {
+    Rect::new(WIDTH - 70.0 - 22.0, 4.0, 20.0, 20.0)
+}
+
+/// Clicking it rerolls the conveyor.
+pub(super) fn reroll_rect() -> Rect {
+    Rect::new(WIDTH - 70.0 - 20.0, 28.0, 16.0, 16.0)
+}
+
+/// Whether this spot is on the hold slot or the reroll button,
+/// so clicks there don't go through to the chasm underneath.
+pub(super) fn covers(mx: f32, my: f32) -> bool {
+    hold_slot_rect().contains(vec2(mx, my)) || reroll_rect().contains(vec2(mx, my))
+}
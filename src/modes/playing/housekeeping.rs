@@ -0,0 +1,123 @@
+//! Keeping long runs from slowly eating memory, and showing where it's going.
+//!
+//! Press F3 during a run to see how big the main containers are.
+
+use super::{blocks::Block, replay::RunEventKind, FallingBlockChunk, ModePlaying, RunEvent};
+use crate::{
+    drawutils::{self, hexcolor},
+    ui, Globals, HEIGHT,
+};
+
+use cogs_gamedev::int_coords::ICoord;
+use macroquad::prelude::*;
+
+use std::mem::size_of;
+
+/// How often housekeeping runs, in frames
+pub const HOUSEKEEPING_INTERVAL: u64 = 1800;
+/// Most events kept in the log; past this, the oldest are forgotten
+const EVENT_LOG_MAX: usize = 256;
+/// Collapses this many frames apart or closer get logged as one
+const COLLAPSE_MERGE_FRAMES: u64 = 60;
+
+impl ModePlaying {
+    /// Tidy up after things that only ever grow.
+    ///
+    /// None of this changes how the run plays out.
+    pub(super) fn housekeeping(&mut self) {
+        self.falling_blocks.retain(|chunk| !chunk.blocks.is_empty());
+        self.falling_blocks.shrink_to_fit();
+        // after a big collapse the map can be mostly empty space
+        if self.stable_blocks.capacity() > self.stable_blocks.len() * 4 + 64 {
+            self.stable_blocks.shrink_to_fit();
+        }
+        if self.events.len() > EVENT_LOG_MAX {
+            compact_events(&mut self.events);
+        }
+    }
+
+    /// Draw roughly how much memory the biggest things in the run take up.
+    pub(super) fn draw_memory_report(&self, globals: &Globals) {
+        let falling_blocks: usize = self
+            .falling_blocks
+            .iter()
+            .map(|chunk| chunk.blocks.capacity())
+            .sum();
+        let lines = [
+            format!(
+                "BLOCKS {} ({})",
+                self.stable_blocks.len(),
+                kilobytes(self.stable_blocks.capacity() * size_of::<(ICoord, Block)>())
+            ),
+            format!(
+                "FALLING {} IN {} ({})",
+                falling_blocks,
+                self.falling_blocks.len(),
+                kilobytes(
+                    falling_blocks * size_of::<(ICoord, Block)>()
+                        + self.falling_blocks.capacity() * size_of::<FallingBlockChunk>()
+                )
+            ),
+            format!(
+                "EVENTS {} ({})",
+                self.events.len(),
+                kilobytes(self.events.capacity() * size_of::<RunEvent>())
+            ),
+            format!(
+                "INPUTS {}",
+                kilobytes(self.recording.as_ref().map_or(0, |log| log.heap_bytes()))
+            ),
+            format!("TIMELINE {}", kilobytes(self.timeline.heap_bytes())),
+            format!("FILMSTRIP {} SHOTS", self.filmstrip.len()),
+            format!(
+                "ANCHORS {}, LINKS {:.2}/BLOCK",
+                self.report.anchors,
+                self.report.link_redundancy()
+            ),
+            format!("LONGEST BRIDGE {}", self.report.largest_span),
+        ];
+
+        let line_height = 8.0;
+        let height = lines.len() as f32 * line_height + 4.0;
+        let top = HEIGHT - height - 2.0;
+        draw_rectangle(2.0, top, 120.0, height, hexcolor(ui::BACKGROUND));
+        for (idx, line) in lines.iter().enumerate() {
+            drawutils::draw_text_px(
+                line,
+                4.0,
+                top + 3.0 + idx as f32 * line_height,
+                hexcolor(ui::DIM),
+                globals,
+            );
+        }
+    }
+}
+
+/// Shrink the event log back under its cap.
+///
+/// Collapses that happened right after each other get merged first,
+/// since that's usually one collapse coming down in pieces.
+/// If that isn't enough, the oldest events go.
+fn compact_events(events: &mut Vec<RunEvent>) {
+    let mut compacted: Vec<RunEvent> = Vec::with_capacity(events.len());
+    for event in events.drain(..) {
+        if let (Some(last), RunEventKind::Collapse(count)) = (compacted.last_mut(), event.kind) {
+            if let RunEventKind::Collapse(last_count) = &mut last.kind {
+                if event.frame - last.frame <= COLLAPSE_MERGE_FRAMES {
+                    *last_count += count;
+                    continue;
+                }
+            }
+        }
+        compacted.push(event);
+    }
+    if compacted.len() > EVENT_LOG_MAX {
+        compacted.drain(..compacted.len() - EVENT_LOG_MAX);
+    }
+    compacted.shrink_to_fit();
+    *events = compacted;
+}
+
+fn kilobytes(bytes: usize) -> String {
+    format!("{:.1}KB", bytes as f32 / 1024.0)
+}
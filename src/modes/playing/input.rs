@@ -0,0 +1,236 @@
+use super::coop::KeyboardInput;
+use crate::{
+    drawutils::{self, mouse_position_pixel, window_to_pixel},
+    HEIGHT, WIDTH,
+};
+
+use macroquad::{
+    input::utils::{register_input_subscriber, repeat_all_miniquad_input},
+    math::Rect,
+    miniquad::{self, MouseButton},
+};
+use serde::{Deserialize, Serialize};
+
+use std::cell::Cell;
+
+/// How many frames an input that came in too early to act on is kept around for
+pub const INPUT_BUFFER_FRAMES: u64 = 4;
+
+/// Everything the player did on one frame that matters to the simulation.
+///
+/// Runs read their input through this instead of straight from macroquad,
+/// so that it can be recorded and played back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputFrame {
+    /// In pixel coordinates
+    pub mouse: (f32, f32),
+    pub left_down: bool,
+    pub left_pressed: bool,
+    pub right_pressed: bool,
+    /// Which way the mouse wheel went: -1, 0, or 1
+    pub wheel: i8,
+    /// Save and quit, for runs that can do that
+    pub suspend: bool,
+    /// The second player, in co-op runs
+    #[serde(default)]
+    pub keyboard: KeyboardInput,
+    /// Where the mouse was when the left button went down, if it did this frame.
+    /// After a hitch the mouse might be somewhere else entirely by the time the frame runs.
+    #[serde(default)]
+    pub pressed_at: Option<(f32, f32)>,
+    /// Where the mouse was when the left button came up, if it did this frame
+    #[serde(default)]
+    pub released_at: Option<(f32, f32)>,
+    /// Take back the last placement
+    #[serde(default)]
+    pub undo: bool,
+    /// Zoom in or back out
+    #[serde(default)]
+    pub zoom: bool,
+}
+
+impl InputFrame {
+    /// Read what the player is doing right now.
+    pub fn capture() -> Self {
+        use macroquad::prelude::*;
+
+        let wheel = mouse_wheel().1;
+        let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        let pointer = POINTER.with(|pointer| pointer.get());
+        Self {
+            mouse: mouse_position_pixel(),
+            left_down: is_mouse_button_down(MouseButton::Left),
+            left_pressed: is_mouse_button_pressed(MouseButton::Left),
+            right_pressed: is_mouse_button_pressed(MouseButton::Right),
+            wheel: if wheel > 0.0 {
+                1
+            } else if wheel < 0.0 {
+                -1
+            } else {
+                0
+            },
+            suspend: is_key_pressed(KeyCode::Escape),
+            keyboard: KeyboardInput::default(),
+            pressed_at: pointer.pressed_at,
+            released_at: pointer.released_at,
+            undo: ctrl && is_key_pressed(KeyCode::Z),
+            zoom: !ctrl && is_key_pressed(KeyCode::Z),
+        }
+    }
+
+    /// Move the mouse into the pixels of a run drawn shrunk into this part of the canvas.
+    ///
+    /// Outside of it, clicks don't count, and the mouse stays at the edge
+    /// so it doesn't scroll any faster than it would at the edge of the screen.
+    /// Letting go still counts, so a block carried out of it gets put back.
+    pub fn within(self, area: Rect) -> Self {
+        let (mx, my) = self.mouse;
+        let inside = area.contains(macroquad::math::vec2(mx, my));
+        let (x, y) = drawutils::unfit(area, mx, my);
+        let unfit = |(x, y): (f32, f32)| drawutils::unfit(area, x, y);
+        Self {
+            mouse: (x.clamp(0.0, WIDTH), y.clamp(0.0, HEIGHT)),
+            left_pressed: self.left_pressed && inside,
+            right_pressed: self.right_pressed && inside,
+            wheel: if inside { self.wheel } else { 0 },
+            pressed_at: self.pressed_at.filter(|_| inside).map(unfit),
+            released_at: self.released_at.map(unfit),
+            ..self
+        }
+    }
+}
+
+/// Where a run gets its input from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputSource {
+    /// The mouse and the whole keyboard, or the arrow keys and such for the second player in co-op
+    Everything,
+    /// Only the mouse, when somebody else has the keyboard
+    Mouse,
+    /// Only the keyboard, when somebody else has the mouse
+    Keyboard,
+}
+
+impl InputSource {
+    /// Read what the player on this is doing right now,
+    /// for a run drawn in this part of the canvas.
+    pub fn capture(self, coop: bool, screen: Rect) -> InputFrame {
+        match self {
+            InputSource::Everything => {
+                let input = InputFrame::capture().within(screen);
+                InputFrame {
+                    keyboard: if coop {
+                        KeyboardInput::capture()
+                    } else {
+                        KeyboardInput::capture_solo()
+                    },
+                    // in co-op, Z turns the second player's block
+                    zoom: input.zoom && !coop,
+                    ..input
+                }
+            }
+            InputSource::Mouse => InputFrame {
+                // Escape belongs to whatever is sharing the screen
+                suspend: false,
+                undo: false,
+                zoom: false,
+                ..InputFrame::capture().within(screen)
+            },
+            // The mouse is somebody else's, so it sits in the middle, away from the edges that scroll
+            InputSource::Keyboard => InputFrame {
+                mouse: (WIDTH / 2.0, HEIGHT / 2.0),
+                keyboard: KeyboardInput::capture_solo(),
+                undo: InputFrame::capture().undo,
+                zoom: InputFrame::capture().zoom,
+                ..InputFrame::default()
+            },
+        }
+    }
+}
+
+/// Inputs that couldn't be acted on the frame they came in, and the frame they came in on.
+///
+/// If a whole drag happens during one long frame, the block gets picked up that frame,
+/// and the release and any turns of the wheel wait here to be used on the next.
+#[derive(Clone, Default)]
+pub struct InputBuffer {
+    release: Option<(u64, (f32, f32))>,
+    wheel: Option<(u64, i8)>,
+}
+
+impl InputBuffer {
+    pub fn buffer_release(&mut self, frame: u64, pos: (f32, f32)) {
+        self.release = Some((frame, pos));
+    }
+
+    pub fn buffer_wheel(&mut self, frame: u64, wheel: i8) {
+        self.wheel = Some((frame, wheel));
+    }
+
+    /// Take the buffered release, if it's recent enough to still count.
+    pub fn take_release(&mut self, frame: u64) -> Option<(f32, f32)> {
+        self.release
+            .take()
+            .filter(|(stamp, _)| frame - stamp <= INPUT_BUFFER_FRAMES)
+            .map(|(_, pos)| pos)
+    }
+
+    /// Take the buffered wheel turn, if it's recent enough to still count.
+    pub fn take_wheel(&mut self, frame: u64) -> i8 {
+        self.wheel
+            .take()
+            .filter(|(stamp, _)| frame - stamp <= INPUT_BUFFER_FRAMES)
+            .map_or(0, |(_, wheel)| wheel)
+    }
+}
+
+thread_local! {
+    /// Gets a copy of every raw miniquad event
+    static SUBSCRIBER: usize = register_input_subscriber();
+    /// Where the left button last went down and came up this frame, if it did
+    static POINTER: Cell<PointerCatcher> = Cell::new(PointerCatcher::default());
+}
+
+/// Go through the raw mouse events since last frame, to see exactly where the clicks were.
+/// Call this once at the start of each frame.
+pub fn poll_pointer_events() {
+    let mut catcher = PointerCatcher::default();
+    SUBSCRIBER.with(|subscriber| repeat_all_miniquad_input(&mut catcher, *subscriber));
+    POINTER.with(|pointer| pointer.set(catcher));
+}
+
+#[derive(Clone, Copy, Default)]
+struct PointerCatcher {
+    pressed_at: Option<(f32, f32)>,
+    released_at: Option<(f32, f32)>,
+}
+
+impl miniquad::EventHandler for PointerCatcher {
+    fn update(&mut self, _ctx: &mut miniquad::Context) {}
+
+    fn draw(&mut self, _ctx: &mut miniquad::Context) {}
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut miniquad::Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        if button == MouseButton::Left {
+            self.pressed_at = Some(window_to_pixel(x, y));
+        }
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        _ctx: &mut miniquad::Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        if button == MouseButton::Left {
+            self.released_at = Some(window_to_pixel(x, y));
+        }
+    }
+}
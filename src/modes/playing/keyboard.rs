@@ -0,0 +1,164 @@
+//! Playing without a mouse, in runs with only one player.
+//!
+//! The arrow keys or WASD move a cursor around the grid, the number keys grab
+//! a block off the conveyor (1 is the bottom), Q and E turn it, space or enter
+//! puts it down, and backspace puts it back.
+//! With nothing held, space or enter knocks at a scaffold under the cursor.
+//! Page up and page down scroll, B buys an anchor, and F finishes the run.
+//! Z zooms in and out, as long as nobody's using it to turn blocks in co-op.
+//! K and N are for macros; see the `macros` module.
+
+use super::{
+    coop::{outline_cell, KeyboardInput},
+    events::PlayEvent,
+    geometry::Viewport,
+    HoldInfo, InputFrame, ModePlaying, ScrollAxis, BLOCK_SIZE, CONVEYOR_SLOT_HEIGHT,
+    CONVEYOR_Y_BOTTOM, MANUAL_SCROLL_GRACE, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+use crate::{
+    drawutils::{self, hexcolor},
+    ui, Globals, WIDTH,
+};
+
+use cogs_gamedev::int_coords::ICoord;
+
+/// How many rows a press of page up or page down scrolls
+const KEY_SCROLL_ROWS: f32 = 4.0;
+/// How close to the edge of the view the cursor can get before the camera follows it
+const CURSOR_MARGIN: isize = 2;
+
+/// Where the keyboard is pointing on the grid.
+#[derive(Clone)]
+pub struct Cursor {
+    pub pos: ICoord,
+    /// Whether the keyboard is being used. Clicking hands control back to the mouse.
+    pub active: bool,
+}
+
+impl Cursor {
+    pub fn new() -> Self {
+        Self {
+            pos: ICoord::new(0, 0),
+            active: false,
+        }
+    }
+}
+
+impl ModePlaying {
+    pub(super) fn handle_keyboard(&mut self, input: InputFrame) {
+        if input.left_pressed {
+            self.cursor.active = false;
+        }
+        // macros go wherever the mouse is unless the cursor's already in use
+        let keys = KeyboardInput {
+            macro_record: false,
+            macro_play: false,
+            ..input.keyboard
+        };
+        if keys == KeyboardInput::default() {
+            return;
+        }
+        self.cursor.active = true;
+
+        if keys.scroll != 0 {
+            self.scroll_depth += keys.scroll as f32 * KEY_SCROLL_ROWS;
+            self.manual_scroll_timer = MANUAL_SCROLL_GRACE;
+        }
+
+        let moved = self.move_grid_cursor(self.cursor.pos, keys);
+        if moved != self.cursor.pos {
+            self.cursor.pos = moved;
+            self.scroll_to_cursor();
+        }
+
+        if keys.buy_anchor {
+            let held = self.held.as_ref().map(|info| info.idx);
+            if held.is_some() || self.can_buy_blocks() {
+                self.buy_anchor(held);
+            }
+        }
+
+        let pos = self.cursor.pos;
+        match &mut self.held {
+            None => {
+                if let (Some(slot), 0) = (keys.pick, self.cooldown_left) {
+                    let slot = slot as usize;
+                    if slot < self.conveyor_blocks.len() && !self.is_slot_held(slot) {
+                        self.held = Some(HoldInfo {
+                            idx: slot,
+                            rotation: 0.0,
+                        });
+                        self.emit(PlayEvent::PickedUp);
+                    }
+                } else if keys.grab {
+                    if let Some(block) = self.stable_blocks.get_mut(&pos) {
+                        if block.is_removable() {
+                            block.damage += 1;
+                            self.emit(PlayEvent::BlockDamaged { pos });
+                        }
+                    }
+                }
+            }
+            Some(info) => {
+                let idx = info.idx;
+                if keys.rotate != 0 {
+                    info.rotate(&mut self.conveyor_blocks[idx], keys.rotate > 0);
+                    self.emit(PlayEvent::Rotated);
+                }
+                if keys.cancel {
+                    self.held = None;
+                    self.emit(PlayEvent::Returned);
+                } else if keys.grab {
+                    if self.can_place(&self.conveyor_blocks[idx], pos) {
+                        self.held = None;
+                        self.place_from_conveyor(idx, pos);
+                    } else {
+                        self.emit(PlayEvent::Dropped);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move the camera just enough to keep the cursor in view.
+    fn scroll_to_cursor(&mut self) {
+        let axis = self.config.scroll_axis;
+        let half_screen = match axis {
+            ScrollAxis::Vertical => SCREEN_HEIGHT / 2,
+            // the conveyor covers the right side
+            ScrollAxis::Horizontal => SCREEN_WIDTH / 2 - 5,
+        };
+        // zoomed in, less of the grid fits
+        let half_view = half_screen as f32 / self.viewport().zoom - CURSOR_MARGIN as f32;
+        // the camera counts depth from the anchors, whichever way is up
+        let reach = (axis.reach(self.cursor.pos) * self.config.gravity.sign()) as f32;
+        let low = reach - half_view;
+        let high = reach + half_view;
+        let before = self.scroll_depth;
+        self.scroll_depth = self.scroll_depth.clamp(low, high);
+        if self.scroll_depth != before {
+            self.manual_scroll_timer = MANUAL_SCROLL_GRACE;
+        }
+    }
+
+    /// Draw the keyboard cursor, and the numbers of the conveyor slots, if the keyboard's in use.
+    pub(super) fn draw_keyboard_cursor(&self, view: &Viewport, globals: &Globals) {
+        if !self.cursor.active {
+            return;
+        }
+        let fg = hexcolor(ui::FOREGROUND);
+        let (cx, cy) = view.block_to_screen(self.cursor.pos);
+        outline_cell(cx, cy, view.block_size(), fg);
+
+        for idx in 0..self.conveyor_blocks.len() {
+            let slot_y = CONVEYOR_Y_BOTTOM - idx as f32 * CONVEYOR_SLOT_HEIGHT;
+            drawutils::draw_text_px(
+                &(idx + 1).to_string(),
+                WIDTH - 70.0 + 17.0,
+                slot_y + BLOCK_SIZE / 2.0 - 3.0,
+                fg,
+                globals,
+            );
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Recording a few placements and stamping them down again somewhere else,
+//! for building the same lattice over and over.
+//!
+//! K starts recording, and K again stops. Every block placed in between is noted down
+//! relative to the first one. N plays it back with the first block under the cursor,
+//! one block at a time, using whichever conveyor blocks match once they're turned around.
+
+use super::{
+    blocks::{Block, BlockKind, Connector},
+    coop::outline_cell,
+    geometry::Viewport,
+    InputFrame, ModePlaying,
+};
+use crate::{drawutils::hexcolor, ui};
+
+use cogs_gamedev::int_coords::ICoord;
+
+/// The most placements one macro can hold
+const MACRO_MAX_STEPS: usize = 8;
+
+/// One recorded placement.
+#[derive(Clone, Debug)]
+struct MacroStep {
+    /// From the first block placed
+    offset: ICoord,
+    kind: BlockKind,
+    /// Facing the way they did when it was placed
+    connectors: [Option<Connector>; 4],
+}
+
+impl MacroStep {
+    /// How many quarter turns counterclockwise it takes for this block to match,
+    /// if it can at all.
+    fn turns_to_match(&self, block: &Block) -> Option<usize> {
+        if block.kind != self.kind {
+            return None;
+        }
+        (0..4).find(|&turns| {
+            let mut connectors = block.connectors.clone();
+            connectors.rotate_left(turns);
+            connectors == self.connectors
+        })
+    }
+}
+
+/// Recording and playing back macros.
+#[derive(Clone, Default)]
+pub struct Macros {
+    /// Steps so far, while recording
+    recording: Option<Vec<MacroStep>>,
+    /// Where the first recorded block went
+    recording_origin: Option<ICoord>,
+    /// The last macro recorded
+    saved: Vec<MacroStep>,
+    /// Where the playback started, and which step is next
+    playback: Option<(ICoord, usize)>,
+}
+
+impl ModePlaying {
+    /// Start and stop recording or playing back, and place the next block of a playback.
+    pub(super) fn handle_macros(&mut self, input: InputFrame) {
+        // taking turns would leave half the macro to the other player
+        if self.config.coop || self.config.versus {
+            return;
+        }
+        let keys = input.keyboard;
+
+        if keys.macro_record {
+            self.macros.playback = None;
+            match self.macros.recording.take() {
+                Some(steps) if !steps.is_empty() => {
+                    self.toasts
+                        .push(format!("MACRO SAVED: {} BLOCKS", steps.len()));
+                    self.macros.saved = steps;
+                }
+                Some(_) => self.toasts.push("MACRO CANCELLED"),
+                None => {
+                    self.macros.recording = Some(Vec::new());
+                    self.toasts.push("RECORDING MACRO");
+                }
+            }
+        } else if keys.macro_play && self.macros.recording.is_none() {
+            if self.macros.playback.is_some() {
+                self.macros.playback = None;
+                self.toasts.push("MACRO STOPPED");
+            } else if self.macros.saved.is_empty() {
+                self.toasts.push("NO MACRO RECORDED");
+            } else {
+                let origin = if self.cursor.active {
+                    self.cursor.pos
+                } else {
+                    let (mx, my) = input.mouse;
+                    self.viewport().pixel_to_block(mx, my)
+                };
+                self.macros.playback = Some((origin, 0));
+            }
+        }
+
+        self.play_macro_step();
+    }
+
+    /// Place the next block of the macro being played back, if it's time.
+    fn play_macro_step(&mut self) {
+        let (origin, next) = match self.macros.playback {
+            Some(it) => it,
+            None => return,
+        };
+        // wait on the player's hand too, so it doesn't yank a block out of it
+        if self.cooldown_left > 0 || self.held.is_some() {
+            return;
+        }
+        let step = &self.macros.saved[next];
+        let pos = origin + step.offset;
+
+        let found = (0..self.conveyor_blocks.len())
+            .filter(|&idx| !self.is_slot_held(idx))
+            .find_map(|idx| {
+                let turns = step.turns_to_match(&self.conveyor_blocks[idx])?;
+                let mut block = self.conveyor_blocks[idx].clone();
+                block.connectors.rotate_left(turns);
+                if self.can_place(&block, pos) {
+                    Some((idx, block))
+                } else {
+                    None
+                }
+            });
+        let (idx, block) = match found {
+            Some(it) => it,
+            None => {
+                let reason = if self.stable_blocks.contains_key(&pos) {
+                    "MACRO BLOCKED"
+                } else {
+                    "MACRO NEEDS A BLOCK"
+                };
+                self.toasts.push(reason);
+                self.macros.playback = None;
+                return;
+            }
+        };
+
+        self.conveyor_blocks[idx] = block;
+        self.place_from_conveyor(idx, pos);
+        self.macros.playback = if next + 1 < self.macros.saved.len() {
+            Some((origin, next + 1))
+        } else {
+            None
+        };
+    }
+
+    /// Note down a block that was just placed, if a macro's being recorded.
+    pub(super) fn record_macro_step(&mut self, pos: ICoord) {
+        let recording = match &mut self.macros.recording {
+            Some(it) => it,
+            None => return,
+        };
+        let block = match self.stable_blocks.get(&pos) {
+            Some(it) => it,
+            None => return,
+        };
+        if recording.is_empty() {
+            self.macros.recording_origin = Some(pos);
+        }
+        let origin = self.macros.recording_origin.unwrap_or(pos);
+        recording.push(MacroStep {
+            offset: ICoord::new(pos.x - origin.x, pos.y - origin.y),
+            kind: block.kind.clone(),
+            connectors: block.connectors.clone(),
+        });
+
+        if recording.len() >= MACRO_MAX_STEPS {
+            let steps = self.macros.recording.take().unwrap();
+            self.toasts
+                .push(format!("MACRO FULL: {} BLOCKS", steps.len()));
+            self.macros.saved = steps;
+        }
+    }
+
+    /// A line for the stats box, while recording
+    pub(super) fn macro_label(&self) -> Option<String> {
+        let recording = self.macros.recording.as_ref()?;
+        Some(format!("REC {}/{}", recording.len(), MACRO_MAX_STEPS))
+    }
+
+    /// Outline where the rest of a playback is going.
+    pub(super) fn draw_macro_playback(&self, view: &Viewport) {
+        if let Some((origin, next)) = self.macros.playback {
+            for step in &self.macros.saved[next..] {
+                let (cx, cy) = view.block_to_screen(origin + step.offset);
+                outline_cell(cx, cy, view.block_size(), hexcolor(ui::DIM));
+            }
+        }
+    }
+}
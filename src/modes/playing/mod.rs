@@ -1,23 +1,111 @@
+#[cfg(not(target_arch = "wasm32"))]
+mod artifacts;
+mod bench;
+mod blockmap;
 mod blocks;
-
-use self::blocks::{Block, BlockKind, Connector, FallingBlockChunk};
-use crate::{drawutils, Gamemode, Globals, ModeDenoument, Transition, HEIGHT, WIDTH};
+mod blueprint;
+mod borders;
+mod chasm;
+mod columns;
+mod combo;
+mod config;
+mod coop;
+mod events;
+mod filmstrip;
+mod forecast;
+mod generator;
+mod geometry;
+mod ghost;
+mod hazards;
+mod hold;
+mod housekeeping;
+mod input;
+mod keyboard;
+mod macros;
+mod objectives;
+mod photo;
+mod race;
+mod replay;
+mod report;
+mod shake;
+mod site;
+mod snapshot;
+mod spawner;
+mod speed;
+mod stability;
+mod timeline;
+mod tutorial;
+mod undo;
+mod versus;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::bench::StressRun;
+pub use self::blocks::{Block, BlockKind, Connector, ConnectorShape};
+pub use self::blueprint::Blueprint;
+pub use self::config::{GameType, Gravity, RunConfig, SavePolicy, ScrollAxis};
+pub use self::events::PlayEvent;
+pub use self::filmstrip::Filmstrip;
+pub use self::input::{poll_pointer_events, InputFrame, InputSource};
+pub use self::objectives::Objective;
+pub use self::photo::Photo;
+pub use self::race::{RaceFinish, RACE_DEPTH};
+pub use self::replay::{Replay, ReplayHeader, RunEvent, RunEventKind};
+pub use self::snapshot::RunSnapshot;
+pub use self::versus::{player_color, player_name, Versus, VersusOutcome};
+
+use self::artifacts::Artifacts;
+use self::blockmap::BlockMap;
+use self::blocks::FallingBlockChunk;
+use self::chasm::ChasmProfile;
+use self::combo::Combo;
+use self::coop::SecondPlayer;
+use self::generator::Generator;
+use self::geometry::Viewport;
+use self::ghost::Ghost;
+use self::hazards::Hazards;
+use self::hold::Hold;
+use self::housekeeping::HOUSEKEEPING_INTERVAL;
+use self::input::InputBuffer;
+use self::keyboard::Cursor;
+use self::macros::Macros;
+use self::replay::{InputLog, REPLAY_VERSION};
+use self::report::StructureReport;
+use self::shake::Shake;
+use self::spawner::Spawner;
+use self::speed::SimSpeed;
+use self::stability::{Overlay, StabilityCache};
+use self::timeline::{Timeline, TIMELINE_INTERVAL};
+use self::undo::UndoStack;
+use crate::{
+    cursor::CursorIcon,
+    drawutils::{self, hexcolor, OffscreenTarget},
+    music::Track,
+    particles::Particles,
+    random::GameRng,
+    save,
+    score::{self, BlueprintResult, RunResult},
+    seasonal,
+    settings::{CameraMode, GraphicsQuality},
+    ui::{self, Toasts},
+    Gamemode, Globals, ModeDenoument, ModeTitle, ModeVersusResult, Transition, HEIGHT, WIDTH,
+};
 
 use cogs_gamedev::{directions::Direction4, int_coords::ICoord};
-use drawutils::mouse_position_pixel;
 use itertools::Itertools;
 use quad_rand::compat::QuadRand;
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 
-use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
-    f32::consts::TAU,
-};
+use std::{collections::HashSet, f32::consts::TAU};
 
 // In block coordinates, (0, 0) is the middle of the very top of the chasm.
 // Y increases down. 0 is the level where the ground begins (so it's inside the ground.)
 
+/// How many columns across the chasm is, unless the run says otherwise.
+/// Always odd, so there's a middle column.
 const CHASM_WIDTH: isize = 9;
+/// How many rows tall the tunnel is, in horizontal runs.
+/// It runs from row 0 down; its end wall is column 0.
+const SHAFT_HEIGHT: isize = 8;
 /// How many grid squares across the whole screen would be
 const SCREEN_WIDTH: isize = (WIDTH / BLOCK_SIZE) as isize;
 /// How many grid squares down the whole screen would be
@@ -28,233 +116,613 @@ const BOTTOM_VIEW_SIZE: isize = SCREEN_HEIGHT / 2;
 const FALL_ACCELLERATION: f32 = 1.0 / 60.0;
 const FALL_TERMINAL: f32 = 0.5;
 
-const BLOCK_SIZE: f32 = 16.0;
+pub const BLOCK_SIZE: f32 = 16.0;
+/// How much bigger everything is drawn when zoomed in
+const ZOOMED_IN: f32 = 2.0;
 
 const SCROLL_HOTZONE_SIZE: f32 = 16.0;
 const SCROLL_SPEED: f32 = 0.45;
+/// How long the camera leaves you alone after you scroll it yourself, in frames
+const MANUAL_SCROLL_GRACE: u32 = 120;
+/// Fraction of the way the camera moves towards where it wants to be each frame
+const CAMERA_EASING: f32 = 0.08;
 
 const CONVEYOR_MAX_SIZE: usize = 7;
 const CONVEYOR_Y_BOTTOM: f32 = 184.0;
+/// Distance between blocks on the conveyor
+const CONVEYOR_SLOT_HEIGHT: f32 = 24.0;
+/// How fast blocks slide along the conveyor, in pixels per frame
+const CONVEYOR_SPEED: f32 = 2.0;
+/// The part of the conveyor texture that's the moving belt
+const BELT_TOP: f32 = 32.0;
+const BELT_BOTTOM: f32 = 208.0;
+/// Distance between the slats on the belt
+const BELT_SLAT_SPACING: f32 = 8.0;
+/// How many of the blocks coming up next are shown over the belt
+const PREVIEW_COUNT: usize = 3;
+/// How big the blocks coming up are drawn, next to blocks on the belt
+const PREVIEW_SCALE: f32 = 0.5;
+/// Distance between the blocks coming up, at full scale
+const PREVIEW_SPACING: f32 = 24.0;
+
+/// Slowest the held block's connectors swing into place after rotating it, in radians per frame
+const ROTATION_MIN_SPEED: f32 = TAU / 4.0 / 6.0;
+/// Fraction of the remaining swing covered each frame, so fast spinning catches up
+const ROTATION_EASING: f32 = 0.3;
 
-/// Chance a block takes damage per frame based on the number of things it links to
-const BREAK_CHANCES: [f64; 5] = [
-    0.0, // a block resting never takes damage
-    0.3 / 60.0,
-    1.0 / 60.0,
-    1.5 / 60.0,
-    3.0 / 60.0,
-];
 const BREAK_TIMER: u64 = 60;
-
-const BLOCK_ALLOWANCE: usize = 100;
+/// How many frames before blocks might break the structure creaks, as a warning
+const STRESS_WARNING: u64 = 12;
+/// How many of the blocks likeliest to break get dust shaken off them when it creaks
+const STRESS_MOTES: usize = 3;
+/// How far from an explosive block its blast reaches, in blocks
+const BLAST_RADIUS: isize = 2;
+/// How much likelier blocks under water are to take damage
+const SUBMERGED_DECAY: f64 = 3.0;
+const WATER_COLOR: u32 = 0x3f7fd080;
+const WATER_SURFACE_COLOR: u32 = 0xa8d4ffc0;
+const GLUE_SEAM_COLOR: u32 = 0xe8c170ff;
+/// How much of the edge between a glue block and its neighbor the seam covers, in pixels
+const GLUE_SEAM_LENGTH: f32 = 12.0;
+/// Marks on the edges of a held block that would link, or whose connectors wouldn't fit
+const LINK_HINT_COLOR: u32 = 0x6abe30e0;
+const CLASH_HINT_COLOR: u32 = 0xd95763e0;
+
+/// How often runs that get saved are autosaved, in frames
+const AUTOSAVE_INTERVAL: u64 = 600;
+
+/// Bridges shorter than this aren't worth anything
+const BRIDGE_MIN_LENGTH: usize = 2;
+/// Bridges at least this long get a toast
+const LONG_BRIDGE_LENGTH: usize = 5;
+
+/// A snapshot of the viewport is taken every time the depth passes a multiple of this
+const MILESTONE_INTERVAL: f32 = 10.0;
+
+/// How full the structure practice runs start on is
+const START_DENSITY: f64 = 0.7;
+
+/// How long R has to be held to restart, in frames
+const RESTART_HOLD_FRAMES: u32 = 60;
+
+/// Points the first bought anchor costs. Each one after that costs twice as much.
+const ANCHOR_BASE_COST: u32 = 25;
 
 #[derive(Clone)]
 pub struct ModePlaying {
+    config: RunConfig,
+
     /// Maps coordinates to whatever block is there.
-    stable_blocks: HashMap<ICoord, Block>,
+    stable_blocks: BlockMap,
     /// Blocks visually falling right now.
     /// Each entry is a clump of together-falling blocks.
     falling_blocks: Vec<FallingBlockChunk>,
     /// Blocks in the conveyor on the side
     conveyor_blocks: Vec<Block>,
+    /// How far below its slot each conveyor block is drawn, as they slide into place
+    conveyor_offsets: Vec<f32>,
+    /// How far the belt has moved, for animating it
+    belt_scroll: f32,
+    /// Makes new blocks for the conveyor
+    spawner: Spawner,
+    /// Which columns are open at each depth, worked out from the seed
+    profile: ChasmProfile,
+    /// Water and such, worked out from the seed
+    hazards: Hazards,
+    /// Buried things to dig out for points
+    artifacts: Artifacts,
+    /// Randomness for everything that affects how the run plays out.
+    /// Cosmetic stuff should use `QuadRand` instead.
+    rng: GameRng,
+    /// The block stashed off to the side, and the rerolls
+    hold: Hold,
     /// Index in the conveyor of the block being held by the player right now
     held: Option<HoldInfo>,
-    blocks_left: usize,
+    /// For playing with the keyboard alone
+    cursor: Cursor,
+    /// Recorded placements to stamp down again
+    macros: Macros,
+    /// The last few placements, to take back
+    undo: UndoStack,
+    /// The player on the keyboard, in co-op runs
+    second: Option<SecondPlayer>,
+    /// Turns and blame, in versus runs
+    versus: Option<Versus>,
+    /// In races, how big the chunk that lost it was, once one falls
+    race_collapse: Option<usize>,
+    /// The best run of the same kind, playing back alongside, if the player wants it
+    ghost: Option<Box<Ghost>>,
+    /// How many more blocks will come down the conveyor, if they ever stop
+    blocks_left: Option<usize>,
+    blocks_placed: u32,
+    /// For objectives that count them
+    solids_placed: u32,
+    /// How many blocks came down at once in the worst collapse so far
+    biggest_collapse: u32,
+    /// Frames until the player can pick up another block
+    cooldown_left: u64,
 
     /// How far down I have scrolled.
     /// When this is 0, block (0, 0) is in the dead center of the screen
     scroll_depth: f32,
-
-    /// Cached maximum depth value
-    max_depth: isize,
-    /// Cached center of mass
-    center_of_mass: f32,
-
-    audio: AudioSignals,
+    /// Where the camera was a step ago, to draw it partway between
+    prev_scroll_depth: f32,
+    /// Frames until the camera can move on its own again
+    manual_scroll_timer: u32,
+    /// Where the last block was placed, for the camera to follow
+    last_placed: Option<ICoord>,
+    /// Camera mode to use instead of the one in the settings (for replays)
+    camera_override: Option<CameraMode>,
+    /// Scroll speed to use instead of the one in the settings (for replays)
+    scroll_speed_override: Option<u8>,
+    /// Whether the field is drawn zoomed in, showing less of it but bigger
+    zoomed: bool,
+
+    /// The shape of the structure as of the last step
+    report: StructureReport,
+    /// Bonus points earned this run, on top of the depth
+    points: u32,
+    /// Good placements in a row, which multiply points
+    combo: Combo,
+    /// How many depth milestones have been passed
+    milestones_reached: u32,
+    /// Snapshots from the milestones
+    filmstrip: Filmstrip,
+    /// How many anchors have been bought with points so far
+    anchors_bought: u32,
+
+    /// What happened during the last frame
+    frame_events: Vec<PlayEvent>,
+    toasts: Toasts,
 
     frames_elapsed: u64,
+    paused: bool,
+    /// How many frames R has been held for, to restart once it's been long enough
+    restart_held: u32,
+    /// Snapshots to rewind to, for rewindable runs
+    timeline: Timeline,
+    /// Whether this run was saved and then picked back up
+    resumed: bool,
+    /// Whether this is the first frame this instance is running (for bringing up the ghost)
+    just_started: bool,
+    /// What the player has done so far, to save as a replay.
+    /// Only fresh runs that can't be rewound are recorded.
+    recording: Option<InputLog>,
+    /// Input from the last frame that ran, for drawing
+    last_input: InputFrame,
+    /// Input that came in before it could be used
+    input_buffer: InputBuffer,
+    /// Decisive moments in the recording
+    events: Vec<RunEvent>,
+    /// Whether to show how much memory things are taking up
+    show_memory: bool,
+    /// Which overlay Tab has brought up
+    overlay: Overlay,
+    /// How likely each block was to take damage as of the last step, for the overlay
+    break_chances: Vec<(ICoord, f64)>,
+    /// What holds what up, kept until the blocks move
+    stability: StabilityCache,
+    /// How fast the simulation runs, in runs that let it be changed
+    sim_speed: SimSpeed,
+    /// Seasonal snow and such
+    particles: Particles,
+    shake: Shake,
+    /// On low graphics quality, the background as last drawn and the scroll it was drawn at
+    background_cache: Option<(f32, OffscreenTarget)>,
+    /// Where on the canvas the run is drawn. All of it, unless it's sharing the screen.
+    screen: macroquad::math::Rect,
+    /// What the player plays with
+    input_source: InputSource,
 }
 
 impl ModePlaying {
-    #[allow(clippy::new_without_default)]
-    pub fn new() -> Self {
-        let mut stable_blocks = HashMap::new();
+    pub fn new(config: RunConfig) -> Self {
+        // The only time gameplay randomness comes from the cosmetic stream is to pick the seed
+        Self::with_seed(config, QuadRand.gen())
+    }
+
+    /// Start a run whose gameplay randomness comes from this seed.
+    pub fn with_seed(config: RunConfig, seed: u64) -> Self {
+        let mut rng = GameRng::new(seed);
+        let profile = ChasmProfile::generate(seed, &config);
+
+        let mut stable_blocks = BlockMap::default();
         // Embed blocks into the ground facing inwards.
-        for side in 0..2 {
-            for depth in 0..4 {
-                let x = (CHASM_WIDTH + 1) / 2 * if side == 0 { -1 } else { 1 };
-                let y = depth;
-
-                let conn = QuadRand.gen();
-                let mut connectors = [None, None, None, None];
-                let dir = if side == 0 {
-                    Direction4::East
-                } else {
-                    Direction4::West
-                };
-                connectors[dir as usize] = Some(conn);
-
-                stable_blocks.insert(
-                    ICoord::new(x, y),
-                    Block {
-                        connectors,
-                        kind: BlockKind::Anchor,
-                        damage: 0,
-                    },
-                );
-            }
+        for (pos, dir) in site::starting_anchors(config.scroll_axis, &profile) {
+            let conn = rng.gen();
+            let mut connectors = [None, None, None, None];
+            connectors[dir as usize] = Some(conn);
+
+            stable_blocks.insert(
+                pos,
+                Block {
+                    connectors,
+                    kind: BlockKind::Anchor,
+                    damage: 0,
+                },
+            );
+        }
+
+        let mut spawner = Spawner::new(rng.gen(), config.block_kinds.clone());
+        // With a budget, the first batch comes out of it too
+        let first_batch = config.block_budget.map_or(config.conveyor_size, |budget| {
+            budget.min(config.conveyor_size)
+        });
+        let conveyor_blocks = (0..first_batch).map(|_| spawner.next()).collect_vec();
+
+        // after the spawner, so its seed is the same as without a head start
+        let start_depth = config.start_depth;
+        if start_depth > 0 && config.scroll_axis == ScrollAxis::Vertical {
+            Generator::new(start_depth, START_DENSITY)
+                .with_profile(profile)
+                .with_gravity(config.gravity)
+                .with_kinds(config.block_kinds.clone())
+                .build(&mut stable_blocks, &mut rng);
         }
 
-        let conveyor_blocks = (0..CONVEYOR_MAX_SIZE).map(|_| QuadRand.gen()).collect_vec();
+        let hazards = Hazards::generate(seed, &config);
+        let artifacts = Artifacts::generate(seed, &config, &profile);
+
+        let mut toasts = Toasts::default();
+        if config.sandbox {
+            toasts.push("PRESS ESCAPE TO GO BACK");
+        }
 
         Self {
+            blocks_left: match config.block_budget {
+                Some(budget) => Some(budget - first_batch),
+                None => config.block_allowance,
+            },
+            recording: if config.rewindable {
+                None
+            } else {
+                Some(InputLog::default())
+            },
+            second: if config.coop {
+                Some(SecondPlayer::new())
+            } else {
+                None
+            },
+            versus: if config.versus {
+                Some(Versus::default())
+            } else {
+                None
+            },
+            race_collapse: None,
+            ghost: None,
+            config,
             stable_blocks,
             falling_blocks: Vec::new(),
+            conveyor_offsets: vec![0.0; conveyor_blocks.len()],
             conveyor_blocks,
+            belt_scroll: 0.0,
+            spawner,
+            profile,
+            hazards,
+            artifacts,
+            rng,
+            hold: Hold::default(),
             held: None,
-            blocks_left: BLOCK_ALLOWANCE,
-            scroll_depth: 0.0,
-            max_depth: 0,
-            center_of_mass: 0.0,
-            audio: AudioSignals::default(),
+            cursor: Cursor::new(),
+            macros: Macros::default(),
+            undo: UndoStack::default(),
+            blocks_placed: 0,
+            solids_placed: 0,
+            biggest_collapse: 0,
+            cooldown_left: 0,
+            scroll_depth: start_depth as f32,
+            prev_scroll_depth: start_depth as f32,
+            manual_scroll_timer: 0,
+            last_placed: None,
+            camera_override: None,
+            scroll_speed_override: None,
+            zoomed: false,
+            report: StructureReport::default(),
+            points: 0,
+            combo: Combo::default(),
+            milestones_reached: 0,
+            filmstrip: Filmstrip::default(),
+            anchors_bought: 0,
+            frame_events: Vec::new(),
+            toasts,
             frames_elapsed: 0,
+            paused: false,
+            restart_held: 0,
+            timeline: Timeline::default(),
+            resumed: false,
+            just_started: true,
+            last_input: InputFrame::default(),
+            input_buffer: InputBuffer::default(),
+            events: Vec::new(),
+            show_memory: false,
+            overlay: Overlay::None,
+            break_chances: Vec::new(),
+            stability: StabilityCache::default(),
+            sim_speed: SimSpeed::new(),
+            particles: Particles::default(),
+            shake: Shake::default(),
+            background_cache: None,
+            screen: macroquad::math::Rect::new(0.0, 0.0, WIDTH, HEIGHT),
+            input_source: InputSource::Everything,
+        }
+    }
+
+    /// Draw the run shrunk into this part of the canvas, to share it with something else.
+    pub fn with_screen(mut self, screen: macroquad::math::Rect) -> Self {
+        self.screen = screen;
+        self
+    }
+
+    /// Play with only the mouse or only the keyboard, leaving the other to somebody else.
+    pub fn with_input(mut self, input_source: InputSource) -> Self {
+        self.input_source = input_source;
+        self
+    }
+
+    /// Run however many steps are due this frame, sped up or slowed down
+    /// if the run allows it.
+    fn run_ticks(&mut self, input: InputFrame, globals: &mut Globals) -> Transition {
+        use macroquad::prelude::*;
+
+        if self.config.sim_speed_control {
+            if is_key_pressed(KeyCode::Equal) {
+                self.sim_speed.faster();
+            }
+            if is_key_pressed(KeyCode::Minus) {
+                self.sim_speed.slower();
+            }
+        }
+        let inputs = self.sim_speed.frame_inputs(input, globals.timestep.ticks());
+        if inputs.is_empty() {
+            self.between_steps();
+        }
+        for input in inputs {
+            // replays go step by step, not frame by frame
+            if let Some(recording) = &mut self.recording {
+                recording.push(input);
+            }
+            let transition = self.step(input, globals);
+            // even the last step of a run gets heard, and counted
+            self.cue_event_sounds(globals);
+            self.record_stats(globals);
+            if !matches!(transition, Transition::None) {
+                return transition;
+            }
+        }
+        Transition::None
+    }
+
+    /// How far it is from the last step to the next one, from 0 to 1
+    fn sim_alpha(&self, globals: &Globals) -> f32 {
+        self.sim_speed.alpha(globals.timestep.alpha())
+    }
+
+    /// On low graphics quality, draw the background ahead of time if the camera moved,
+    /// so it doesn't get redrawn every frame.
+    ///
+    /// This has to happen outside of drawing, because it draws to its own render target.
+    fn update_background_cache(&mut self, globals: &Globals) {
+        if globals.settings.graphics != GraphicsQuality::Low {
+            self.background_cache = None;
+            return;
+        }
+        let scroll_depth = self.drawn_viewport(globals).scroll_depth;
+        let target = match self.background_cache.take() {
+            Some((drawn_at, target)) if drawn_at == scroll_depth => {
+                self.background_cache = Some((drawn_at, target));
+                return;
+            }
+            Some((_, target)) => target,
+            None => OffscreenTarget::screen(),
+        };
+        let view = self.drawn_viewport(globals);
+        drawutils::draw_offscreen(target.get(), || self.draw_backdrop(&view, globals));
+        self.background_cache = Some((scroll_depth, target));
+    }
+
+    /// Keep the seasonal particles going. Low graphics quality goes without.
+    fn update_particles(&mut self, globals: &Globals) {
+        match (globals.theme().emitter, globals.settings.graphics) {
+            (Some(emitter), GraphicsQuality::High) => emitter.emit(&mut self.particles),
+            (_, GraphicsQuality::Low) => {
+                self.particles.clear();
+                return;
+            }
+            _ => {}
         }
+        self.event_particles();
+        self.particles.update();
     }
 
-    pub fn update(&mut self, globals: &mut Globals) -> Transition {
-        self.audio = AudioSignals::default();
-        match self.handle_input(globals) {
+    /// Run one frame of the game with this input.
+    pub fn step(&mut self, input: InputFrame, globals: &mut Globals) -> Transition {
+        self.frame_events.clear();
+        self.toasts.update();
+        self.combo.tick();
+        self.last_input = input;
+        self.prev_scroll_depth = self.scroll_depth;
+
+        self.cooldown_left = self.cooldown_left.saturating_sub(1);
+        self.update_conveyor();
+        if !self.config.coop {
+            self.handle_keyboard(input);
+        }
+        self.handle_macros(input);
+        if input.zoom {
+            self.zoomed = !self.zoomed;
+        }
+        match self.handle_input(input, globals) {
             Transition::None => {}
             other => return other,
         }
-
-        // Damage blocks and record stats
-        // Stability algorithm:
-        // - Anchors have a stability of 1.
-        // - The stability of any other block is
-        let mut max_depth = 0;
-        let mut superposes = 0.0;
-        let mut masses = 0.0;
-        let mut present_depths = HashSet::new();
+        self.handle_second_player(input.keyboard);
+
+        // Damage blocks and record stats.
+        // How likely a block is to wear depends on how much weight it's holding up.
+        let axis = self.config.scroll_axis;
+        let report = StructureReport::new(&self.stable_blocks, &self.config, &self.profile);
+        let new_rows = report
+            .full_rows
+            .iter()
+            .copied()
+            .filter(|depth| !self.report.full_rows.contains(depth))
+            .collect_vec();
+        self.report = report;
+        let loads = self
+            .stability
+            .loads(&self.stable_blocks, self.config.gravity);
         let poses_to_break_chance = self
             .stable_blocks
             .iter()
             .map(|(pos, block)| {
-                max_depth = max_depth.max(pos.y);
-                superposes += pos.y as f32 * block.mass();
-                masses += block.mass();
-
-                let link_count = Direction4::DIRECTIONS
-                    .iter()
-                    .filter(|dir| {
-                        if let Some(conn) = &block.connectors[**dir as usize] {
-                            Self::would_link(&self.stable_blocks, *pos, conn, **dir)
-                        } else {
-                            false
-                        }
-                    })
-                    .count();
-                let mut break_chance = BREAK_CHANCES[link_count];
-                // Blocks by the wall are more bolstered
-                if pos.x.abs() > CHASM_WIDTH / 2 {
-                    break_chance /= 2.0;
-                }
-                present_depths.insert(pos.y);
-                (*pos, break_chance)
+                // anything not held up is about to fall, so it only has itself to carry
+                let load = loads.get(pos).copied().unwrap_or_else(|| block.mass());
+                (*pos, self.break_chance(*pos, block, load))
             })
             .collect_vec();
-        self.max_depth = max_depth;
-        self.center_of_mass = if masses == 0.0 {
-            // imagine having division by zero errors couldn't be me
-            0.0
-        } else {
-            superposes / masses
-        };
+        for name in self.spawner.update(self.report.max_depth) {
+            self.emit(PlayEvent::KindUnlocked { name });
+        }
 
-        let depths_with_rows = present_depths
-            .into_iter()
-            .filter(|depth| {
-                // Check if all xposes have solid blocks
-                (0..CHASM_WIDTH).all(|idx| {
-                    let col = idx - CHASM_WIDTH / 2;
-                    self.stable_blocks.contains_key(&ICoord::new(col, *depth))
-                })
-            })
-            .collect_vec();
+        let milestones = (self.report.center_of_mass / MILESTONE_INTERVAL).max(0.0) as u32;
+        if milestones > self.milestones_reached {
+            self.milestones_reached = milestones;
+            let depth = (milestones as f32 * MILESTONE_INTERVAL) as i32;
+            // take it out so drawing can borrow the rest of self
+            let mut filmstrip = std::mem::take(&mut self.filmstrip);
+            let view = self.viewport();
+            filmstrip.capture(depth, || self.draw_world(&view, globals));
+            self.filmstrip = filmstrip;
+        }
+
+        for depth in new_rows {
+            self.emit(PlayEvent::RowCompleted { depth });
+        }
 
+        let mut anchors_lost = 0;
+        let mut mass_broken = 0.0;
+        let mut damaged = Vec::new();
+        let mut broken = Vec::new();
+        let mut detonated = Vec::new();
+        let warning_due = self.frames_elapsed % BREAK_TIMER == BREAK_TIMER - STRESS_WARNING;
+        let mut at_risk = Vec::new();
+        self.break_chances.clear();
         for (pos, mut chance) in poses_to_break_chance {
-            if depths_with_rows.contains(&pos.y) {
+            if self.report.full_rows.contains(&axis.reach(pos)) {
                 chance *= 0.1;
             }
-            let entry = self.stable_blocks.entry(pos);
-            if let Entry::Occupied(mut occupied) = entry {
-                let block = occupied.get_mut();
-                if self.frames_elapsed % BREAK_TIMER == 0 && QuadRand.gen_bool(chance) {
+            if self.hazards.is_submerged(pos) {
+                chance *= SUBMERGED_DECAY;
+            }
+            chance *= self.config.decay_multiplier;
+            self.break_chances.push((pos, chance));
+            if warning_due && chance > 0.0 {
+                at_risk.push((pos, chance));
+            }
+            if let Some(block) = self.stable_blocks.get_mut(&pos) {
+                if self.frames_elapsed % BREAK_TIMER == 0 && self.rng.gen_bool(chance) {
                     block.damage += 1;
-                    self.audio.damage = true;
+                    damaged.push(pos);
                 }
                 if block.damage > block.resilience() {
                     // die
-                    occupied.remove_entry();
+                    if block.kind == BlockKind::Anchor {
+                        anchors_lost += 1;
+                    } else if block.kind == BlockKind::Explosive {
+                        detonated.push(pos);
+                    }
+                    mass_broken += block.mass();
+                    broken.push(pos);
+                    self.stable_blocks.remove(&pos);
                 }
             } // else we got a problem}
         }
+        for pos in damaged {
+            self.emit(PlayEvent::BlockDamaged { pos });
+        }
+        for pos in broken {
+            self.emit(PlayEvent::BlockBroken { pos });
+        }
+        if !at_risk.is_empty() {
+            at_risk.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
+            let at_risk = at_risk
+                .into_iter()
+                .take(STRESS_MOTES)
+                .map(|(pos, _)| pos)
+                .collect();
+            self.emit(PlayEvent::StressTick { at_risk });
+        }
+        for _ in 0..anchors_lost {
+            self.emit(PlayEvent::AnchorLost);
+        }
+        if let (Some(versus), true) = (&mut self.versus, mass_broken > 0.0) {
+            // one block at a time wearing out is never a collapse
+            versus.lost(mass_broken, 1);
+        }
+        self.detonate(detonated);
 
         // Check for blocks that should fall
-        let mut queries = self
+        let gravity = self.config.gravity;
+        let stable_poses = self.stability.held_up(&self.stable_blocks, gravity);
+
+        let loose = self
             .stable_blocks
-            .iter()
-            .filter_map(|(pos, block)| {
-                if block.kind == BlockKind::Anchor {
-                    Some(*pos)
-                } else {
-                    None
-                }
-            })
-            .collect_vec();
-        let mut stable_poses = HashSet::new();
-        while let Some(pos) = queries.pop() {
-            if stable_poses.insert(pos) {
-                // i've never met this coord in my life
-                if let Some(block) = self.stable_blocks.get(&pos) {
-                    queries.push(pos + ICoord::new(0, -1));
-                    for &dir in &[Direction4::South, Direction4::East, Direction4::West] {
-                        let neighbor_pos = pos + dir.deltas();
-                        if let Some(neighbor) = self.stable_blocks.get(&neighbor_pos) {
-                            let connects = match (
-                                &block.connectors[dir as usize],
-                                &neighbor.connectors[dir.flip() as usize],
-                            ) {
-                                (Some(a), Some(b)) => a.links_with(b),
-                                _ => false,
-                            };
-                            if connects {
-                                queries.push(neighbor_pos);
-                            }
-                        }
-                    }
-                }
+            .drain_where(|pos, _| !stable_poses.contains(pos));
+        let hazards = &self.hazards;
+        let (floating, falling_chunk): (Vec<_>, Vec<_>) =
+            loose.into_iter().partition(|(pos, block)| {
+                block.kind == BlockKind::Scaffold && hazards.is_submerged(*pos)
+            });
+        // scaffolding already bobbing up against something stays put
+        let (pinned, floating): (Vec<_>, Vec<_>) =
+            floating.into_iter().partition(|(pos, block)| {
+                Self::is_stable(&self.stable_blocks, *pos, block, gravity.flipped())
+            });
+        for (pos, block) in pinned {
+            self.stable_blocks.insert(pos, block);
+        }
+        let loose_count = falling_chunk.len() + floating.len();
+        if loose_count > 0 {
+            self.emit(PlayEvent::ChunkFell { size: loose_count });
+            self.biggest_collapse = self.biggest_collapse.max(loose_count as u32);
+            self.race_chunk_fell(loose_count);
+            if let Some(versus) = &mut self.versus {
+                let mass = falling_chunk
+                    .iter()
+                    .chain(floating.iter())
+                    .map(|(_, block)| block.mass())
+                    .sum();
+                versus.lost(mass, loose_count);
             }
         }
 
-        let falling_chunk = self
-            .stable_blocks
-            .drain_filter(|pos, _| !stable_poses.contains(pos))
-            .collect_vec();
-        self.audio.fall = !falling_chunk.is_empty();
-
         let falling_chunk = FallingBlockChunk {
             blocks: falling_chunk,
             dy: 0.0,
+            prev_dy: 0.0,
             time_alive: 0,
+            floating: false,
         };
         self.falling_blocks.push(falling_chunk);
+        if !floating.is_empty() {
+            self.falling_blocks.push(FallingBlockChunk {
+                blocks: floating,
+                dy: 0.0,
+                prev_dy: 0.0,
+                time_alive: 0,
+                floating: true,
+            });
+        }
 
         // Update falling blocks
+        // Below this they're gone for good
+        let bottom = self.despawn_row();
         // do this stupid backwards dance because of borrow errors
         for chunk_idx in (0..self.falling_blocks.len()).rev() {
             let chunk = self.falling_blocks.get_mut(chunk_idx).unwrap();
+            let gravity = chunk.gravity(gravity);
             let original_dy = chunk.dy;
+            chunk.prev_dy = chunk.dy;
             chunk.dy += (FALL_ACCELLERATION * chunk.time_alive as f32).min(FALL_TERMINAL);
             // Record how many blocks we fell past.
             let delta = chunk.dy as isize - (original_dy as isize - 1);
@@ -273,17 +741,23 @@ impl ModePlaying {
                 let (pos, block) = chunk.blocks.get_mut(faller_idx).unwrap();
                 // Starting down and moving up, check everything we fell past
                 for diff in 0..delta {
-                    let passed_y = pos.y + chunk.dy as isize - diff;
-                    if passed_y < (self.max_depth + BOTTOM_VIEW_SIZE * 2) {
+                    let fallen = (chunk.dy as isize - diff) * gravity.sign();
+                    let passed_y = pos.y + fallen;
+                    let in_bounds = match gravity {
+                        Gravity::Down => passed_y < bottom,
+                        // it smashes on the cavern floor
+                        Gravity::Up => passed_y >= 0,
+                    };
+                    if in_bounds {
                         // k we're in bounds, don't de;ete it
                         removal = Removal::Keep;
                     }
 
                     let rounded_pos = ICoord::new(pos.x, passed_y);
-                    let links = Self::is_stable(&self.stable_blocks, rounded_pos, &block);
+                    let links = Self::is_stable(&self.stable_blocks, rounded_pos, &block, gravity);
                     if links {
                         // we link up here with this offset!
-                        removal = Removal::InsertWithDelta(chunk.dy as isize - diff);
+                        removal = Removal::InsertWithDelta(fallen);
                         break 'block;
                     }
                 }
@@ -308,99 +782,202 @@ impl ModePlaying {
             }
         }
 
+        if let Some(ghost) = &mut self.ghost {
+            ghost.step(globals);
+        }
+
         self.frames_elapsed += 1;
+        self.just_started = false;
+
+        if !self
+            .stable_blocks
+            .values()
+            .any(|block| block.kind == BlockKind::Anchor)
+        {
+            // Nothing's holding anything up anymore; it's over
+            return self.end_run(globals);
+        }
+        if self.reached_ceiling() || self.objective_met() || self.race_collapse.is_some() {
+            return self.end_run(globals);
+        }
+        if self.config.block_budget.is_some()
+            && self.conveyor_blocks.is_empty()
+            && self.falling_blocks.is_empty()
+        {
+            // Spent the whole budget and it's all settled
+            return self.end_run(globals);
+        }
+        if let Some(Versus {
+            collapse: Some(_), ..
+        }) = &self.versus
+        {
+            // Somebody blew it
+            return self.end_run(globals);
+        }
+        if self.config.save_policy.autosaves() && self.frames_elapsed % AUTOSAVE_INTERVAL == 0 {
+            self.autosave();
+        }
+        if self.frames_elapsed % HOUSEKEEPING_INTERVAL == 0 {
+            self.housekeeping();
+        }
+        if self.config.rewindable && self.frames_elapsed % TIMELINE_INTERVAL == 0 {
+            self.timeline.record(self.snapshot());
+        }
+
         Transition::None
     }
 
-    fn handle_input(&mut self, globals: &mut Globals) -> Transition {
+    fn handle_input(&mut self, input: InputFrame, globals: &mut Globals) -> Transition {
         use macroquad::prelude::*;
 
-        let (mx, my) = mouse_position_pixel();
-
-        let scroll_y = mouse_wheel().1;
-        if my < SCROLL_HOTZONE_SIZE {
-            self.scroll_depth -= SCROLL_SPEED * (SCROLL_HOTZONE_SIZE - my) / SCROLL_HOTZONE_SIZE;
+        let (mx, my) = input.mouse;
+
+        let scroll_y = input.wheel as f32;
+        let scroll_speed = SCROLL_SPEED
+            * self
+                .scroll_speed_override
+                .unwrap_or(globals.settings.scroll_speed) as f32
+            / 100.0;
+        let scroll_before = self.scroll_depth;
+        // how much deeper scrolling down the screen goes
+        let downwards = self.config.gravity.sign() as f32;
+        // Where the mouse is along the scroll axis, and where the far end of the view is.
+        // Sideways, the conveyor is in the way of the right edge, so it doesn't count.
+        let (along, far_end) = match self.config.scroll_axis {
+            ScrollAxis::Vertical => (my, HEIGHT),
+            ScrollAxis::Horizontal => {
+                let end = WIDTH - 70.0;
+                (if mx < end { mx } else { end / 2.0 }, end)
+            }
+        };
+        if along < SCROLL_HOTZONE_SIZE {
+            self.scroll_depth -=
+                downwards * scroll_speed * (SCROLL_HOTZONE_SIZE - along) / SCROLL_HOTZONE_SIZE;
         }
-        if self.held.is_none() && scroll_y > 0.0 {
+        // the wheel turns a block picked up this frame instead of scrolling
+        let wheel_scrolls = self.held.is_none() && input.pressed_at.is_none();
+        if wheel_scrolls && scroll_y > 0.0 {
             // mouse wheel seems to only trigger every few frames so we speed it up;
-            self.scroll_depth -= 2.0 * SCROLL_SPEED;
+            self.scroll_depth -= downwards * 2.0 * scroll_speed;
         }
-        if my > HEIGHT - SCROLL_HOTZONE_SIZE {
-            self.scroll_depth +=
-                SCROLL_SPEED * (my - HEIGHT + SCROLL_HOTZONE_SIZE) / SCROLL_HOTZONE_SIZE;
+        if along > far_end - SCROLL_HOTZONE_SIZE {
+            self.scroll_depth += downwards * scroll_speed * (along - far_end + SCROLL_HOTZONE_SIZE)
+                / SCROLL_HOTZONE_SIZE;
         }
-        if self.held.is_none() && scroll_y < 0.0 {
-            self.scroll_depth += 2.0 * SCROLL_SPEED;
+        if wheel_scrolls && scroll_y < 0.0 {
+            self.scroll_depth += downwards * 2.0 * scroll_speed;
+        }
+        if self.scroll_depth != scroll_before {
+            self.manual_scroll_timer = MANUAL_SCROLL_GRACE;
+        } else if self.manual_scroll_timer > 0 {
+            self.manual_scroll_timer -= 1;
+        } else if let Some(goal) =
+            self.camera_goal(self.camera_override.unwrap_or(globals.settings.camera))
+        {
+            self.scroll_depth += (goal - self.scroll_depth) * CAMERA_EASING;
         }
         self.scroll_depth = self
             .scroll_depth
-            .clamp(0.0, (self.max_depth + BOTTOM_VIEW_SIZE) as f32);
+            .clamp(self.min_scroll(), self.max_scroll());
+
+        let undo_clicked = input.left_pressed
+            && !self.undo.is_empty()
+            && undo::undo_button().rect.contains(vec2(mx, my));
+        if self.can_undo() && (input.undo || undo_clicked) {
+            self.undo_placement();
+            return Transition::None;
+        }
+
+        // escape puts down what's in hand before it quits anything
+        let put_back = input.suspend && self.held.take().is_some();
+        if put_back {
+            self.emit(PlayEvent::Returned);
+        }
+
+        if let Some(info) = &self.held {
+            if input.right_pressed {
+                let idx = info.idx;
+                self.buy_anchor(Some(idx));
+            }
+        } else if self.can_buy_blocks()
+            && input.left_pressed
+            && anchor_button(self.anchor_cost())
+                .rect
+                .contains(vec2(mx, my))
+        {
+            self.buy_anchor(None);
+        }
 
         match &mut self.held {
             None => {
-                if is_mouse_button_down(MouseButton::Left)
-                    && mx > WIDTH - 64.0
-                    && mx < WIDTH - 32.0
-                    && my > 40.0
-                    && my < 200.0
-                {
-                    // we're in the conveyor pickup zone
-                    let remainder = (CONVEYOR_Y_BOTTOM - my + BLOCK_SIZE) % 24.0;
-                    if remainder < 16.0 {
-                        let idx = ((CONVEYOR_Y_BOTTOM - my + BLOCK_SIZE) / 24.0) as usize;
-                        if self.conveyor_blocks.len() > idx {
-                            self.held = Some(HoldInfo { idx });
-                            self.audio.pick_up = true;
+                let press = match input.pressed_at {
+                    Some(pos) => Some(pos),
+                    None if input.left_down => Some((mx, my)),
+                    None => None,
+                };
+                if let (Some((px, py)), 0) = (press, self.cooldown_left) {
+                    let slot = self
+                        .conveyor_slot_at(px, py)
+                        .filter(|&idx| !self.is_slot_held(idx));
+                    if let Some(idx) = slot {
+                        self.held = Some(HoldInfo { idx, rotation: 0.0 });
+                        self.emit(PlayEvent::PickedUp);
+
+                        // The rest of a drag that happened all within this frame
+                        let frame = self.frames_elapsed;
+                        if let (Some(pos), false) = (input.released_at, input.left_down) {
+                            self.input_buffer.buffer_release(frame, pos);
+                        }
+                        if input.wheel != 0 {
+                            self.input_buffer.buffer_wheel(frame, input.wheel);
                         }
                     }
                 }
 
-                if is_mouse_button_pressed(MouseButton::Left) {
-                    let blockpos = self.pixel_to_block(mx, my);
-                    match self.stable_blocks.get_mut(&blockpos) {
-                        Some(block) if block.is_removable() => {
+                if input.left_pressed && hold::reroll_rect().contains(vec2(mx, my)) {
+                    self.reroll();
+                } else if input.left_pressed && !hold::covers(mx, my) {
+                    let blockpos = self.viewport().pixel_to_block(mx, my);
+                    if let Some(block) = self.stable_blocks.get_mut(&blockpos) {
+                        if block.is_removable() {
                             block.damage += 1;
-                            self.audio.damage = true;
+                            self.emit(PlayEvent::BlockDamaged { pos: blockpos });
                         }
-                        _ => {}
                     }
                 }
             }
             Some(info) => {
-                if scroll_y > 0.0 {
-                    self.conveyor_blocks[info.idx].connectors.rotate_left(1);
-                    self.audio.rotate = true;
-                } else if scroll_y < 0.0 {
-                    self.conveyor_blocks[info.idx].connectors.rotate_right(1);
-                    self.audio.rotate = true;
+                let frame = self.frames_elapsed;
+                let wheel = match input.wheel {
+                    0 => self.input_buffer.take_wheel(frame),
+                    wheel => wheel,
+                };
+                if wheel != 0 {
+                    info.rotate(&mut self.conveyor_blocks[info.idx], wheel > 0);
+                }
+                info.swing();
+                let idx = info.idx;
+                if wheel != 0 {
+                    self.emit(PlayEvent::Rotated);
                 }
 
-                if !is_mouse_button_down(MouseButton::Left) {
-                    let idx = info.idx;
-                    let blockpos = self.pixel_to_block(mx, my);
-
+                // if it was picked up with the keyboard, that's what puts it down
+                if !input.left_down && !self.cursor.active {
+                    // put it where it was let go, even if the mouse has moved on since
+                    let buffered = self.input_buffer.take_release(frame);
+                    let (rx, ry) = input.released_at.or(buffered).unwrap_or((mx, my));
                     let block = self.conveyor_blocks.get(idx).unwrap();
-                    let valid_pos = block.is_valid_pos(blockpos);
-                    let anchored_ok = if block.kind == BlockKind::Anchor {
-                        // anchors must match up in order to be placed
-                        Self::can_anchor_be_placed(&self.stable_blocks, blockpos, block)
-                    } else {
-                        true
-                    };
 
-                    if valid_pos && anchored_ok && !self.stable_blocks.contains_key(&blockpos) {
+                    if hold::hold_slot_rect().contains(vec2(rx, ry)) {
+                        self.stash(idx);
+                    } else if rx >= WIDTH - 70.0 {
+                        // let go over the conveyor, so it goes back where it came from
+                        self.emit(PlayEvent::Returned);
+                    } else if let Some(blockpos) = self.placement_target(block, rx, ry) {
                         // poggers
-                        let block = self.conveyor_blocks.remove(idx);
-                        self.stable_blocks.insert(blockpos, block);
-
-                        if self.blocks_left > 0 {
-                            self.blocks_left -= 1;
-                            self.conveyor_blocks.push(QuadRand.gen());
-                        }
-
-                        self.audio.put_down = true;
+                        self.place_from_conveyor(idx, blockpos);
                     } else {
-                        self.audio.rotate = true;
+                        self.emit(PlayEvent::Dropped);
                     }
                     // in any case stop holding it
                     self.held = None;
@@ -408,289 +985,1541 @@ impl ModePlaying {
             }
         }
 
-        if self.conveyor_blocks.is_empty()
-            && is_mouse_button_pressed(MouseButton::Left)
-            && Rect::new(WIDTH - 70.0 + 16.0, 224.0, 32.0, 16.0).contains(vec2(mx, my))
-        {
-            macroquad::audio::stop_sound(globals.assets.sounds.engineer_gaming);
-            Transition::Swap(Gamemode::Denoument(ModeDenoument::new(self.center_of_mass)))
+        let finish_clicked = input.left_pressed
+            && Rect::new(WIDTH - 70.0 + 16.0, 224.0, 32.0, 16.0).contains(vec2(mx, my));
+        if self.can_finish() && (finish_clicked || input.keyboard.finish) {
+            self.end_run(globals)
+        } else if input.suspend && !put_back && self.config.sandbox {
+            // back to wherever it was tried from
+            Transition::Pop
+        } else if input.suspend && !put_back && self.can_suspend() {
+            // Save and quit
+            self.autosave();
+            Transition::Swap(Box::new(ModeTitle::new()))
         } else {
             Transition::None
         }
     }
 
-    pub fn draw(&self, globals: &Globals) {
-        use macroquad::{audio::*, prelude::*};
+    /// Finish the run and show the results.
+    fn end_run(&self, globals: &mut Globals) -> Transition {
+        self.delete_autosave();
+
+        let result = RunResult {
+            game_type: self.config.game_type,
+            depth: self.report.center_of_mass,
+            points: self.points,
+            blocks_placed: self.blocks_placed,
+            assisted: self.config.assisted,
+            tower_height: self.report.tower_height as u32,
+            artifacts: self.artifacts.found_count() as u32,
+            biggest_collapse: self.biggest_collapse,
+            objective_met: self.objective_met(),
+            blueprint: self.config.blueprint.as_ref().map(|blueprint| {
+                let accuracy = blueprint.accuracy(&self.stable_blocks);
+                BlueprintResult {
+                    name: blueprint.name.clone(),
+                    accuracy,
+                    frames: self.frames_elapsed,
+                    score: blueprint::blueprint_score(accuracy, self.frames_elapsed),
+                }
+            }),
+        };
+        if let Some(versus) = &self.versus {
+            return Transition::Swap(Box::new(ModeVersusResult::new(
+                versus.clone(),
+                result,
+                self.config.clone(),
+            )));
+        }
+        let replay = self.recording.as_ref().map(|inputs| Replay {
+            header: ReplayHeader {
+                version: REPLAY_VERSION,
+                seed: self.rng.seed(),
+                config: self.config.clone(),
+                camera: self.camera_override.unwrap_or(globals.settings.camera),
+                scroll_speed: self
+                    .scroll_speed_override
+                    .unwrap_or(globals.settings.scroll_speed),
+                player: globals.profile.name.clone(),
+                result: result.clone(),
+            },
+            inputs: inputs.clone(),
+            events: self.events.clone(),
+        });
+        Transition::Swap(Box::new(ModeDenoument::new(
+            result,
+            self.config.clone(),
+            self.rng.seed(),
+            self.filmstrip.clone(),
+            self.results_photo(globals),
+            replay,
+        )))
+    }
+
+    /// Throw this run away and start a fresh one under the same rules.
+    /// Holding shift keeps the seed, to try the exact same run again.
+    fn quick_restart(&self) -> Transition {
+        use macroquad::prelude::*;
 
-        if self.frames_elapsed == 0 {
-            play_sound(
-                globals.assets.sounds.engineer_gaming,
-                PlaySoundParams {
-                    looped: true,
-                    volume: 0.7,
-                },
-            );
+        self.delete_autosave();
+        let config = self.config.clone();
+        let keep_seed = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        // a daily run is only ever played with that day's seed
+        let fresh = if keep_seed || config.daily.is_some() {
+            ModePlaying::with_seed(config, self.rng.seed())
+        } else {
+            ModePlaying::new(config)
+        };
+        Transition::Swap(Box::new(fresh))
+    }
+
+    /// Whether this run can be saved to pick up later.
+    /// Hardcore runs only get one chance.
+    fn can_suspend(&self) -> bool {
+        match self.config.save_policy {
+            SavePolicy::Never => false,
+            SavePolicy::Hardcore => !self.resumed,
+            SavePolicy::Suspend => true,
+        }
+    }
+
+    /// Get rid of this run's autosave, once it can't be picked back up.
+    /// Runs that only save on quitting have nothing to get rid of;
+    /// their save slot might have some other run in it.
+    fn delete_autosave(&self) {
+        if let (true, Some(save_name)) = (
+            self.config.save_policy.autosaves(),
+            self.config.save_policy.save_name(),
+        ) {
+            save::delete(save_name);
+        }
+    }
+
+    fn autosave(&self) {
+        if let (true, Some(save_name)) = (self.can_suspend(), self.config.save_policy.save_name()) {
+            self.snapshot().save(save_name);
         }
-        let mut sounds = vec![];
-        if self.audio.damage {
-            sounds.push(globals.assets.sounds.damage);
+    }
+
+    /// Where the camera wants to scroll to on its own, if anywhere
+    fn camera_goal(&self, mode: CameraMode) -> Option<f32> {
+        match mode {
+            CameraMode::Manual => None,
+            CameraMode::FollowPlacement => self
+                .last_placed
+                .map(|pos| self.config.scroll_axis.reach(pos) as f32),
+            CameraMode::CenterOfMass => Some(self.report.center_of_mass),
         }
-        if self.audio.fall {
-            sounds.push(globals.assets.sounds.fall);
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        if self.paused && !paused && self.config.rewindable {
+            // Playing on from wherever we scrubbed to
+            self.timeline.branch();
         }
-        if self.audio.pick_up {
-            sounds.push(globals.assets.sounds.pickup);
+        self.paused = paused;
+        self.held = None;
+        if let Some(second) = &mut self.second {
+            second.held = None;
         }
-        if self.audio.put_down {
-            sounds.push(globals.assets.sounds.putdown);
+    }
+
+    /// Scrub through the timeline while paused.
+    fn update_paused(&mut self) {
+        use macroquad::prelude::*;
+
+        if !self.config.rewindable {
+            return;
         }
-        if self.audio.rotate {
-            sounds.push(globals.assets.sounds.rotate);
+        let cursor = self.timeline.cursor();
+        let (mx, my) = crate::drawutils::mouse_position_pixel();
+        let target = if is_key_pressed(KeyCode::Left) {
+            Some(cursor.saturating_sub(1))
+        } else if is_key_pressed(KeyCode::Right) {
+            Some(cursor + 1)
+        } else if is_mouse_button_down(MouseButton::Left) {
+            self.timeline.index_at(mx, my)
+        } else {
+            None
+        };
+
+        if let Some(target) = target.filter(|&target| target != cursor) {
+            if let Some(snapshot) = self.timeline.select(target).cloned() {
+                self.rewind_to(snapshot);
+            }
         }
-        for sound in sounds {
-            play_sound(
-                sound,
-                PlaySoundParams {
-                    looped: false,
-                    volume: 1.0,
-                },
+    }
+
+    /// Load a snapshot from the timeline in place, keeping everything that
+    /// isn't part of the run itself.
+    fn rewind_to(&mut self, snapshot: RunSnapshot) {
+        let mut rewound = ModePlaying::from_snapshot(snapshot);
+        std::mem::swap(&mut rewound.timeline, &mut self.timeline);
+        std::mem::swap(&mut rewound.filmstrip, &mut self.filmstrip);
+        rewound.milestones_reached = self.milestones_reached;
+        rewound.zoomed = self.zoomed;
+        rewound.toasts = self.toasts.clone();
+        rewound.paused = true;
+        // it's the same run carrying on, not a new one
+        rewound.just_started = false;
+        *self = rewound;
+    }
+
+    /// Which block on the conveyor clicking here would grab, if any.
+    /// Picking up and drawing the highlight both go by this.
+    fn conveyor_slot_at(&self, mx: f32, my: f32) -> Option<usize> {
+        (0..self.conveyor_blocks.len())
+            .find(|&idx| conveyor_slot_rect(idx).contains(macroquad::math::vec2(mx, my)))
+    }
+
+    /// Draw a card next to the conveyor telling you about the block in this slot.
+    fn draw_block_card(&self, block: &Block, idx: usize, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let lines = [
+            block.kind.name().to_owned(),
+            format!("MASS {}", block.mass()),
+            format!("TOUGH {}", block.resilience()),
+        ];
+        // Room for the block and any connectors poking into the squares around it
+        let diagram_size = BLOCK_SIZE * 3.0;
+
+        let w = diagram_size + 8.0;
+        let h = lines.len() as f32 * 7.0 + diagram_size + 6.0;
+        let x = WIDTH - 70.0 + 4.0 - w;
+        let slot_y = CONVEYOR_Y_BOTTOM - idx as f32 * CONVEYOR_SLOT_HEIGHT;
+        let y = slot_y.min(HEIGHT - h - 4.0).max(4.0);
+        ui::draw_panel(Rect::new(x, y, w, h));
+
+        for (line_idx, line) in lines.iter().enumerate() {
+            drawutils::draw_text_px(
+                line,
+                x + 4.0,
+                y + 4.0 + line_idx as f32 * 7.0,
+                hexcolor(ui::FOREGROUND),
+                globals,
             );
         }
+        let diagram_y = y + lines.len() as f32 * 7.0 + 3.0;
+        self.draw_block(
+            block,
+            x + w / 2.0,
+            diagram_y + diagram_size / 2.0,
+            WHITE,
+            0.0,
+            globals,
+        );
+    }
+
+    /// Take a block off the conveyor.
+    /// The blocks above it slide down to fill the gap.
+    fn take_from_conveyor(&mut self, idx: usize) -> Block {
+        self.conveyor_offsets.remove(idx);
+        for offset in self.conveyor_offsets.iter_mut().skip(idx) {
+            *offset -= CONVEYOR_SLOT_HEIGHT;
+        }
+        // whoever's holding a block above it should keep holding the same one
+        for held in self.holds_mut() {
+            if held.idx > idx {
+                held.idx -= 1;
+            }
+        }
+        self.conveyor_blocks.remove(idx)
+    }
+
+    /// Put a new block on the bottom of the conveyor, pushing the rest up.
+    fn feed_conveyor(&mut self, block: Block) {
+        for offset in self.conveyor_offsets.iter_mut() {
+            *offset += CONVEYOR_SLOT_HEIGHT;
+        }
+        for held in self.holds_mut() {
+            held.idx += 1;
+        }
+        self.conveyor_blocks.insert(0, block);
+        self.conveyor_offsets.insert(0, CONVEYOR_SLOT_HEIGHT);
+    }
+
+    /// Slide the conveyor blocks towards their slots, and move the belt along with them.
+    fn update_conveyor(&mut self) {
+        // The belt goes along with the lowest block that's moving
+        let mut belt_moved = None;
+        for offset in self.conveyor_offsets.iter_mut() {
+            let step = offset.abs().min(CONVEYOR_SPEED) * offset.signum();
+            *offset -= step;
+            if step != 0.0 && belt_moved.is_none() {
+                belt_moved = Some(step);
+            }
+        }
+        if let Some(step) = belt_moved {
+            self.belt_scroll -= step;
+        }
+    }
 
-        let (mx, my) = mouse_position_pixel();
+    /// Put the block from this conveyor slot into the structure here,
+    /// and do all the bookkeeping that goes with placing a block.
+    fn place_from_conveyor(&mut self, idx: usize, pos: ICoord) {
+        self.remember_placement(pos);
+        let block = self.take_from_conveyor(idx);
+        if block.kind == BlockKind::Solid {
+            self.solids_placed += 1;
+        }
+        self.stable_blocks.insert(pos, block);
+        self.blocks_placed += 1;
+        self.last_placed = Some(pos);
+        self.record_macro_step(pos);
+        // before scoring, so a placement that keeps the combo going counts towards it
+        let links = Self::link_count(&self.stable_blocks, pos, &self.stable_blocks[&pos]);
+        self.emit(PlayEvent::BlockPlaced { pos, links });
+        self.score_bridge(pos);
+        self.collect_artifacts(pos);
+        if let Some(versus) = &mut self.versus {
+            versus.placed();
+        }
 
-        clear_background(BLUE);
+        self.top_up_conveyor();
+        self.cooldown_left = self.config.placement_cooldown;
+    }
 
-        // Draw background
-        let top_row = self.scroll_depth.floor() as isize - SCREEN_HEIGHT / 2;
-        for y_idx in -1..SCREEN_HEIGHT + 1 {
-            let row = top_row + y_idx;
-            if row < 0 {
-                continue;
+    /// Bring the next block onto the conveyor to fill a gap, if there are any left.
+    fn top_up_conveyor(&mut self) {
+        match &mut self.blocks_left {
+            Some(0) => {}
+            Some(left) => {
+                *left -= 1;
+                let block = self.spawner.next();
+                self.feed_conveyor(block);
             }
-            // i don't know why this 0.5 is needed
-            let deficit = self.scroll_depth.fract() - 0.5;
+            None => {
+                let block = self.spawner.next();
+                self.feed_conveyor(block);
+            }
+        }
+    }
 
-            for x_idx in -1..SCREEN_WIDTH + 1 {
-                let col = x_idx - SCREEN_WIDTH / 2;
-                let mut rng = SmallRng::seed_from_u64(row as u64 ^ (col as u64).rotate_left(32));
+    /// Every block being held by anyone
+    fn holds_mut(&mut self) -> impl Iterator<Item = &mut HoldInfo> {
+        let second = self.second.as_mut().and_then(|second| second.held.as_mut());
+        self.held.iter_mut().chain(second)
+    }
 
-                let (tex, rot) = if col.abs() < CHASM_WIDTH / 2 + 1 {
-                    // we're inside the chasm
-                    let depth_mod = row as f32 / 20.0 + rng.gen_range(-0.2..0.2);
-                    let tex = if rng.gen_range(0.0..1.0) < depth_mod {
-                        let depth_mod = row as f32 / 100.0 + rng.gen_range(-0.5..0.5);
-                        if rng.gen_range(0.0..1.0) < depth_mod {
-                            globals.assets.textures.stone3
-                        } else {
-                            globals.assets.textures.stone2
-                        }
-                    } else {
-                        globals.assets.textures.stone
-                    };
-                    (tex, 0.0)
-                } else if row == 0 {
-                    // we're at the top of the chasm
-                    (globals.assets.textures.dirt_edge, -TAU / 4.0)
-                } else if col.abs() == CHASM_WIDTH / 2 + 1 {
-                    // we're at the chasm edge
-                    let rot = if col > 0 { TAU / 2.0 } else { 0.0 };
-                    (globals.assets.textures.dirt_edge, rot)
-                } else {
-                    // we're in the chasm body
-                    let rot = if col > 0 { TAU / 2.0 } else { 0.0 };
-                    (globals.assets.textures.dirt_body, rot)
-                };
+    /// Whether anyone's holding the block in this conveyor slot
+    fn is_slot_held(&self, idx: usize) -> bool {
+        let second = self.second.as_ref().and_then(|second| second.held.as_ref());
+        self.held.iter().chain(second).any(|held| held.idx == idx)
+    }
 
-                // Based on the block position, get darker as we go deeper
-                let mut deepness_color = |depth_mod: f32| {
-                    let jitter = rng.gen_range(-0.2..0.2);
-                    let darkness = depth_mod / (-row as f32 - depth_mod) + 1.0;
-                    let lightness = 1.0 - darkness + jitter * 0.2;
-                    (lightness * 100.0).round() / 100.0
-                };
+    /// Whether anchors can be bought as extra blocks, instead of only by converting one.
+    /// Runs on a budget get exactly the blocks they're given.
+    fn can_buy_blocks(&self) -> bool {
+        self.config.block_budget.is_none()
+    }
 
-                let lightness = deepness_color(100.0).max(0.5);
-                let orangey = deepness_color(500.0) / 10.0;
-                let col = Color::new(
-                    lightness + orangey,
-                    lightness + orangey / 2.0,
-                    lightness,
-                    1.0,
-                );
+    /// Whether the player is allowed to end the run right now
+    fn can_finish(&self) -> bool {
+        self.config.finish_anytime || self.conveyor_blocks.is_empty()
+    }
 
-                let center_x = x_idx as f32 * BLOCK_SIZE;
-                let center_y = (y_idx as f32 - deficit) * BLOCK_SIZE;
-                draw_texture_ex(
-                    tex,
-                    center_x - BLOCK_SIZE / 2.0,
-                    center_y - BLOCK_SIZE / 2.0,
-                    col,
-                    DrawTextureParams {
-                        rotation: rot,
-                        ..Default::default()
-                    },
-                );
+    /// Draw the chasm, the structure, and the depth meter, in the view's own pixels.
+    fn draw_world(&self, view: &Viewport, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        // Draw background
+        match &self.background_cache {
+            Some((drawn_at, target)) if *drawn_at == view.scroll_depth => {
+                draw_texture(target.texture(), 0.0, 0.0, WHITE)
             }
+            _ => self.draw_backdrop(view, globals),
         }
 
-        for (&pos, block) in self.stable_blocks.iter() {
-            let (cx, cy) = self.block_to_pixel(pos);
-            // TODO: don't draw blocks offscreen?
-            block.draw_absolute(cx, cy, globals);
+        self.draw_artifacts(view, globals);
+        let on_screen = self
+            .stable_blocks
+            .in_area(view.visible_cols(), view.visible_rows());
+        for (&pos, block) in on_screen {
+            let (cx, cy) = view.block_to_pixel(pos);
+            self.draw_block(block, cx, cy, WHITE, 0.0, globals);
         }
+        self.draw_glue_seams(view);
+        self.draw_blueprint(view, globals);
+        self.draw_borders(view);
+        let alpha = self.sim_alpha(globals);
         for chunk in self.falling_blocks.iter() {
+            let dy = chunk.prev_dy + (chunk.dy - chunk.prev_dy) * alpha;
             for (pos, block) in chunk.blocks.iter() {
-                let fake_coord = ICoord::new(pos.x, 0);
-                let (cx, _) = self.block_to_pixel(fake_coord);
-                let cy = (pos.y as f32 + chunk.dy - self.scroll_depth) * BLOCK_SIZE + HEIGHT / 2.0;
-                block.draw_absolute(cx, cy, globals);
+                let fallen = dy * chunk.gravity(self.config.gravity).sign() as f32;
+                let (cx, cy) = view.world_to_pixel(pos.x as f32, pos.y as f32 + fallen);
+                self.draw_block(block, cx, cy, WHITE, 0.0, globals);
             }
         }
 
         // Draw the depth meter
-        let pixel_depth =
-            ((self.center_of_mass - self.scroll_depth) * BLOCK_SIZE + HEIGHT / 2.0).round();
-        draw_line(
-            BLOCK_SIZE * 2.0,
-            pixel_depth,
-            WIDTH + 10.0,
-            pixel_depth,
-            1.0,
-            drawutils::hexcolor(0xffee83aa),
-        );
-        let corner_x = BLOCK_SIZE * 2.0 - 16.0;
-        let corner_y = pixel_depth - 16.0;
-        draw_texture(
-            globals.assets.textures.depth_meter,
-            corner_x,
-            corner_y,
+        let pixel_depth = view.depth_to_pixel(self.report.center_of_mass).round();
+        let meter_color = drawutils::hexcolor(0xffee83aa);
+        let (corner_x, corner_y) = match self.config.scroll_axis {
+            ScrollAxis::Vertical => {
+                draw_line(
+                    BLOCK_SIZE * 2.0,
+                    pixel_depth,
+                    WIDTH + 10.0,
+                    pixel_depth,
+                    1.0,
+                    meter_color,
+                );
+                (BLOCK_SIZE * 2.0 - 16.0, pixel_depth - 16.0)
+            }
+            ScrollAxis::Horizontal => {
+                // hang it off the bottom of the tunnel, clear of the stats
+                let meter_y = HEIGHT - BLOCK_SIZE * 2.0;
+                draw_line(pixel_depth, 0.0, pixel_depth, meter_y, 1.0, meter_color);
+                (pixel_depth - 16.0, meter_y - 16.0)
+            }
+        };
+        draw_texture(
+            globals.assets.textures.depth_meter,
+            corner_x,
+            corner_y,
             WHITE,
         );
         // Draw the depth
         drawutils::draw_number(
-            self.center_of_mass.round() as i32,
+            self.report.center_of_mass.round() as i32,
             corner_x + 27.0,
             corner_y + 13.0,
             globals,
         );
+    }
 
-        // Draw the conveyor
-        let conveyor_x = WIDTH - 70.0;
-        draw_texture(globals.assets.textures.conveyor, conveyor_x, 0.0, WHITE);
-        for (idx, block) in self.conveyor_blocks.iter().enumerate() {
-            let (cx, cy, color) = if matches!(&self.held, Some(held) if held.idx == idx) {
-                let blockpos = self.pixel_to_block(mx, my);
-                let anchored_ok = if block.kind == BlockKind::Anchor {
-                    // anchors must match up in order to be placed
-                    Self::can_anchor_be_placed(&self.stable_blocks, blockpos, block)
-                } else {
-                    true
-                };
-                if block.is_valid_pos(blockpos) && anchored_ok {
-                    // we're at a good pos
-                    let (cx, cy) = self.block_to_pixel(blockpos);
-                    (cx, cy, Color::new(1.0, 1.0, 1.0, 0.8))
-                } else {
-                    (mx, my, Color::new(1.0, 1.0, 1.0, 0.7))
+    /// Draw where glue blocks are stuck to their neighbors, across the edge between them.
+    fn draw_glue_seams(&self, view: &Viewport) {
+        let color = drawutils::hexcolor(GLUE_SEAM_COLOR);
+        let glued = self
+            .stable_blocks
+            .in_area(view.visible_cols(), view.visible_rows())
+            .filter(|(_, block)| block.kind == BlockKind::Glue);
+        for (&pos, _) in glued {
+            let (x1, y1) = view.block_to_pixel(pos);
+            for &dir in Direction4::DIRECTIONS.iter() {
+                if !self.stable_blocks.contains_key(&(pos + dir.deltas())) {
+                    continue;
                 }
+                let (x2, y2) = view.block_to_pixel(pos + dir.deltas());
+                draw_across_edge((x1, y1), (x2, y2), GLUE_SEAM_LENGTH, 2.0, color);
+            }
+        }
+    }
+
+    /// Mark the edges of the held block that would link up where it's about to go,
+    /// and the ones where connectors meet but don't fit.
+    fn draw_link_hints(&self, view: &Viewport) {
+        let info = match &self.held {
+            Some(it) => it,
+            None => return,
+        };
+        let block = &self.conveyor_blocks[info.idx];
+        let target = if self.cursor.active {
+            Some(self.cursor.pos).filter(|&pos| self.can_place(block, pos))
+        } else {
+            let (mx, my) = self.last_input.mouse;
+            self.placement_target(block, mx, my)
+        };
+        let pos = match target {
+            Some(it) => it,
+            None => return,
+        };
+
+        let here = view.block_to_screen(pos);
+        for &dir in Direction4::DIRECTIONS.iter() {
+            let neighbor = match self.stable_blocks.get(&(pos + dir.deltas())) {
+                Some(it) => it,
+                None => continue,
+            };
+            let color = if Self::would_link(&self.stable_blocks, pos, block, dir) {
+                LINK_HINT_COLOR
+            } else if block.connectors[dir as usize].is_some()
+                || neighbor.connectors[dir.flip() as usize].is_some()
+            {
+                CLASH_HINT_COLOR
             } else {
-                let cx = WIDTH - 70.0 + 24.0 + BLOCK_SIZE / 2.0;
-                let cy = CONVEYOR_Y_BOTTOM - idx as f32 * 24.0 + BLOCK_SIZE / 2.0;
-                (cx, cy, WHITE)
+                // two smooth faces just sit there
+                continue;
             };
+            let there = view.block_to_screen(pos + dir.deltas());
+            draw_across_edge(here, there, BLOCK_SIZE, 3.0, drawutils::hexcolor(color));
+        }
+    }
 
-            block.draw_absolute_color(cx, cy, color, globals);
+    /// Draw whichever background this run has.
+    fn draw_backdrop(&self, view: &Viewport, globals: &Globals) {
+        if self.config.scroll_axis == ScrollAxis::Horizontal {
+            self.draw_shaft_background(view, globals);
+        } else if self.config.gravity == Gravity::Up {
+            drawutils::draw_mirrored(HEIGHT / 2.0, || self.draw_background(view, globals));
+        } else {
+            self.draw_background(view, globals);
         }
-        // Draw the blocks left
-        drawutils::draw_number(self.blocks_left as i32, conveyor_x + 25.0, 6.0, globals);
+    }
 
-        if self.conveyor_blocks.is_empty() {
-            draw_texture(
-                globals.assets.textures.finish_popup,
-                conveyor_x + 16.0,
-                224.0,
-                WHITE,
+    /// Draw the rock and dirt, as if gravity was down.
+    fn draw_background(&self, view: &Viewport, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let cavern = self.config.gravity == Gravity::Up;
+        let theme = globals.theme();
+        // upside-down runs get this mirrored afterwards
+        let view = Viewport {
+            gravity: Gravity::Down,
+            ..*view
+        };
+        for row in view.visible_rows() {
+            if row < 0 && !cavern {
+                if self.config.tower {
+                    self.draw_sky_row(&view, row);
+                }
+                continue;
+            }
+
+            for col in view.visible_cols() {
+                let mut rng = SmallRng::seed_from_u64(row as u64 ^ (col as u64).rotate_left(32));
+
+                let pos = ICoord::new(col, row);
+                let inside = self.profile.is_open(pos);
+                let ceiling = self.ceiling_at(col);
+                let (tex, rot) = if cavern && row < 0 {
+                    // we're in the cavern floor
+                    if inside && row == -1 {
+                        (globals.assets.textures.dirt_edge, TAU / 4.0)
+                    } else {
+                        (globals.assets.textures.dirt_body, 0.0)
+                    }
+                } else if inside && ceiling.map_or(false, |ceiling| row >= ceiling) {
+                    // we're in the cavern ceiling
+                    if Some(row) == ceiling {
+                        (globals.assets.textures.dirt_edge, -TAU / 4.0)
+                    } else {
+                        (globals.assets.textures.dirt_body, 0.0)
+                    }
+                } else if inside {
+                    // we're inside the chasm
+                    let depth_mod = row as f32 / 20.0 + rng.gen_range(-0.2..0.2);
+                    let tex = if rng.gen_range(0.0..1.0) < depth_mod {
+                        let depth_mod = row as f32 / 100.0 + rng.gen_range(-0.5..0.5);
+                        if rng.gen_range(0.0..1.0) < depth_mod {
+                            globals.assets.textures.stone3
+                        } else {
+                            globals.assets.textures.stone2
+                        }
+                    } else {
+                        globals.assets.textures.stone
+                    };
+                    (tex, 0.0)
+                } else if row == 0 && !cavern {
+                    // we're at the top of the chasm
+                    (globals.assets.textures.dirt_edge, -TAU / 4.0)
+                } else if self.profile.is_wall_face(pos) {
+                    // we're at the chasm edge
+                    let rot = if col > 0 { TAU / 2.0 } else { 0.0 };
+                    (globals.assets.textures.dirt_edge, rot)
+                } else if self.profile.is_open(pos + Direction4::North.deltas()) {
+                    // we're on a ledge where the chasm narrows
+                    (globals.assets.textures.dirt_edge, -TAU / 4.0)
+                } else if self.profile.is_open(pos + Direction4::South.deltas()) {
+                    // we're over a spot where the chasm widens
+                    (globals.assets.textures.dirt_edge, TAU / 4.0)
+                } else {
+                    // we're in the chasm body
+                    let rot = if col > 0 { TAU / 2.0 } else { 0.0 };
+                    (globals.assets.textures.dirt_body, rot)
+                };
+
+                let color = seasonal::tint(rock_color(row, &mut rng), theme.rock_tint);
+
+                let (center_x, center_y) = view.block_to_pixel(pos);
+                let (corner_x, corner_y) =
+                    (center_x - BLOCK_SIZE / 2.0, center_y - BLOCK_SIZE / 2.0);
+                draw_texture_ex(
+                    tex,
+                    corner_x,
+                    corner_y,
+                    color,
+                    DrawTextureParams {
+                        rotation: rot,
+                        ..Default::default()
+                    },
+                );
+                if inside && self.hazards.is_submerged(pos) {
+                    let water = drawutils::hexcolor(WATER_COLOR);
+                    draw_rectangle(corner_x, corner_y, BLOCK_SIZE, BLOCK_SIZE, water);
+                    if self.hazards.is_surface(pos) {
+                        let surface = drawutils::hexcolor(WATER_SURFACE_COLOR);
+                        draw_rectangle(corner_x, corner_y, BLOCK_SIZE, 2.0, surface);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw a band of sky above the rim, bluer the higher it is.
+    fn draw_sky_row(&self, view: &Viewport, row: isize) {
+        use macroquad::prelude::*;
+
+        let height = (-row as f32 / 40.0).min(1.0);
+        let color = Color::new(
+            0.65 - height * 0.35,
+            0.85 - height * 0.3,
+            0.95 - height * 0.1,
+            1.0,
+        );
+        let (_, cy) = view.block_to_pixel(ICoord::new(0, row));
+        draw_rectangle(0.0, cy - BLOCK_SIZE / 2.0, WIDTH, BLOCK_SIZE, color);
+    }
+
+    /// Draw the tunnel and its end wall, for horizontal runs.
+    fn draw_shaft_background(&self, view: &Viewport, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let theme = globals.theme();
+        for col in view.visible_cols() {
+            for row in view.visible_rows() {
+                let mut rng = SmallRng::seed_from_u64(row as u64 ^ (col as u64).rotate_left(32));
+                let (tex, rot) = if col < 0 || row < -1 || (col == 0 && row == -1) {
+                    // we're in the rock around the tunnel
+                    (globals.assets.textures.dirt_body, 0.0)
+                } else if col == 0 {
+                    // we're at the end wall
+                    (globals.assets.textures.dirt_edge, 0.0)
+                } else if row == -1 {
+                    // we're at the ceiling
+                    (globals.assets.textures.dirt_edge, TAU / 4.0)
+                } else if row >= SHAFT_HEIGHT {
+                    // we're looking down the drop under the tunnel
+                    (globals.assets.textures.stone3, 0.0)
+                } else {
+                    let depth_mod = col as f32 / 20.0 + rng.gen_range(-0.2..0.2);
+                    let tex = if rng.gen_range(0.0..1.0) < depth_mod {
+                        globals.assets.textures.stone2
+                    } else {
+                        globals.assets.textures.stone
+                    };
+                    (tex, 0.0)
+                };
+
+                let mut color = seasonal::tint(rock_color(col, &mut rng), theme.rock_tint);
+                if row >= SHAFT_HEIGHT {
+                    let shade = 0.4 / (row - SHAFT_HEIGHT + 1) as f32;
+                    color = Color::new(color.r * shade, color.g * shade, color.b * shade, 1.0);
+                }
+                let (cx, cy) = view.block_to_pixel(ICoord::new(col, row));
+                draw_texture_ex(
+                    tex,
+                    cx - BLOCK_SIZE / 2.0,
+                    cy - BLOCK_SIZE / 2.0,
+                    color,
+                    DrawTextureParams {
+                        rotation: rot,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
+
+    /// Draw the little panel of live stats in the corner.
+    fn draw_stats(&self, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let efficiency = score::efficiency(self.report.center_of_mass, self.blocks_placed);
+        let mut lines = vec![
+            format!("PTS {}", self.points),
+            format!("BLOCKS {}", self.blocks_placed),
+            format!("EFF {:.2}", efficiency),
+        ];
+        if self.artifacts.total() > 0 {
+            lines.push(format!(
+                "FINDS {}/{}",
+                self.artifacts.found_count(),
+                self.artifacts.total()
+            ));
+        }
+        if self.config.assisted {
+            lines.push("ASSISTED".to_owned());
+        }
+        if !self.sim_speed.is_normal() {
+            lines.push(self.sim_speed.label());
+        }
+        if let Some(label) = self.macro_label() {
+            lines.push(label);
+        }
+        if let Some(blueprint) = &self.config.blueprint {
+            let accuracy = blueprint.accuracy(&self.stable_blocks);
+            lines.push(format!("MATCH {}%", (accuracy * 100.0).round() as i32));
+        }
+        let mut bg = hexcolor(ui::BACKGROUND);
+        bg.a = 0.6;
+        draw_rectangle(2.0, 2.0, 48.0, lines.len() as f32 * 7.0 + 3.0, bg);
+        if self.can_suspend() {
+            drawutils::draw_text_px(
+                "ESC: SAVE + QUIT",
+                4.0,
+                HEIGHT - 9.0,
+                hexcolor(ui::DIM),
+                globals,
             );
         }
+        for (idx, line) in lines.iter().enumerate() {
+            drawutils::draw_text_px(
+                line,
+                4.0,
+                4.0 + idx as f32 * 7.0,
+                hexcolor(ui::FOREGROUND),
+                globals,
+            );
+        }
+    }
+
+    /// How many points the next anchor costs
+    fn anchor_cost(&self) -> u32 {
+        ANCHOR_BASE_COST.saturating_mul(1 << self.anchors_bought.min(16))
+    }
+
+    /// Spend points on an anchor.
+    ///
+    /// With `Some(idx)`, the block at that index in the conveyor gets turned into one.
+    /// Otherwise a new anchor goes on the bottom of the conveyor, bumping off the
+    /// block there if it's full.
+    fn buy_anchor(&mut self, convert: Option<usize>) {
+        let cost = self.anchor_cost();
+        if self.points < cost {
+            self.toasts
+                .push(format!("NEED {} POINTS FOR AN ANCHOR", cost));
+            return;
+        }
+        match convert {
+            Some(idx) if self.conveyor_blocks[idx].kind == BlockKind::Anchor => return,
+            Some(idx) => self.conveyor_blocks[idx].make_anchor(),
+            None if self.conveyor_blocks.len() < self.config.conveyor_size => {
+                let anchor = Block::anchor(&mut self.rng);
+                self.feed_conveyor(anchor);
+            }
+            None => {
+                // a full conveyor gives up its oldest block, but not one somebody's holding
+                let free = (0..self.conveyor_blocks.len()).find(|&idx| !self.is_slot_held(idx));
+                let idx = match free {
+                    Some(idx) => idx,
+                    None => {
+                        self.toasts.push("NO ROOM FOR AN ANCHOR");
+                        return;
+                    }
+                };
+                self.conveyor_blocks[idx] = Block::anchor(&mut self.rng);
+            }
+        }
+        self.points -= cost;
+        self.anchors_bought += 1;
+        // undoing from before this would hand the points back
+        self.undo.clear();
+        self.emit(PlayEvent::AnchorBought);
+    }
+
+    /// Award points if the block just placed here is part of a bridge.
+    fn score_bridge(&mut self, pos: ICoord) {
+        let length = Self::bridge_length(&self.stable_blocks, pos, self.config.gravity);
+        if length < BRIDGE_MIN_LENGTH {
+            return;
+        }
+        // The longer the bridge, the more each block in it is worth
+        let gained = self.award_points((length * length) as u32);
+        if length >= LONG_BRIDGE_LENGTH {
+            self.toasts
+                .push(format!("{}-BLOCK BRIDGE! +{}", length, gained));
+        }
     }
 
-    /// Check if a connector here facing in the specified direction would connect
+    /// How long the bridge running through this position is.
+    ///
+    /// A bridge is a row of blocks linked side-to-side with nothing directly underneath
+    /// any of them. Anchors hold bridges up but aren't part of them.
+    fn bridge_length(stable_blocks: &BlockMap, pos: ICoord, gravity: Gravity) -> usize {
+        let unsupported = |pos: ICoord| match stable_blocks.get(&pos) {
+            Some(block) => {
+                block.kind != BlockKind::Anchor
+                    && !stable_blocks.contains_key(&(pos + gravity.down().deltas()))
+            }
+            None => false,
+        };
+        if !unsupported(pos) {
+            return 0;
+        }
+
+        let mut length = 1;
+        for &dir in &[Direction4::East, Direction4::West] {
+            let mut here = pos;
+            loop {
+                let linked = Self::would_link(stable_blocks, here, &stable_blocks[&here], dir);
+                let next = here + dir.deltas();
+                if !linked || !unsupported(next) {
+                    break;
+                }
+                length += 1;
+                here = next;
+            }
+        }
+        length
+    }
+
+    /// Figure out where the held block would go if it was dropped with the mouse here,
+    /// or `None` if it can't go anywhere.
+    ///
+    /// With snap placement on, a drop one cell off from a spot where the block would link up
+    /// gets nudged over to that spot.
+    fn placement_target(&self, block: &Block, mx: f32, my: f32) -> Option<ICoord> {
+        let can_place = |pos: ICoord| self.can_place(block, pos);
+
+        let blockpos = self.viewport().pixel_to_block(mx, my);
+        let aimed_ok = can_place(blockpos);
+        if !self.config.snap_placement
+            || (aimed_ok && Self::link_count(&self.stable_blocks, blockpos, block) > 0)
+        {
+            return if aimed_ok { Some(blockpos) } else { None };
+        }
+
+        // Look for a better spot next door: most links first, then closest to the mouse
+        let snapped = Direction4::DIRECTIONS
+            .iter()
+            .map(|dir| blockpos + dir.deltas())
+            .filter(|&pos| can_place(pos))
+            .map(|pos| (pos, Self::link_count(&self.stable_blocks, pos, block)))
+            .filter(|&(_, links)| links > 0)
+            .max_by(|&(pos_a, links_a), &(pos_b, links_b)| {
+                let dist = |pos: ICoord| {
+                    let (cx, cy) = self.viewport().block_to_screen(pos);
+                    (cx - mx).powi(2) + (cy - my).powi(2)
+                };
+                links_a
+                    .cmp(&links_b)
+                    .then_with(|| dist(pos_b).partial_cmp(&dist(pos_a)).unwrap())
+            })
+            .map(|(pos, _)| pos);
+        snapped.or(if aimed_ok { Some(blockpos) } else { None })
+    }
+
+    /// Whether this block is allowed to go here
+    fn can_place(&self, block: &Block, pos: ICoord) -> bool {
+        let (axis, profile) = (self.config.scroll_axis, &self.profile);
+        let anchored_ok = match block.kind {
+            // anchors must match up in order to be placed
+            BlockKind::Anchor => {
+                Self::can_anchor_be_placed(&self.stable_blocks, pos, block, self.config.gravity)
+            }
+            // cranes have to hang off something anchored
+            BlockKind::Crane => {
+                let anchored = Self::anchored(&self.stable_blocks);
+                Direction4::DIRECTIONS.iter().any(|&dir| {
+                    anchored.contains(&(pos + dir.deltas()))
+                        && Self::would_link(&self.stable_blocks, pos, block, dir)
+                })
+            }
+            _ => true,
+        };
+        let under_ceiling = self
+            .ceiling_at(pos.x)
+            .map_or(true, |ceiling| pos.y < ceiling);
+        // above the rim, it goes by the columns of the chasm below
+        let on_surface = pos.y < 0
+            && self.config.tower
+            && block.kind != BlockKind::Anchor
+            && self.is_bridged()
+            && block.is_valid_pos(ICoord::new(pos.x, 0), axis, profile);
+        (block.is_valid_pos(pos, axis, profile) || on_surface)
+            && anchored_ok
+            && under_ceiling
+            && !self.stable_blocks.contains_key(&pos)
+            && !self.artifacts.is_buried_at(pos)
+    }
+
+    /// How many of this block's connectors would link up if it was here
+    fn link_count(stable_blocks: &BlockMap, pos: ICoord, block: &Block) -> usize {
+        Direction4::DIRECTIONS
+            .iter()
+            .filter(|&&dir| Self::would_link(stable_blocks, pos, block, dir))
+            .count()
+    }
+
+    /// Check if this block here would connect to whatever's next to it in the specified direction
     fn would_link(
-        stable_blocks: &HashMap<ICoord, Block>,
+        stable_blocks: &BlockMap,
         position: ICoord,
-        connector: &Connector,
+        block: &Block,
         facing: Direction4,
     ) -> bool {
         let target = position + facing.deltas();
-        if let Some(block) = stable_blocks.get(&target) {
-            let flip_dir = facing.flip();
-            match &block.connectors[flip_dir as usize] {
-                // ok this block has something; does it match?
-                Some(conn) => conn.links_with(connector),
-                // nothing matches with a smooth face
-                None => false,
-            }
-        } else {
+        let other = match stable_blocks.get(&target) {
+            Some(it) => it,
             // can't match with empty air
-            false
+            None => return false,
+        };
+        // glue sticks to anything, connectors or no
+        if block.kind == BlockKind::Glue || other.kind == BlockKind::Glue {
+            return true;
+        }
+        match (
+            &block.connectors[facing as usize],
+            &other.connectors[facing.flip() as usize],
+        ) {
+            // ok they both have something; does it match?
+            (Some(a), Some(b)) => a.links_with(b),
+            // nothing matches with a smooth face
+            _ => false,
+        }
+    }
+
+    /// Where the blocks that the anchors hold up are, by linking to them or resting on them.
+    /// Anything else falls.
+    fn held_up(stable_blocks: &BlockMap, gravity: Gravity) -> HashSet<ICoord> {
+        let mut queries = Self::anchored(stable_blocks).into_iter().collect_vec();
+        let mut stable_poses = HashSet::new();
+        while let Some(pos) = queries.pop() {
+            if stable_poses.insert(pos) {
+                // i've never met this coord in my life
+                for &dir in Direction4::DIRECTIONS.iter() {
+                    if Self::holds_up(stable_blocks, pos, dir, gravity) {
+                        queries.push(pos + dir.deltas());
+                    }
+                }
+            }
+        }
+        stable_poses
+    }
+
+    /// Where the anchors are, along with the cranes that link back to one.
+    /// Those count as anchors too, so they can hold things up from any side.
+    fn anchored(stable_blocks: &BlockMap) -> HashSet<ICoord> {
+        let mut queries = stable_blocks
+            .iter()
+            .filter_map(|(pos, block)| {
+                if block.kind == BlockKind::Anchor {
+                    Some(*pos)
+                } else {
+                    None
+                }
+            })
+            .collect_vec();
+        let mut anchored = HashSet::new();
+        while let Some(pos) = queries.pop() {
+            if !anchored.insert(pos) {
+                continue;
+            }
+            let block = &stable_blocks[&pos];
+            for &dir in Direction4::DIRECTIONS.iter() {
+                let next = pos + dir.deltas();
+                let is_crane =
+                    matches!(stable_blocks.get(&next), Some(it) if it.kind == BlockKind::Crane);
+                if is_crane && Self::would_link(stable_blocks, pos, block, dir) {
+                    queries.push(next);
+                }
+            }
+        }
+        anchored
+    }
+
+    /// Blow up everything around these explosive blocks, which already broke.
+    ///
+    /// Explosives caught in a blast go off too, until the chain runs out.
+    fn detonate(&mut self, mut blasts: Vec<ICoord>) {
+        let mut mass_broken = 0.0;
+        let mut blocks_broken = 0;
+        while let Some(center) = blasts.pop() {
+            self.emit(PlayEvent::Exploded { pos: center });
+            for dx in -BLAST_RADIUS..=BLAST_RADIUS {
+                for dy in -BLAST_RADIUS..=BLAST_RADIUS {
+                    if dx * dx + dy * dy > BLAST_RADIUS * BLAST_RADIUS {
+                        continue;
+                    }
+                    let pos = center + ICoord::new(dx, dy);
+                    let block = match self.stable_blocks.remove(&pos) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    if block.kind == BlockKind::Anchor {
+                        self.emit(PlayEvent::AnchorLost);
+                    } else if block.kind == BlockKind::Explosive {
+                        blasts.push(pos);
+                    }
+                    mass_broken += block.mass();
+                    blocks_broken += 1;
+                }
+            }
+        }
+        if let (Some(versus), true) = (&mut self.versus, blocks_broken > 0) {
+            versus.lost(mass_broken, blocks_broken);
         }
     }
 
     /// Check if this block can remain stable here: either it links up or rests on a block.
-    fn is_stable(stable_blocks: &HashMap<ICoord, Block>, pos: ICoord, block: &Block) -> bool {
-        block.kind == BlockKind::Anchor || Self::is_stable_anchorless(stable_blocks, pos, block)
+    fn is_stable(stable_blocks: &BlockMap, pos: ICoord, block: &Block, gravity: Gravity) -> bool {
+        block.kind == BlockKind::Anchor
+            || Self::is_stable_anchorless(stable_blocks, pos, block, gravity)
     }
 
     fn is_stable_anchorless(
-        stable_blocks: &HashMap<ICoord, Block>,
+        stable_blocks: &BlockMap,
         pos: ICoord,
         block: &Block,
+        gravity: Gravity,
     ) -> bool {
-        stable_blocks.get(&(pos + ICoord::new(0, 1))).is_some()
-            || Direction4::DIRECTIONS.iter().any(|&dir| {
-                if let Some(conn) = &block.connectors[dir as usize] {
-                    // It sticks if links to there
-                    Self::would_link(stable_blocks, pos, conn, dir)
-                } else {
-                    false
-                }
-            })
+        stable_blocks
+            .get(&(pos + gravity.down().deltas()))
+            .is_some()
+            // It sticks if links to there
+            || Direction4::DIRECTIONS
+                .iter()
+                .any(|&dir| Self::would_link(stable_blocks, pos, block, dir))
     }
 
     fn can_anchor_be_placed(
-        stable_blocks: &HashMap<ICoord, Block>,
+        stable_blocks: &BlockMap,
         pos: ICoord,
         block: &Block,
+        gravity: Gravity,
     ) -> bool {
-        stable_blocks.contains_key(&(pos + ICoord::new(0, -1)))
-            || Self::is_stable_anchorless(stable_blocks, pos, block)
+        stable_blocks.contains_key(&(pos + gravity.up().deltas()))
+            || Self::is_stable_anchorless(stable_blocks, pos, block, gravity)
+    }
+
+    /// Where the cavern ceiling is in this column, if there is one.
+    ///
+    /// It's bumpy, and the bumps are different every run.
+    fn ceiling_at(&self, col: isize) -> Option<isize> {
+        self.config.ceiling.map(|height| {
+            let mut rng = SmallRng::seed_from_u64(self.rng.seed() ^ col as u64);
+            height - rng.gen_range(0..4)
+        })
+    }
+
+    /// The empty cells in the line across the chasm at this depth
+    fn line_gaps(&self, depth: isize) -> Vec<ICoord> {
+        site::line_cells(self.config.scroll_axis, &self.profile, depth)
+            .into_iter()
+            .filter(|pos| !self.stable_blocks.contains_key(pos))
+            .collect()
+    }
+
+    /// Whether a row across the chasm is all filled in, so a tower can stand on it
+    fn is_bridged(&self) -> bool {
+        self.report.full_rows.iter().any(|&depth| depth >= 0)
+    }
+
+    /// How far up the camera can go. Tower runs get to look at the sky.
+    fn min_scroll(&self) -> f32 {
+        if self.config.tower {
+            -(self.report.tower_height + BOTTOM_VIEW_SIZE) as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether anything's been built all the way up to the cavern ceiling
+    fn reached_ceiling(&self) -> bool {
+        self.config.ceiling.is_some()
+            && self.stable_blocks.keys().any(|pos| {
+                self.ceiling_at(pos.x)
+                    .map_or(false, |ceiling| pos.y + 1 >= ceiling)
+            })
+    }
+
+    /// Draw a block in the world, upside down if the world is.
+    fn draw_block(
+        &self,
+        block: &Block,
+        cx: f32,
+        cy: f32,
+        color: macroquad::color::Color,
+        rotation: f32,
+        globals: &Globals,
+    ) {
+        let color = seasonal::tint(color, globals.theme().block_tint);
+        if self.config.gravity == Gravity::Up {
+            drawutils::draw_mirrored(cy, || {
+                block.draw_absolute_rotated(cx, cy, color, rotation, globals)
+            });
+        } else {
+            block.draw_absolute_rotated(cx, cy, color, rotation, globals);
+        }
+    }
+
+    /// Draw the next few blocks the conveyor will get, small, over the top of the belt.
+    /// The soonest one is on the left.
+    fn draw_upcoming(&self, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        // don't show blocks that won't ever come
+        let count = self.blocks_left.unwrap_or(PREVIEW_COUNT).min(PREVIEW_COUNT);
+        for (idx, block) in self.spawner.peek(count).enumerate() {
+            let cx = WIDTH - 70.0 + 20.0 + idx as f32 * PREVIEW_SPACING * PREVIEW_SCALE;
+            let cy = 22.0;
+            drawutils::draw_scaled(cx, cy, PREVIEW_SCALE, || {
+                self.draw_block(block, cx, cy, WHITE, 0.0, globals)
+            });
+        }
+    }
+
+    /// Run a frame with whatever the player is doing right now.
+    ///
+    /// This skips the hotkeys for pausing and such, so when the run is sharing the screen,
+    /// whatever it's sharing with gets those instead.
+    pub fn play_frame(&mut self, globals: &mut Globals) -> Transition {
+        self.summon_ghost(globals);
+        let mut input = self.input_source.capture(self.config.coop, self.screen);
+        if self.just_started && globals.settings.zoomed && !self.config.coop {
+            // it goes in as a press of Z, so replays start zoomed in too
+            input.zoom = true;
+        }
+        let transition = self.run_ticks(input, globals);
+        self.update_background_cache(globals);
+        self.update_particles(globals);
+        self.shake.update(&self.frame_events);
+        transition
+    }
+
+    /// Draw the run wherever on the canvas it goes.
+    pub fn draw_screen(&self, globals: &Globals) {
+        drawutils::draw_fitted(self.screen, || self.draw_run(globals));
+    }
+
+    /// Draw the run as if it had the whole canvas.
+    fn draw_run(&self, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let view = self.drawn_viewport(globals);
+        // not clear_background, in case it's only got part of the canvas
+        draw_rectangle(0.0, 0.0, WIDTH, HEIGHT, BLUE);
+        view.draw_in(|| self.draw_world(&view, globals));
+        view.draw_in(|| self.draw_ghost(&view, globals));
+        if self.overlay == Overlay::Stability {
+            view.draw_in(|| self.draw_stability(&view));
+        }
+        self.particles.draw();
+
+        let (mx, my) = self.last_input.mouse;
+
+        self.draw_link_hints(&view);
+
+        // Draw the conveyor
+        let conveyor_x = WIDTH - 70.0;
+        draw_texture(globals.assets.textures.conveyor, conveyor_x, 0.0, WHITE);
+        // Slats on the belt, so you can see it move
+        let slat_color = Color::new(1.0, 1.0, 1.0, 0.06);
+        let mut slat_y = BELT_TOP + self.belt_scroll.rem_euclid(BELT_SLAT_SPACING);
+        while slat_y < BELT_BOTTOM {
+            draw_line(
+                conveyor_x + 16.0,
+                slat_y.floor() + 0.5,
+                conveyor_x + 48.0,
+                slat_y.floor() + 0.5,
+                1.0,
+                slat_color,
+            );
+            slat_y += BELT_SLAT_SPACING;
+        }
+        for (idx, block) in self.conveyor_blocks.iter().enumerate() {
+            let second_held = self.second.as_ref().and_then(|second| second.held.as_ref());
+            if second_held.map_or(false, |held| held.idx == idx) {
+                // that gets drawn at their cursor
+                continue;
+            }
+            // held blocks are on the field, so they're as big as it is
+            let (cx, cy, color, rotation, scale) = match &self.held {
+                Some(held) if held.idx == idx && self.cursor.active => {
+                    let (cx, cy) = view.block_to_screen(self.cursor.pos);
+                    let alpha = if self.can_place(block, self.cursor.pos) {
+                        0.8
+                    } else {
+                        0.4
+                    };
+                    (
+                        cx,
+                        cy,
+                        Color::new(1.0, 1.0, 1.0, alpha),
+                        held.rotation,
+                        view.zoom,
+                    )
+                }
+                Some(held) if held.idx == idx => {
+                    if let Some(blockpos) = self.placement_target(block, mx, my) {
+                        // we're at a good pos
+                        let (cx, cy) = view.block_to_screen(blockpos);
+                        (
+                            cx,
+                            cy,
+                            Color::new(1.0, 1.0, 1.0, 0.8),
+                            held.rotation,
+                            view.zoom,
+                        )
+                    } else {
+                        (
+                            mx,
+                            my,
+                            Color::new(1.0, 1.0, 1.0, 0.7),
+                            held.rotation,
+                            view.zoom,
+                        )
+                    }
+                }
+                _ => {
+                    let offset = self.conveyor_offsets.get(idx).copied().unwrap_or(0.0);
+                    let cx = WIDTH - 70.0 + 24.0 + BLOCK_SIZE / 2.0;
+                    let cy = CONVEYOR_Y_BOTTOM - idx as f32 * CONVEYOR_SLOT_HEIGHT
+                        + offset
+                        + BLOCK_SIZE / 2.0;
+                    // fade in as it comes up out of the bottom of the belt
+                    let alpha = 1.0 - (offset / CONVEYOR_SLOT_HEIGHT).clamp(0.0, 1.0);
+                    let color = if idx == 0 {
+                        Color::new(1.0, 1.0, 1.0, alpha)
+                    } else {
+                        WHITE
+                    };
+                    (cx, cy, color, 0.0, 1.0)
+                }
+            };
+
+            drawutils::draw_scaled(cx, cy, scale, || {
+                self.draw_block(block, cx, cy, color, rotation, globals)
+            });
+        }
+        if self.cooldown_left > 0 {
+            // Shade the belt, draining from the top as the cooldown runs out
+            let frac = self.cooldown_left as f32 / self.config.placement_cooldown as f32;
+            let mut shade = hexcolor(ui::BACKGROUND);
+            shade.a = 0.5;
+            let belt_top = 40.0;
+            let belt_height = CONVEYOR_Y_BOTTOM + BLOCK_SIZE - belt_top;
+            draw_rectangle(
+                WIDTH - 64.0,
+                belt_top + belt_height * (1.0 - frac),
+                32.0,
+                belt_height * frac,
+                shade,
+            );
+        }
+        self.draw_upcoming(globals);
+        // Draw the blocks left
+        if let Some(blocks_left) = self.blocks_left {
+            drawutils::draw_number(blocks_left as i32, conveyor_x + 25.0, 6.0, globals);
+        }
+
+        if self.held.is_none() {
+            if self.can_buy_blocks() {
+                anchor_button(self.anchor_cost()).draw(false, globals);
+            }
+        } else if self.points >= self.anchor_cost() {
+            drawutils::draw_text_px(
+                "RMB: ANCHOR",
+                conveyor_x + 14.0,
+                207.0,
+                hexcolor(ui::FOREGROUND),
+                globals,
+            );
+        }
+
+        if self.can_finish() {
+            draw_texture(
+                globals.assets.textures.finish_popup,
+                conveyor_x + 16.0,
+                224.0,
+                WHITE,
+            );
+        }
+
+        if self.held.is_none() {
+            if let Some(idx) = self.conveyor_slot_at(mx, my) {
+                if !self.is_slot_held(idx) {
+                    // dimmed while it can't be picked up yet
+                    let color = if self.cooldown_left == 0 {
+                        ui::FOREGROUND
+                    } else {
+                        ui::DIM
+                    };
+                    let slot_y = CONVEYOR_Y_BOTTOM - idx as f32 * CONVEYOR_SLOT_HEIGHT;
+                    draw_rectangle_lines(
+                        WIDTH - 70.0 + 24.0 - 2.0,
+                        slot_y - 2.0,
+                        BLOCK_SIZE + 4.0,
+                        BLOCK_SIZE + 4.0,
+                        1.0,
+                        hexcolor(color),
+                    );
+                }
+                self.draw_block_card(&self.conveyor_blocks[idx], idx, globals);
+            }
+        }
+
+        self.draw_hold(globals);
+        self.draw_undo_button(globals);
+        self.draw_cursors(&view, globals);
+        self.draw_keyboard_cursor(&view, globals);
+        self.draw_macro_playback(&view);
+        self.draw_turn_banner(globals);
+        self.draw_row_forecast(&view, globals);
+        self.draw_objective(globals);
+        if self.overlay == Overlay::Columns {
+            self.draw_column_stats(&view, globals);
+        }
+        self.draw_stats(globals);
+        self.draw_combo(&view, globals);
+        self.toasts.draw(globals);
+        if self.show_memory {
+            self.draw_memory_report(globals);
+        }
+
+        if self.paused {
+            let mut shade = hexcolor(ui::BACKGROUND);
+            shade.a = 0.4;
+            draw_rectangle(0.0, 0.0, WIDTH, HEIGHT, shade);
+            if self.config.rewindable && !self.timeline.is_empty() {
+                self.timeline.draw(globals);
+            } else {
+                ui::draw_centered_text("PAUSED", HEIGHT / 2.0, hexcolor(ui::FOREGROUND), globals);
+            }
+            restart_button().draw(false, globals);
+            photo_button().draw(false, globals);
+        }
+        if self.restart_held > 0 {
+            let frac = self.restart_held as f32 / RESTART_HOLD_FRAMES as f32;
+            let label = "RESTARTING...";
+            let width = drawutils::text_width_px(label) + 8.0;
+            let x = ((WIDTH - 70.0 - width) / 2.0).round();
+            ui::draw_panel(Rect::new(x, 4.0, width, 14.0));
+            drawutils::draw_text_px(label, x + 4.0, 7.0, hexcolor(ui::FOREGROUND), globals);
+            draw_rectangle(
+                x + 1.0,
+                16.0,
+                (width - 2.0) * frac,
+                1.0,
+                hexcolor(ui::ALERT),
+            );
+        }
+    }
+
+    /// How the world is laid out on the screen right now
+    fn viewport(&self) -> Viewport {
+        Viewport {
+            zoom: if self.zoomed { ZOOMED_IN } else { 1.0 },
+            ..Viewport::new(
+                self.config.scroll_axis,
+                self.config.gravity,
+                self.scroll_depth,
+            )
+        }
+    }
+
+    /// The camera to draw with, partway between where it was last step and where it is
+    fn drawn_viewport(&self, globals: &Globals) -> Viewport {
+        let alpha = self.sim_alpha(globals);
+        Viewport {
+            scroll_depth: self.prev_scroll_depth
+                + (self.scroll_depth - self.prev_scroll_depth) * alpha,
+            ..self.viewport()
+        }
+    }
+}
+
+impl Gamemode for ModePlaying {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        if macroquad::prelude::is_key_pressed(macroquad::prelude::KeyCode::P) {
+            self.set_paused(!self.paused);
+        }
+        if macroquad::prelude::is_key_pressed(macroquad::prelude::KeyCode::F3) {
+            self.show_memory = !self.show_memory;
+        }
+        if macroquad::prelude::is_key_pressed(macroquad::prelude::KeyCode::Tab) {
+            self.overlay = self.overlay.next();
+        }
+        if macroquad::prelude::is_key_pressed(macroquad::prelude::KeyCode::F12)
+            || (self.paused && photo_button().clicked())
+        {
+            self.export_tower_photo(globals);
+        }
+        if macroquad::prelude::is_key_down(macroquad::prelude::KeyCode::R) {
+            self.restart_held += 1;
+        } else {
+            self.restart_held = 0;
+        }
+        if self.restart_held >= RESTART_HOLD_FRAMES || (self.paused && restart_button().clicked()) {
+            return self.quick_restart();
+        }
+        if self.paused {
+            self.idle();
+            self.toasts.update();
+            self.update_paused();
+            return Transition::None;
+        }
+
+        if globals.frame_monitor.should_suggest()
+            && globals.settings.graphics == GraphicsQuality::High
+        {
+            self.toasts
+                .push("RUNNING SLOW? TRY LOW GRAPHICS IN OPTIONS");
+        }
+        self.play_frame(globals)
     }
 
-    fn block_to_pixel(&self, pos: ICoord) -> (f32, f32) {
-        let cx = pos.x as f32 * BLOCK_SIZE + WIDTH / 2.0;
-        let cy = (pos.y as f32 - self.scroll_depth) * BLOCK_SIZE + HEIGHT / 2.0;
-        (cx, cy)
+    fn draw(&self, globals: &Globals) {
+        self.draw_screen(globals);
     }
 
-    fn pixel_to_block(&self, x: f32, y: f32) -> ICoord {
-        let block_x = (x / BLOCK_SIZE).round() as isize - SCREEN_WIDTH / 2;
-        let block_y = (y / BLOCK_SIZE - 0.5).round() as isize - SCREEN_HEIGHT / 2
-            + self.scroll_depth.round() as isize;
-        ICoord::new(block_x, block_y)
+    /// The sim speed keys double as the volume hotkeys
+    fn claims_key(&self, key: macroquad::prelude::KeyCode) -> bool {
+        use macroquad::prelude::KeyCode;
+
+        self.config.sim_speed_control && matches!(key, KeyCode::Minus | KeyCode::Equal)
     }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Playing)
+    }
+
+    /// What the mouse cursor should look like over this spot.
+    fn cursor_icon(&self, mx: f32, my: f32) -> CursorIcon {
+        let (mx, my) = drawutils::unfit(self.screen, mx, my);
+        if self.paused {
+            return CursorIcon::Pointer;
+        }
+        let over_chasm = mx < WIDTH - 70.0 && !hold::covers(mx, my);
+        match &self.held {
+            Some(info) if over_chasm => {
+                let block = &self.conveyor_blocks[info.idx];
+                if self.placement_target(block, mx, my).is_some() {
+                    CursorIcon::Crosshair
+                } else {
+                    CursorIcon::Forbidden
+                }
+            }
+            Some(_) => CursorIcon::Grab,
+            None => {
+                let grabbable = self
+                    .conveyor_slot_at(mx, my)
+                    .map_or(false, |idx| !self.is_slot_held(idx));
+                let removable = over_chasm
+                    && self
+                        .stable_blocks
+                        .get(&self.viewport().pixel_to_block(mx, my))
+                        .map_or(false, Block::is_removable);
+                if grabbable {
+                    CursorIcon::Grab
+                } else if removable {
+                    CursorIcon::Hammer
+                } else {
+                    CursorIcon::Pointer
+                }
+            }
+        }
+    }
+
+    fn screen_shake(&self) -> (f32, f32) {
+        if self.paused {
+            (0.0, 0.0)
+        } else {
+            self.shake.offset()
+        }
+    }
+}
+
+/// How light to draw the rock at this depth, getting darker as it goes down.
+fn rock_color(depth: isize, rng: &mut SmallRng) -> macroquad::color::Color {
+    let mut deepness_color = |depth_mod: f32| {
+        let jitter = rng.gen_range(-0.2..0.2);
+        let darkness = depth_mod / (-depth as f32 - depth_mod) + 1.0;
+        let lightness = 1.0 - darkness + jitter * 0.2;
+        (lightness * 100.0).round() / 100.0
+    };
+
+    let lightness = deepness_color(100.0).max(0.5);
+    let orangey = deepness_color(500.0) / 10.0;
+    macroquad::color::Color::new(
+        lightness + orangey,
+        lightness + orangey / 2.0,
+        lightness,
+        1.0,
+    )
+}
+
+/// Draw a line this long across the middle of the edge between the blocks centered at these two points.
+fn draw_across_edge(
+    (x1, y1): (f32, f32),
+    (x2, y2): (f32, f32),
+    length: f32,
+    thickness: f32,
+    color: macroquad::color::Color,
+) {
+    let (mx, my) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0);
+    let (along_x, along_y) = ((y2 - y1) / 2.0, (x1 - x2) / 2.0);
+    let reach = length / BLOCK_SIZE;
+    macroquad::shapes::draw_line(
+        mx - along_x * reach,
+        my - along_y * reach,
+        mx + along_x * reach,
+        my + along_y * reach,
+        thickness,
+        color,
+    );
+}
+
+/// The part of the belt that grabs the block in this slot.
+/// Each one takes up half the gap on either side, so clicks between blocks still grab one.
+fn conveyor_slot_rect(idx: usize) -> macroquad::math::Rect {
+    let slot_y = CONVEYOR_Y_BOTTOM - idx as f32 * CONVEYOR_SLOT_HEIGHT;
+    let gap = CONVEYOR_SLOT_HEIGHT - BLOCK_SIZE;
+    macroquad::math::Rect::new(WIDTH - 64.0, slot_y - gap / 2.0, 32.0, CONVEYOR_SLOT_HEIGHT)
+}
+
+/// In the pause screen. Shift-clicking keeps the seed, like holding shift with R.
+fn restart_button() -> ui::Button {
+    ui::Button::new(93.0, 140.0, 64.0, 16.0, "RESTART")
+}
+
+/// Saves a picture of the whole structure, same as F12
+fn photo_button() -> ui::Button {
+    ui::Button::new(93.0, 160.0, 64.0, 16.0, "PHOTO")
+}
+
+/// Buy an anchor with points; it shows how much one costs.
+fn anchor_button(cost: u32) -> ui::Button {
+    ui::Button::new(
+        WIDTH - 70.0 + 12.0,
+        204.0,
+        48.0,
+        11.0,
+        format!("ANCHOR {}", cost),
+    )
 }
 
 #[derive(Clone)]
 struct HoldInfo {
     idx: usize,
+    /// How far the connectors are from where they should be, for the rotation animation
+    rotation: f32,
 }
 
-#[derive(Clone, Default)]
-struct AudioSignals {
-    pick_up: bool,
-    rotate: bool,
-    fall: bool,
-    put_down: bool,
-    damage: bool,
+impl HoldInfo {
+    /// Turn the held block a quarter turn.
+    ///
+    /// The connectors are rotated right away; `rotation` is how far they
+    /// still have to visually swing to get there.
+    fn rotate(&mut self, block: &mut Block, counterclockwise: bool) {
+        if counterclockwise {
+            block.connectors.rotate_left(1);
+            self.rotation += TAU / 4.0;
+        } else {
+            block.connectors.rotate_right(1);
+            self.rotation -= TAU / 4.0;
+        }
+    }
+
+    /// Swing the connectors a bit closer to where they actually are.
+    fn swing(&mut self) {
+        let swing = (self.rotation.abs() * ROTATION_EASING)
+            .max(ROTATION_MIN_SPEED)
+            .min(self.rotation.abs());
+        self.rotation -= swing * self.rotation.signum();
+    }
 }
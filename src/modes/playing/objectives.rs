@@ -0,0 +1,87 @@
+//! Goals that end a run in a win once they're met.
+//!
+//! Runs don't have one unless their config says so.
+
+use super::ModePlaying;
+use crate::{
+    drawutils::{self, hexcolor},
+    ui, Globals, HEIGHT, WIDTH,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// What has to be done to win.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// Get the center of mass down this deep
+    ReachDepth(u32),
+    /// Keep the structure up for this many minutes
+    Survive(u32),
+    /// Put down this many solid blocks
+    PlaceSolid(u32),
+}
+
+impl Objective {
+    pub fn describe(self) -> String {
+        match self {
+            Objective::ReachDepth(depth) => format!("REACH DEPTH {}", depth),
+            Objective::Survive(1) => "SURVIVE 1 MINUTE".to_owned(),
+            Objective::Survive(minutes) => format!("SURVIVE {} MINUTES", minutes),
+            Objective::PlaceSolid(1) => "PLACE 1 SOLID BLOCK".to_owned(),
+            Objective::PlaceSolid(count) => format!("PLACE {} SOLID BLOCKS", count),
+        }
+    }
+
+    /// How far along it is and how far it has to get, in whatever units it counts in
+    fn progress(self, mode: &ModePlaying) -> (u32, u32) {
+        match self {
+            Objective::ReachDepth(depth) => (mode.report.center_of_mass.max(0.0) as u32, depth),
+            // the sim runs at 60 steps a second
+            Objective::Survive(minutes) => ((mode.frames_elapsed / 60) as u32, minutes * 60),
+            Objective::PlaceSolid(count) => (mode.solids_placed, count),
+        }
+    }
+}
+
+impl ModePlaying {
+    /// Whether the run has an objective and it's been met
+    pub(super) fn objective_met(&self) -> bool {
+        self.config.objective.map_or(false, |objective| {
+            let (done, needed) = objective.progress(self);
+            done >= needed
+        })
+    }
+
+    /// Show the objective and a bar for how close it is.
+    pub(super) fn draw_objective(&self, globals: &Globals) {
+        use macroquad::prelude::*;
+
+        let objective = match self.config.objective {
+            Some(it) => it,
+            None => return,
+        };
+        let (done, needed) = objective.progress(self);
+        let label = format!("GOAL: {}", objective.describe());
+        let width = (drawutils::text_width_px(&label) + 8.0).max(80.0);
+        // centered over the chasm, which is the screen minus the conveyor
+        let x = ((WIDTH - 70.0 - width) / 2.0).round();
+        let y = HEIGHT - 22.0;
+
+        let mut bg = hexcolor(ui::BACKGROUND);
+        bg.a = 0.6;
+        draw_rectangle(x, y, width, 18.0, bg);
+        drawutils::draw_text_px(&label, x + 4.0, y + 3.0, hexcolor(ui::FOREGROUND), globals);
+
+        let frac = (done as f32 / needed.max(1) as f32).min(1.0);
+        let bar_width = width - 8.0;
+        draw_rectangle_lines(x + 4.0, y + 11.0, bar_width, 4.0, 1.0, hexcolor(ui::DIM));
+        draw_rectangle(
+            x + 4.0,
+            y + 11.0,
+            (bar_width * frac).round(),
+            4.0,
+            hexcolor(ui::FOREGROUND),
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Pictures of the whole structure, instead of just the part on the screen.
+//!
+//! The results screen's picture centers the view on the structure and zooms out
+//! until all of it fits. Tower photos are drawn at full size instead, a screen at a time
+//! down a picture as long as the structure is deep.
+//! Results pictures can be shown later or saved as a PNG; tower photos only get saved.
+
+use super::{geometry::Viewport, ModePlaying, ScrollAxis, BLOCK_SIZE};
+use crate::{
+    drawutils::{self, OffscreenTarget},
+    save, Globals, HEIGHT, WIDTH,
+};
+
+use cogs_gamedev::int_coords::ICoord;
+use itertools::{Itertools, MinMaxResult};
+use macroquad::prelude::*;
+
+/// Photos are saved under this prefix
+const PHOTO_DIR: &str = "photos/";
+/// Longest a tower photo gets, in pixels. Deeper structures get shrunk to fit.
+/// Graphics cards won't make textures much bigger than this.
+const MAX_PHOTO_LENGTH: f32 = 4096.0;
+
+/// A picture of the structure.
+#[derive(Clone)]
+pub struct Photo {
+    target: OffscreenTarget,
+}
+
+impl Photo {
+    pub fn width(&self) -> f32 {
+        self.target.texture().width()
+    }
+
+    pub fn height(&self) -> f32 {
+        self.target.texture().height()
+    }
+
+    /// Draw it with its corner here, shrunk or grown by `scale`.
+    pub fn draw(&self, x: f32, y: f32, scale: f32) {
+        draw_texture_ex(
+            self.target.texture(),
+            x,
+            y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(self.width() * scale, self.height() * scale)),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Save it as a PNG, and say what it got saved as.
+    pub fn export(&self) -> String {
+        export_image(self.target.texture().get_texture_data())
+    }
+}
+
+/// Save a picture as a PNG in the photos folder, and say what it got saved as.
+fn export_image(image: Image) -> String {
+    let timestamp = macroquad::miniquad::date::now() as u64;
+    let name = format!("{}{}.png", PHOTO_DIR, timestamp);
+    save::store_png(&name, image.width as u32, image.height as u32, image.bytes);
+    name
+}
+
+impl ModePlaying {
+    /// Draw the whole structure into a `width`x`height` picture, zoomed out if it has to be.
+    ///
+    /// Structures are tall and thin, or long and low in tunnels, so the picture
+    /// doesn't have to be the shape of the screen.
+    pub(super) fn take_photo(&self, width: f32, height: f32, globals: &Globals) -> Photo {
+        let view = self.structure_view(width, height);
+        let target = OffscreenTarget::new(width as u32, height as u32);
+        let area = Rect::new(
+            (WIDTH - width) / 2.0,
+            (HEIGHT - height) / 2.0,
+            width,
+            height,
+        );
+        drawutils::draw_offscreen_area(target.get(), area, || {
+            view.draw_in(|| self.draw_world(&view, globals))
+        });
+        Photo { target }
+    }
+
+    /// The picture on the results screen, the long way along the chasm
+    pub(super) fn results_photo(&self, globals: &Globals) -> Photo {
+        let (width, height) = match self.config.scroll_axis {
+            ScrollAxis::Vertical => (HEIGHT / 2.0, HEIGHT),
+            ScrollAxis::Horizontal => (WIDTH, WIDTH / 4.0),
+        };
+        self.take_photo(width, height, globals)
+    }
+
+    /// Everything from the anchor row to the bottom of the structure, at full size.
+    ///
+    /// This never goes on the screen, so it comes back as pixels and the
+    /// (possibly huge) render target is freed straight away.
+    pub(super) fn tower_photo(&self, globals: &Globals) -> Image {
+        let (shallowest, deepest) = self.depth_range().unwrap_or((0, 0));
+        // from the edge of the cell past one end to the edge of the cell past the other
+        let first = shallowest.min(0) as f32 - 1.5;
+        let last = deepest.max(0) as f32 + 1.5;
+        let length = (last - first) * BLOCK_SIZE;
+        let zoom = (MAX_PHOTO_LENGTH / length).min(1.0);
+        let (screen_length, width, height) = match self.config.scroll_axis {
+            ScrollAxis::Vertical => (HEIGHT, WIDTH * zoom, length * zoom),
+            ScrollAxis::Horizontal => (WIDTH, length * zoom, HEIGHT * zoom),
+        };
+
+        let target = OffscreenTarget::new(width.ceil() as u32, height.ceil() as u32);
+        // upside-down worlds have the last row at the top
+        let sign = self.config.gravity.sign() as f32;
+        let start = if sign > 0.0 { first } else { last };
+        let screens = (length / screen_length).ceil() as usize;
+        let area = Rect::new(0.0, 0.0, width, height);
+        drawutils::draw_offscreen_area(target.get(), area, || {
+            for idx in 0..screens {
+                // how far the middle of this screen is from the start, at 1x
+                let along = (idx as f32 + 0.5) * screen_length;
+                let offset = match self.config.scroll_axis {
+                    ScrollAxis::Vertical => vec2(width / 2.0, along * zoom),
+                    ScrollAxis::Horizontal => vec2(along * zoom, height / 2.0),
+                };
+                let view = Viewport {
+                    scroll_depth: start + sign * along / BLOCK_SIZE,
+                    zoom,
+                    offset,
+                    ..self.viewport()
+                };
+                view.draw_in(|| self.draw_world(&view, globals));
+            }
+        });
+        target.texture().get_texture_data()
+    }
+
+    /// Save a tower photo, and say so.
+    pub(super) fn export_tower_photo(&mut self, globals: &Globals) {
+        export_image(self.tower_photo(globals));
+        self.toasts.push("PHOTO SAVED");
+    }
+
+    /// The shallowest and deepest rows with blocks in them, along the scroll axis
+    fn depth_range(&self) -> Option<(isize, isize)> {
+        let depth = |pos: &ICoord| match self.config.scroll_axis {
+            ScrollAxis::Vertical => pos.y,
+            ScrollAxis::Horizontal => pos.x,
+        };
+        match self.stable_blocks.keys().map(depth).minmax() {
+            MinMaxResult::NoElements => None,
+            MinMaxResult::OneElement(it) => Some((it, it)),
+            MinMaxResult::MinMax(min, max) => Some((min, max)),
+        }
+    }
+
+    /// A view with the middle of the structure in the middle of a `width`x`height` box,
+    /// zoomed out until all of it fits in the box
+    fn structure_view(&self, width: f32, height: f32) -> Viewport {
+        let view = self.viewport();
+        let (shallowest, deepest) = match self.depth_range() {
+            Some(it) => it,
+            None => return view,
+        };
+        let view = Viewport {
+            scroll_depth: (shallowest + deepest) as f32 / 2.0,
+            ..view
+        };
+
+        // how far out from the middle it goes, with a block to spare
+        let (reach_x, reach_y) = self
+            .stable_blocks
+            .keys()
+            .map(|&pos| {
+                let (x, y) = view.block_to_pixel(pos);
+                (
+                    (x - WIDTH / 2.0).abs() + BLOCK_SIZE,
+                    (y - HEIGHT / 2.0).abs() + BLOCK_SIZE,
+                )
+            })
+            .fold((0.0f32, 0.0f32), |(rx, ry), (x, y)| (rx.max(x), ry.max(y)));
+        Viewport {
+            zoom: (width / 2.0 / reach_x).min(height / 2.0 / reach_y).min(1.0),
+            ..view
+        }
+    }
+}
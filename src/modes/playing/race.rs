@@ -0,0 +1,51 @@
+//! One side of a split-screen race: each player builds down their own chasm,
+//! and whoever gets deep enough first wins. Bringing down a big chunk loses on the spot.
+//!
+//! Both sides together are [`ModeVersus`](crate::modes::ModeVersus).
+
+use super::{versus::COLLAPSE_SIZE, BlockKind, ModePlaying};
+
+/// How deep the center of mass has to get to win a race
+pub const RACE_DEPTH: u32 = 30;
+
+/// How one side of a race ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaceFinish {
+    /// Got deep enough
+    Reached,
+    /// Let a chunk of this many blocks fall
+    Collapsed(usize),
+    /// Every anchor wore out
+    LostAnchors,
+}
+
+impl ModePlaying {
+    /// Note a chunk coming loose, in case it's big enough to lose the race over.
+    pub(super) fn race_chunk_fell(&mut self, size: usize) {
+        if self.config.race && size >= COLLAPSE_SIZE && self.race_collapse.is_none() {
+            self.race_collapse = Some(size);
+        }
+    }
+
+    /// How deep the structure's center of mass is, which is what races go by
+    pub fn depth(&self) -> f32 {
+        self.report.center_of_mass.max(0.0)
+    }
+
+    /// How this side of the race ended, if it has
+    pub fn race_finish(&self) -> Option<RaceFinish> {
+        let anchored = self
+            .stable_blocks
+            .values()
+            .any(|block| block.kind == BlockKind::Anchor);
+        if let Some(size) = self.race_collapse {
+            Some(RaceFinish::Collapsed(size))
+        } else if !anchored {
+            Some(RaceFinish::LostAnchors)
+        } else if self.objective_met() {
+            Some(RaceFinish::Reached)
+        } else {
+            None
+        }
+    }
+}
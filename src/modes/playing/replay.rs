@@ -0,0 +1,210 @@
+use super::{GameType, InputFrame, ModePlaying, RunConfig, SavePolicy};
+use crate::{
+    save::{self, PackedError},
+    score::RunResult,
+    settings::{CameraMode, Settings},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever a change to the simulation would make old replays play out differently,
+/// or what's saved in a replay changes
+pub const REPLAY_VERSION: u32 = 13;
+/// Replays are saved under this prefix
+pub const REPLAY_DIR: &str = "replays/";
+const REPLAY_EXTENSION: &str = ".esar";
+/// The best run of each game type is kept under this prefix, to race against
+const GHOST_DIR: &str = "ghosts/";
+
+/// A recording of a run: enough to play it back exactly.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub inputs: InputLog,
+    /// Important things that happened, to mark on the timeline.
+    /// They'd happen again on playback anyways; they're saved so they can be shown up front.
+    #[serde(default)]
+    pub events: Vec<RunEvent>,
+}
+
+/// Something decisive that happened during a run.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct RunEvent {
+    /// Index of the frame it happened on
+    pub frame: u64,
+    pub kind: RunEventKind,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum RunEventKind {
+    /// This many blocks came loose and fell
+    Collapse(usize),
+    /// An anchor wore out
+    AnchorLost,
+    /// A row at this depth was filled all the way across
+    RowCompleted(isize),
+}
+
+impl RunEventKind {
+    pub fn describe(self) -> String {
+        match self {
+            RunEventKind::Collapse(1) => "1 BLOCK FELL".to_owned(),
+            RunEventKind::Collapse(count) => format!("{} BLOCKS FELL", count),
+            RunEventKind::AnchorLost => "ANCHOR LOST".to_owned(),
+            RunEventKind::RowCompleted(depth) => format!("ROW FILLED AT DEPTH {}", depth),
+        }
+    }
+}
+
+/// Information about a replay, to show before playing it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub version: u32,
+    /// Seed for the run's `GameRng`
+    pub seed: u64,
+    pub config: RunConfig,
+    /// The camera setting affects where the mouse points, so it has to be kept
+    pub camera: CameraMode,
+    /// So does the scroll speed
+    #[serde(default = "normal_scroll_speed")]
+    pub scroll_speed: u8,
+    /// Whoever played it
+    pub player: String,
+    pub result: RunResult,
+}
+
+/// Replays from before the scroll speed could be changed were at this speed
+fn normal_scroll_speed() -> u8 {
+    Settings::NORMAL_SCROLL_SPEED
+}
+
+impl Replay {
+    /// Load the replay saved under this name.
+    pub fn load(name: &str) -> Result<Self, String> {
+        let replay: Replay = save::load_packed(name, REPLAY_VERSION).map_err(|err| match err {
+            PackedError::Outdated(Some(version)) => {
+                format!("MADE WITH VERSION {}, NEED {}", version, REPLAY_VERSION)
+            }
+            PackedError::Outdated(None) => "MADE WITH AN OLDER VERSION".to_owned(),
+            PackedError::Missing | PackedError::Corrupt(_) => "COULDN'T READ".to_owned(),
+        })?;
+        // JSON replays don't have a version on the outside
+        if replay.header.version != REPLAY_VERSION {
+            return Err(format!(
+                "MADE WITH VERSION {}, NEED {}",
+                replay.header.version, REPLAY_VERSION
+            ));
+        }
+        Ok(replay)
+    }
+
+    /// Save it with a fresh name, and return the name.
+    pub fn export(&self) -> String {
+        let timestamp = macroquad::miniquad::date::now() as u64;
+        let name = format!("{}{}{}", REPLAY_DIR, timestamp, REPLAY_EXTENSION);
+        save::store_packed(&name, REPLAY_VERSION, self.clone());
+        name
+    }
+
+    /// Keep this as the best run of its game type, replacing the last one.
+    pub fn keep_as_ghost(&self) {
+        save::store_packed(
+            &ghost_name(self.header.config.game_type),
+            REPLAY_VERSION,
+            self.clone(),
+        );
+    }
+
+    /// The best run of this game type, if one's been kept and it still plays back.
+    pub fn load_ghost(game_type: GameType) -> Option<Self> {
+        Replay::load(&ghost_name(game_type)).ok()
+    }
+
+    /// Names of all the saved replays, newest first.
+    pub fn list() -> Vec<String> {
+        let mut names = save::list(REPLAY_DIR)
+            .into_iter()
+            .filter(|name| name.ends_with(REPLAY_EXTENSION))
+            .collect::<Vec<_>>();
+        names.sort_unstable_by(|a, b| b.cmp(a));
+        names
+    }
+}
+
+/// Where the best run of this game type is kept
+fn ghost_name(game_type: GameType) -> String {
+    format!("{}{:?}{}", GHOST_DIR, game_type, REPLAY_EXTENSION)
+}
+
+impl ModePlaying {
+    /// Set up a run to play back this replay on.
+    pub fn for_replay(header: &ReplayHeader) -> Self {
+        let mut config = header.config.clone();
+        // watching a replay had better not touch the real saves
+        config.save_policy = SavePolicy::Never;
+        let mut mode = ModePlaying::with_seed(config, header.seed);
+        mode.camera_override = Some(header.camera);
+        mode.scroll_speed_override = Some(header.scroll_speed);
+        mode.recording = None;
+        mode
+    }
+
+    /// Sit on the current frame without running it, like when a replay is paused.
+    pub fn idle(&mut self) {
+        self.between_steps();
+        // nothing's moving, so there's nothing to draw partway
+        self.prev_scroll_depth = self.scroll_depth;
+        for chunk in self.falling_blocks.iter_mut() {
+            chunk.prev_dy = chunk.dy;
+        }
+    }
+
+    /// Let a frame go by without a step, when the display's faster than the simulation.
+    pub fn between_steps(&mut self) {
+        // otherwise the last step's sounds play over and over
+        self.frame_events.clear();
+    }
+
+    /// Note down something important happening, if this run is being recorded.
+    pub(super) fn log_event(&mut self, kind: RunEventKind) {
+        if self.recording.is_some() {
+            self.events.push(RunEvent {
+                frame: self.frames_elapsed,
+                kind,
+            });
+        }
+    }
+
+    /// How many depth milestones the run has passed
+    pub fn milestones_reached(&self) -> u32 {
+        self.milestones_reached
+    }
+}
+
+/// Inputs for every frame of a run.
+/// Runs of identical frames are stored once along with how many times they repeat.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct InputLog {
+    runs: Vec<(u32, InputFrame)>,
+}
+
+impl InputLog {
+    pub fn push(&mut self, frame: InputFrame) {
+        match self.runs.last_mut() {
+            Some((count, last)) if *last == frame => *count += 1,
+            _ => self.runs.push((1, frame)),
+        }
+    }
+
+    /// Roughly how much memory the log is taking up
+    pub fn heap_bytes(&self) -> usize {
+        self.runs.capacity() * std::mem::size_of::<(u32, InputFrame)>()
+    }
+
+    /// Every frame, in order.
+    pub fn frames(&self) -> impl Iterator<Item = InputFrame> + '_ {
+        self.runs
+            .iter()
+            .flat_map(|&(count, frame)| std::iter::repeat(frame).take(count as usize))
+    }
+}
@@ -0,0 +1,138 @@
+//! Everything worth knowing about the shape of the structure, worked out once a step.
+//!
+//! The HUD, the scoring and the objectives all read from the same [`StructureReport`]
+//! instead of each walking every block again.
+
+use super::{
+    blockmap::BlockMap, chasm::ChasmProfile, columns::ColumnStats, site, BlockKind, ModePlaying,
+    RunConfig,
+};
+
+use cogs_gamedev::int_coords::ICoord;
+
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default)]
+pub struct StructureReport {
+    /// How many blocks are in each line across the chasm, by depth.
+    /// Depths without any aren't in here.
+    pub line_counts: BTreeMap<isize, usize>,
+    /// How many cells make up the line across the chasm at each depth in `line_counts`
+    line_widths: BTreeMap<isize, usize>,
+    /// Depths of the lines that are filled all the way across, shallowest first
+    pub full_rows: Vec<isize>,
+    /// How each column of the chasm is doing, left to right.
+    /// Only the columns open at the rim count, since the rest don't go all the way down.
+    pub columns: Vec<ColumnStats>,
+    /// How many pairs of connectors are linked up
+    pub links: usize,
+    pub anchors: usize,
+    /// The longest bridge anywhere in the structure
+    pub largest_span: usize,
+    /// Deepest block, or 0 if nothing's below the rim
+    pub max_depth: isize,
+    /// How far above the rim the structure goes, in tower runs
+    pub tower_height: isize,
+    pub center_of_mass: f32,
+    /// Not counting the tower, which is scored on its own
+    pub total_mass: f32,
+    pub block_count: usize,
+}
+
+impl StructureReport {
+    pub fn new(stable_blocks: &BlockMap, config: &RunConfig, profile: &ChasmProfile) -> Self {
+        let axis = config.scroll_axis;
+        let width = profile.width(0);
+        let (left, _) = profile.span(0);
+
+        let mut report = Self {
+            columns: vec![ColumnStats::default(); width as usize],
+            block_count: stable_blocks.len(),
+            ..Self::default()
+        };
+        let mut column_filled = vec![0; width as usize];
+        let mut superposes = 0.0;
+        let mut link_ends = 0;
+        for (&pos, block) in stable_blocks.iter() {
+            let depth = axis.reach(pos);
+            report.max_depth = report.max_depth.max(depth);
+            if depth < 0 && config.tower {
+                // the tower is scored on its own, so it doesn't drag the depth up
+                report.tower_height = report.tower_height.max(-depth);
+            } else {
+                superposes += depth as f32 * block.mass();
+                report.total_mass += block.mass();
+            }
+
+            let (first, last) = site::line_span(axis, profile, depth);
+            if (first..=last).contains(&site::across(axis, pos)) {
+                *report.line_counts.entry(depth).or_default() += 1;
+                report
+                    .line_widths
+                    .insert(depth, (last - first + 1) as usize);
+            }
+
+            // the walls and anything sticking up out of the chasm aren't in a column
+            let column = pos.x - left;
+            if pos.y >= 0 && (0..width).contains(&column) {
+                let column = column as usize;
+                column_filled[column] += 1;
+                let stats = &mut report.columns[column];
+                stats.deepest = Some(stats.deepest.map_or(pos.y, |deepest| deepest.max(pos.y)));
+            }
+
+            if block.kind == BlockKind::Anchor {
+                report.anchors += 1;
+            }
+            link_ends += ModePlaying::link_count(stable_blocks, pos, block);
+            let span = ModePlaying::bridge_length(stable_blocks, pos, config.gravity);
+            report.largest_span = report.largest_span.max(span);
+        }
+
+        // both ends of a link see it
+        report.links = link_ends / 2;
+        report.center_of_mass = if report.total_mass == 0.0 {
+            0.0
+        } else {
+            superposes / report.total_mass
+        };
+        report.full_rows = report
+            .line_counts
+            .iter()
+            .filter(|&(depth, &count)| count == report.line_widths[depth])
+            .map(|(&depth, _)| depth)
+            .collect();
+        for (idx, (stats, filled)) in report.columns.iter_mut().zip(column_filled).enumerate() {
+            if let Some(deepest) = stats.deepest {
+                // where the walls come in over it there's nothing to fill
+                let x = left + idx as isize;
+                let open = (0..=deepest)
+                    .filter(|&y| profile.is_open(ICoord::new(x, y)))
+                    .count();
+                stats.gaps = open.saturating_sub(filled);
+            }
+        }
+        report
+    }
+
+    /// How many cells make up the line across the chasm at this depth
+    pub fn line_width(&self, depth: isize) -> usize {
+        self.line_widths.get(&depth).copied().unwrap_or(1)
+    }
+
+    /// How much of the line across the chasm at this depth is filled, from 0 to 1
+    pub fn fill(&self, depth: isize) -> f32 {
+        let count = self.line_counts.get(&depth).copied().unwrap_or(0);
+        count as f32 / self.line_width(depth) as f32
+    }
+
+    /// Links per block. Just a chain of blocks is about 1;
+    /// more means more ways for the weight to get to the anchors.
+    pub fn link_redundancy(&self) -> f32 {
+        if self.block_count == 0 {
+            0.0
+        } else {
+            self.links as f32 / self.block_count as f32
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Knocking the whole screen around when something big comes down.
+//!
+//! This is only for show, so it uses the cosmetic randomness.
+//! The reduced motion setting turns it off in [`run`](crate::run), where it gets applied.
+
+use super::events::PlayEvent;
+
+/// How far the screen moves at the most, in pixels
+const MAX_OFFSET: f32 = 4.0;
+/// How much of the shake wears off each frame
+const DECAY: f32 = 0.04;
+/// Fewer blocks than this falling doesn't shake anything
+const MIN_CHUNK: usize = 4;
+/// A chunk this big or bigger shakes as hard as it gets
+const BIG_CHUNK: usize = 24;
+
+#[derive(Clone, Default)]
+pub struct Shake {
+    /// From 0 to 1. The screen moves by this squared, so small knocks stay small.
+    trauma: f32,
+}
+
+impl Shake {
+    /// Wear off a frame's worth, and get knocked by anything big that happened.
+    pub fn update(&mut self, events: &[PlayEvent]) {
+        self.trauma = (self.trauma - DECAY).max(0.0);
+        for event in events {
+            let knock = match *event {
+                PlayEvent::ChunkFell { size } if size >= MIN_CHUNK => {
+                    (size as f32 / BIG_CHUNK as f32).min(1.0)
+                }
+                PlayEvent::AnchorLost => 0.4,
+                PlayEvent::Exploded { .. } => 0.5,
+                _ => continue,
+            };
+            self.trauma = (self.trauma + knock).min(1.0);
+        }
+    }
+
+    /// How far to move the screen this frame, in whole pixels
+    pub fn offset(&self) -> (f32, f32) {
+        use macroquad::rand::gen_range;
+
+        let reach = self.trauma * self.trauma * MAX_OFFSET;
+        if reach < 0.5 {
+            return (0.0, 0.0);
+        }
+        (
+            (gen_range(-1.0, 1.0) * reach).round(),
+            (gen_range(-1.0, 1.0) * reach).round(),
+        )
+    }
+}
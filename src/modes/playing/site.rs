@@ -0,0 +1,73 @@
+//! The layout of the place being built in, for each way a run can go.
+//!
+//! Runs either go down a chasm, hanging between its walls, or sideways along a tunnel,
+//! sticking out from the wall at its end. Gravity is down either way.
+//! Going between blocks and the screen is in [`geometry`](super::geometry);
+//! this is everything else that changes with the axis, so the rest of the run
+//! can ask instead of matching on it.
+
+use super::{chasm::ChasmProfile, ScrollAxis, SHAFT_HEIGHT};
+
+use cogs_gamedev::{directions::Direction4, int_coords::ICoord};
+use itertools::Itertools;
+
+/// Rows the anchors a run starts with are in, down each side of a chasm
+const CHASM_ANCHOR_ROWS: isize = 4;
+/// Rows the anchors a run starts with are in, in the end wall of a tunnel
+const TUNNEL_ANCHOR_ROWS: std::ops::Range<isize> = 2..6;
+
+/// Where the anchors a run starts with go, and which way each one's connector faces.
+/// They're embedded in the rock facing in.
+pub fn starting_anchors(axis: ScrollAxis, profile: &ChasmProfile) -> Vec<(ICoord, Direction4)> {
+    match axis {
+        ScrollAxis::Vertical => (0..CHASM_ANCHOR_ROWS)
+            .flat_map(|depth| {
+                let (left, right) = profile.span(depth);
+                vec![
+                    (ICoord::new(left - 1, depth), Direction4::East),
+                    (ICoord::new(right + 1, depth), Direction4::West),
+                ]
+            })
+            .collect_vec(),
+        // In the end wall, facing down the tunnel
+        ScrollAxis::Horizontal => TUNNEL_ANCHOR_ROWS
+            .map(|y| (ICoord::new(0, y), Direction4::East))
+            .collect_vec(),
+    }
+}
+
+/// How far across the line it's in this position is: the column in a chasm, the row in a tunnel
+pub fn across(axis: ScrollAxis, pos: ICoord) -> isize {
+    match axis {
+        ScrollAxis::Vertical => pos.x,
+        ScrollAxis::Horizontal => pos.y,
+    }
+}
+
+/// The first and last places across the line at this depth that are open to build in.
+/// Both ends are inclusive.
+pub fn line_span(axis: ScrollAxis, profile: &ChasmProfile, depth: isize) -> (isize, isize) {
+    match axis {
+        ScrollAxis::Vertical => profile.span(depth),
+        ScrollAxis::Horizontal => (0, SHAFT_HEIGHT - 1),
+    }
+}
+
+/// Every cell across the line at this depth
+pub fn line_cells(axis: ScrollAxis, profile: &ChasmProfile, depth: isize) -> Vec<ICoord> {
+    let (first, last) = line_span(axis, profile, depth);
+    (first..=last)
+        .map(|across| match axis {
+            ScrollAxis::Vertical => ICoord::new(across, depth),
+            ScrollAxis::Horizontal => ICoord::new(depth, across),
+        })
+        .collect_vec()
+}
+
+/// Whether this spot is up against the rock, which bolsters whatever's there
+pub fn against_rock(axis: ScrollAxis, profile: &ChasmProfile, pos: ICoord) -> bool {
+    match axis {
+        ScrollAxis::Vertical => !profile.is_open(pos),
+        ScrollAxis::Horizontal => pos.x <= 1 || pos.y <= 0,
+    }
+}
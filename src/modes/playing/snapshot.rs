@@ -0,0 +1,170 @@
+use super::{
+    artifacts::Artifacts, blocks::Block, blocks::FallingBlockChunk, chasm::ChasmProfile,
+    combo::Combo, hazards::Hazards, hold::Hold, spawner::Spawner, ModePlaying, RunConfig,
+};
+use crate::{
+    random::GameRng,
+    save::{self, PackedError},
+};
+
+use cogs_gamedev::int_coords::ICoord;
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever what's in a snapshot changes, since packed ones can't fill in new fields
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Everything needed to pick a run back up where it left off.
+///
+/// `ICoord` can't be serialized, so coordinates are stored as plain `(x, y)` pairs.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunSnapshot {
+    pub config: RunConfig,
+    blocks: Vec<((isize, isize), Block)>,
+    falling: Vec<FallingSnapshot>,
+    conveyor: Vec<Block>,
+    #[serde(default)]
+    spawner: Spawner,
+    #[serde(default)]
+    rng: GameRng,
+    blocks_left: Option<usize>,
+    blocks_placed: u32,
+    #[serde(default)]
+    solids_placed: u32,
+    #[serde(default)]
+    biggest_collapse: u32,
+    points: u32,
+    #[serde(default)]
+    anchors_bought: u32,
+    #[serde(default)]
+    artifacts: Artifacts,
+    #[serde(default)]
+    combo: Combo,
+    #[serde(default)]
+    hold: Hold,
+    scroll_depth: f32,
+    frames_elapsed: u64,
+    /// Whether this run has been saved and resumed before
+    pub resumed: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct FallingSnapshot {
+    blocks: Vec<((isize, isize), Block)>,
+    dy: f32,
+    time_alive: u64,
+    #[serde(default)]
+    floating: bool,
+}
+
+impl RunSnapshot {
+    /// Load the suspended run saved under this name.
+    pub fn load(name: &str) -> Result<Self, PackedError> {
+        save::load_packed(name, SNAPSHOT_VERSION)
+    }
+
+    pub fn save(self, name: &str) {
+        save::store_packed(name, SNAPSHOT_VERSION, self);
+    }
+
+    pub fn frames_elapsed(&self) -> u64 {
+        self.frames_elapsed
+    }
+
+    /// Roughly how much memory this is taking up, not counting itself
+    pub fn heap_bytes(&self) -> usize {
+        let block_size = std::mem::size_of::<((isize, isize), Block)>();
+        let falling: usize = self
+            .falling
+            .iter()
+            .map(|chunk| chunk.blocks.capacity() * block_size)
+            .sum();
+        self.blocks.capacity() * block_size
+            + falling
+            + self.conveyor.capacity() * std::mem::size_of::<Block>()
+    }
+}
+
+impl ModePlaying {
+    pub fn snapshot(&self) -> RunSnapshot {
+        RunSnapshot {
+            config: self.config.clone(),
+            blocks: pack(self.stable_blocks.iter().map(|(pos, block)| (*pos, block))),
+            falling: self
+                .falling_blocks
+                .iter()
+                .map(|chunk| FallingSnapshot {
+                    blocks: pack(chunk.blocks.iter().map(|(pos, block)| (*pos, block))),
+                    dy: chunk.dy,
+                    time_alive: chunk.time_alive,
+                    floating: chunk.floating,
+                })
+                .collect(),
+            conveyor: self.conveyor_blocks.clone(),
+            spawner: self.spawner.clone(),
+            rng: self.rng.clone(),
+            blocks_left: self.blocks_left,
+            blocks_placed: self.blocks_placed,
+            solids_placed: self.solids_placed,
+            biggest_collapse: self.biggest_collapse,
+            points: self.points,
+            anchors_bought: self.anchors_bought,
+            artifacts: self.artifacts.clone(),
+            combo: self.combo.clone(),
+            hold: self.hold.clone(),
+            scroll_depth: self.scroll_depth,
+            frames_elapsed: self.frames_elapsed,
+            resumed: self.resumed,
+        }
+    }
+
+    /// Pick a run back up from a snapshot.
+    pub fn from_snapshot(snapshot: RunSnapshot) -> Self {
+        let mut mode = ModePlaying::new(snapshot.config);
+        mode.stable_blocks = unpack(snapshot.blocks).collect();
+        mode.falling_blocks = snapshot
+            .falling
+            .into_iter()
+            .map(|chunk| FallingBlockChunk {
+                blocks: unpack(chunk.blocks).collect(),
+                dy: chunk.dy,
+                prev_dy: chunk.dy,
+                time_alive: chunk.time_alive,
+                floating: chunk.floating,
+            })
+            .collect();
+        mode.conveyor_offsets = vec![0.0; snapshot.conveyor.len()];
+        mode.conveyor_blocks = snapshot.conveyor;
+        mode.spawner = snapshot.spawner;
+        mode.rng = snapshot.rng;
+        mode.profile = ChasmProfile::generate(mode.rng.seed(), &mode.config);
+        mode.hazards = Hazards::generate(mode.rng.seed(), &mode.config);
+        mode.blocks_left = snapshot.blocks_left;
+        mode.blocks_placed = snapshot.blocks_placed;
+        mode.solids_placed = snapshot.solids_placed;
+        mode.biggest_collapse = snapshot.biggest_collapse;
+        mode.points = snapshot.points;
+        mode.anchors_bought = snapshot.anchors_bought;
+        mode.artifacts = snapshot.artifacts;
+        mode.combo = snapshot.combo;
+        mode.hold = snapshot.hold;
+        mode.scroll_depth = snapshot.scroll_depth;
+        mode.prev_scroll_depth = snapshot.scroll_depth;
+        mode.frames_elapsed = snapshot.frames_elapsed;
+        mode.resumed = snapshot.resumed;
+        // a replay has to start from the very beginning
+        mode.recording = None;
+        mode
+    }
+}
+
+fn pack<'a>(blocks: impl Iterator<Item = (ICoord, &'a Block)>) -> Vec<((isize, isize), Block)> {
+    blocks
+        .map(|(pos, block)| ((pos.x, pos.y), block.clone()))
+        .collect()
+}
+
+fn unpack(blocks: Vec<((isize, isize), Block)>) -> impl Iterator<Item = (ICoord, Block)> {
+    blocks
+        .into_iter()
+        .map(|((x, y), block)| (ICoord::new(x, y), block))
+}
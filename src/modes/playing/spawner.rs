@@ -0,0 +1,112 @@
+use super::blocks::{Block, BlockKind};
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+
+/// Special blocks that start showing up in the conveyor once the structure
+/// gets this deep, in order.
+const UNLOCKS: [(isize, BlockKind, &str); 3] = [
+    (12, BlockKind::Girder, "GIRDERS"),
+    (25, BlockKind::Reinforced, "REINFORCED BLOCKS"),
+    (40, BlockKind::Crane, "CRANES"),
+];
+
+/// Chance a freshly made block is swapped for one of the unlocked special kinds
+const SPECIAL_CHANCE: f64 = 0.2;
+
+/// How many blocks are generated ahead of time.
+///
+/// Unlocks only affect blocks generated after them, so they take this many
+/// blocks to start showing up.
+const QUEUE_LENGTH: usize = 5;
+
+/// Makes the blocks that go in the conveyor, with more kinds as the run goes deeper.
+///
+/// Blocks are generated ahead of time into a queue, each from its own seed
+/// derived from the run's seed and its position in the queue.
+/// That way the blocks a run gets only depend on the seed and how deep the
+/// structure was, not on anything else that happened to use randomness.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Spawner {
+    /// How many of the `UNLOCKS` have been reached this run
+    unlocked: usize,
+    seed: u64,
+    /// How many blocks have been put in the queue so far
+    generated: u64,
+    upcoming: VecDeque<Block>,
+    /// If there are any, the only kinds of block to make besides anchors
+    #[serde(default)]
+    kinds: Vec<BlockKind>,
+}
+
+impl Spawner {
+    pub fn new(seed: u64, kinds: Vec<BlockKind>) -> Self {
+        let mut spawner = Self {
+            seed,
+            kinds,
+            ..Self::default()
+        };
+        spawner.fill_queue();
+        spawner
+    }
+
+    /// Unlock anything the structure has gotten deep enough for.
+    /// Returns the names of the newly unlocked blocks, to announce.
+    pub fn update(&mut self, max_depth: isize) -> Vec<&'static str> {
+        let mut announce = Vec::new();
+        if !self.kinds.is_empty() {
+            // the kinds were picked ahead of time, so there's nothing to unlock
+            return announce;
+        }
+        while let Some(&(depth, _, name)) = UNLOCKS.get(self.unlocked) {
+            if max_depth < depth {
+                break;
+            }
+            announce.push(name);
+            self.unlocked += 1;
+        }
+        announce
+    }
+
+    /// The next few blocks that will come off the queue, soonest first.
+    /// There's only ever `QUEUE_LENGTH` of them to see.
+    pub fn peek(&self, count: usize) -> impl Iterator<Item = &Block> {
+        self.upcoming.iter().take(count)
+    }
+
+    /// Take the next block off the queue.
+    pub fn next(&mut self) -> Block {
+        // this can only be empty for old saves that didn't have a queue yet
+        self.fill_queue();
+        let block = self.upcoming.pop_front().unwrap();
+        self.fill_queue();
+        block
+    }
+
+    fn fill_queue(&mut self) {
+        while self.upcoming.len() < QUEUE_LENGTH {
+            // mix the index up so neighboring blocks don't get similar seeds
+            let block_seed = self
+                .seed
+                .wrapping_add(self.generated.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+            let block = self.generate(&mut SmallRng::seed_from_u64(block_seed));
+            self.upcoming.push_back(block);
+            self.generated += 1;
+        }
+    }
+
+    fn generate<R: Rng + ?Sized>(&self, rng: &mut R) -> Block {
+        let mut block: Block = rng.gen();
+        if block.kind != BlockKind::Anchor && !self.kinds.is_empty() {
+            block.kind = self.kinds[rng.gen_range(0..self.kinds.len())].clone();
+        } else if block.kind != BlockKind::Anchor
+            && self.unlocked > 0
+            && rng.gen_bool(SPECIAL_CHANCE)
+        {
+            block.kind = UNLOCKS[rng.gen_range(0..self.unlocked)].1.clone();
+        }
+        block
+    }
+}
@@ -0,0 +1,107 @@
+//! Running the simulation faster or slower than real time, in runs that allow it.
+//!
+//! Minus and equals step down and up through the speeds. Faster speeds run several
+//! steps a tick; slower ones skip ticks, saving up what the player did meanwhile
+//! for the next step that runs.
+//!
+//! Every run goes through here, since this is also what turns the ticks due each
+//! frame into steps. Runs without speed control just stay at 1x.
+
+use super::InputFrame;
+
+/// How many steps each speed runs per tick
+const SPEEDS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+/// Where 1x is in `SPEEDS`
+const NORMAL_SPEED: usize = 2;
+
+#[derive(Clone)]
+pub struct SimSpeed {
+    /// Index into `SPEEDS`
+    idx: usize,
+    /// Fractions of a step left over from earlier frames
+    progress: f32,
+    /// Input from frames where no step ran, waiting for the next one that does
+    pending: Option<InputFrame>,
+}
+
+impl SimSpeed {
+    pub fn new() -> Self {
+        Self {
+            idx: NORMAL_SPEED,
+            progress: 0.0,
+            pending: None,
+        }
+    }
+
+    pub fn faster(&mut self) {
+        self.idx = (self.idx + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.idx = self.idx.saturating_sub(1);
+    }
+
+    pub fn is_normal(&self) -> bool {
+        self.idx == NORMAL_SPEED
+    }
+
+    pub fn label(&self) -> String {
+        format!("SPEED {}X", SPEEDS[self.idx])
+    }
+
+    /// How far along it is to the next step, given how far along the next tick is
+    pub fn alpha(&self, tick_alpha: f32) -> f32 {
+        (self.progress + SPEEDS[self.idx] * tick_alpha).min(1.0)
+    }
+
+    /// The inputs to run steps with this frame, given what the player is doing now
+    /// and how many ticks are due. Only the first step gets any presses;
+    /// the rest just see where things are held.
+    pub fn frame_inputs(&mut self, input: InputFrame, ticks: u32) -> Vec<InputFrame> {
+        let input = match self.pending.take() {
+            Some(earlier) => merge(earlier, input),
+            None => input,
+        };
+        self.progress += SPEEDS[self.idx] * ticks as f32;
+        let steps = self.progress.floor() as usize;
+        self.progress -= steps as f32;
+        if steps == 0 {
+            self.pending = Some(input);
+            return Vec::new();
+        }
+
+        let held = InputFrame {
+            mouse: input.mouse,
+            left_down: input.left_down,
+            ..InputFrame::default()
+        };
+        let mut inputs = vec![input];
+        inputs.resize(steps, held);
+        inputs
+    }
+}
+
+/// One frame's worth of input that has everything that happened in both.
+fn merge(earlier: InputFrame, later: InputFrame) -> InputFrame {
+    let keyboard = if later.keyboard == Default::default() {
+        earlier.keyboard
+    } else {
+        later.keyboard
+    };
+    InputFrame {
+        left_pressed: earlier.left_pressed || later.left_pressed,
+        right_pressed: earlier.right_pressed || later.right_pressed,
+        wheel: if later.wheel != 0 {
+            later.wheel
+        } else {
+            earlier.wheel
+        },
+        suspend: earlier.suspend || later.suspend,
+        keyboard,
+        pressed_at: earlier.pressed_at.or(later.pressed_at),
+        released_at: later.released_at.or(earlier.released_at),
+        undo: earlier.undo || later.undo,
+        zoom: earlier.zoom || later.zoom,
+        ..later
+    }
+}
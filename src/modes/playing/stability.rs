@@ -0,0 +1,217 @@
+//! How much weight each block is holding up, which is what wears blocks down.
+//!
+//! Every block's mass goes to whatever holds it up, the blocks it links to or rests on,
+//! and from those on towards the anchors. The blocks nearest the anchors end up carrying
+//! everything hanging off them. A block wears faster the more it carries next to its strength.
+//!
+//! Working that out floods through the whole structure, so it's cached until
+//! a block is put in or taken out. Wear alone doesn't change what holds what up.
+//!
+//! Tab cycles through the overlays: the column panel, then one tinting every block
+//! by how likely it is to take damage, then neither.
+
+use super::{
+    blockmap::BlockMap, blocks::Block, geometry::Viewport, site, Gravity, ModePlaying, BLOCK_SIZE,
+};
+use crate::drawutils::hexcolor;
+
+use cogs_gamedev::{directions::Direction4, int_coords::ICoord};
+use itertools::Itertools;
+
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
+    rc::Rc,
+};
+
+/// Chance a block takes damage each time wear is rolled for,
+/// when it's carrying exactly as much as its strength
+const LOAD_WEAR: f64 = 1.0 / 60.0;
+/// The most chance there is, however overloaded a block is
+const MAX_WEAR: f64 = 3.0 / 60.0;
+
+/// Tint over blocks that can't take damage at all
+const SAFE_COLOR: u32 = 0x6abe3070;
+/// Tint over blocks at the highest chance there is, or worse
+const RISKY_COLOR: u32 = 0xd95763a0;
+
+/// Which overlay is showing over the chasm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overlay {
+    None,
+    /// How deep each column goes
+    Columns,
+    /// How likely each block is to take damage
+    Stability,
+}
+
+impl Overlay {
+    /// The one Tab switches to from this one
+    pub fn next(self) -> Self {
+        match self {
+            Overlay::None => Overlay::Columns,
+            Overlay::Columns => Overlay::Stability,
+            Overlay::Stability => Overlay::None,
+        }
+    }
+}
+
+/// What [`ModePlaying::loads`] and [`ModePlaying::held_up`] came to for one layout of the blocks.
+///
+/// Most steps nothing gets placed or breaks, so these get reused instead of worked out again.
+#[derive(Clone, Default)]
+pub(super) struct StabilityCache {
+    loads: Option<(u64, Rc<HashMap<ICoord, f32>>)>,
+    held_up: Option<(u64, Rc<HashSet<ICoord>>)>,
+}
+
+impl StabilityCache {
+    pub(super) fn loads(
+        &mut self,
+        stable_blocks: &BlockMap,
+        gravity: Gravity,
+    ) -> Rc<HashMap<ICoord, f32>> {
+        let layout = stable_blocks.layout();
+        match &self.loads {
+            Some((cached, loads)) if *cached == layout => loads.clone(),
+            _ => {
+                let loads = Rc::new(ModePlaying::loads(stable_blocks, gravity));
+                self.loads = Some((layout, loads.clone()));
+                loads
+            }
+        }
+    }
+
+    pub(super) fn held_up(
+        &mut self,
+        stable_blocks: &BlockMap,
+        gravity: Gravity,
+    ) -> Rc<HashSet<ICoord>> {
+        let layout = stable_blocks.layout();
+        match &self.held_up {
+            Some((cached, held_up)) if *cached == layout => held_up.clone(),
+            _ => {
+                let held_up = Rc::new(ModePlaying::held_up(stable_blocks, gravity));
+                self.held_up = Some((layout, held_up.clone()));
+                held_up
+            }
+        }
+    }
+}
+
+impl ModePlaying {
+    /// Whether the block at `holder` holds up the one next to it in this direction,
+    /// by having it rest on top or by linking to it.
+    pub(super) fn holds_up(
+        stable_blocks: &BlockMap,
+        holder: ICoord,
+        facing: Direction4,
+        gravity: Gravity,
+    ) -> bool {
+        let block = match stable_blocks.get(&holder) {
+            Some(it) => it,
+            None => return false,
+        };
+        if facing == gravity.up() {
+            stable_blocks.contains_key(&(holder + facing.deltas()))
+        } else {
+            Self::would_link(stable_blocks, holder, block, facing)
+        }
+    }
+
+    /// How much weight each held-up block is carrying, its own included.
+    ///
+    /// Blocks further from the anchors go first, splitting everything they carry
+    /// evenly between whatever holds them up, so each block's mass is counted once.
+    pub(super) fn loads(stable_blocks: &BlockMap, gravity: Gravity) -> HashMap<ICoord, f32> {
+        // How many blocks away from an anchor each one is, going the way `held_up` does
+        let mut steps = HashMap::new();
+        let mut queue = Self::anchored(stable_blocks)
+            .into_iter()
+            .map(|pos| (pos, 0))
+            .collect::<VecDeque<_>>();
+        while let Some((pos, step)) = queue.pop_front() {
+            if steps.contains_key(&pos) {
+                continue;
+            }
+            steps.insert(pos, step);
+            for &dir in Direction4::DIRECTIONS.iter() {
+                if Self::holds_up(stable_blocks, pos, dir, gravity) {
+                    queue.push_back((pos + dir.deltas(), step + 1));
+                }
+            }
+        }
+
+        // sorted all the way down so the sums come out the same every time
+        let order = steps
+            .iter()
+            .map(|(&pos, &step)| (Reverse(step), pos.y, pos.x))
+            .sorted()
+            .collect_vec();
+        let mut loads = steps
+            .keys()
+            .map(|&pos| (pos, stable_blocks[&pos].mass()))
+            .collect::<HashMap<_, _>>();
+        for (Reverse(step), y, x) in order {
+            let pos = ICoord::new(x, y);
+            let holders = Direction4::DIRECTIONS
+                .iter()
+                .filter(|&&dir| {
+                    let holder = pos + dir.deltas();
+                    matches!(steps.get(&holder), Some(&held_at) if held_at < step)
+                        && Self::holds_up(stable_blocks, holder, dir.flip(), gravity)
+                })
+                .map(|dir| pos + dir.deltas())
+                .collect_vec();
+            // anchors have nothing to pass it on to but the rock
+            if holders.is_empty() {
+                continue;
+            }
+            let share = loads[&pos] / holders.len() as f32;
+            for holder in holders {
+                *loads.get_mut(&holder).unwrap() += share;
+            }
+        }
+        loads
+    }
+
+    /// The chance this block takes damage each time wear is rolled for, carrying this much,
+    /// before the decay multiplier and full rows.
+    pub(super) fn break_chance(&self, pos: ICoord, block: &Block, load: f32) -> f64 {
+        let mut break_chance = (LOAD_WEAR * (load / block.strength()) as f64).min(MAX_WEAR);
+        // Blocks by the wall are more bolstered
+        if site::against_rock(self.config.scroll_axis, &self.profile, pos) {
+            break_chance /= 2.0;
+        }
+        break_chance
+    }
+
+    /// Tint each block from green to red by its chance to take damage as of the last step.
+    /// Draw it in the view's own pixels.
+    pub(super) fn draw_stability(&self, view: &Viewport) {
+        use macroquad::prelude::*;
+
+        let (safe, risky) = (hexcolor(SAFE_COLOR), hexcolor(RISKY_COLOR));
+        for &(pos, chance) in &self.break_chances {
+            if !self.stable_blocks.contains_key(&pos) {
+                // it broke since
+                continue;
+            }
+            let t = (chance / MAX_WEAR).clamp(0.0, 1.0) as f32;
+            let color = Color::new(
+                safe.r + (risky.r - safe.r) * t,
+                safe.g + (risky.g - safe.g) * t,
+                safe.b + (risky.b - safe.b) * t,
+                safe.a + (risky.a - safe.a) * t,
+            );
+            let (cx, cy) = view.block_to_pixel(pos);
+            draw_rectangle(
+                cx - BLOCK_SIZE / 2.0,
+                cy - BLOCK_SIZE / 2.0,
+                BLOCK_SIZE,
+                BLOCK_SIZE,
+                color,
+            );
+        }
+    }
+}
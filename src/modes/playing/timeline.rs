@@ -0,0 +1,108 @@
+use super::RunSnapshot;
+use crate::{
+    drawutils::{self, hexcolor},
+    ui, Globals,
+};
+
+use macroquad::prelude::*;
+
+/// How often a snapshot is added to the timeline, in frames
+pub const TIMELINE_INTERVAL: u64 = 120;
+/// Most snapshots kept; after this the oldest ones are forgotten
+const TIMELINE_MAX: usize = 150;
+
+/// Where the timeline bar goes when paused
+const BAR_RECT: Rect = Rect {
+    x: 16.0,
+    y: 216.0,
+    w: 216.0,
+    h: 8.0,
+};
+
+/// Snapshots of a practice run over time, to scrub back through.
+#[derive(Clone, Default)]
+pub struct Timeline {
+    snapshots: Vec<RunSnapshot>,
+    /// Which snapshot is being looked at while scrubbing
+    cursor: usize,
+}
+
+impl Timeline {
+    pub fn record(&mut self, snapshot: RunSnapshot) {
+        if self.snapshots.len() >= TIMELINE_MAX {
+            self.snapshots.remove(0);
+        }
+        self.snapshots.push(snapshot);
+        self.cursor = self.snapshots.len() - 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Roughly how much memory all the snapshots are taking up
+    pub fn heap_bytes(&self) -> usize {
+        self.snapshots.iter().map(RunSnapshot::heap_bytes).sum()
+    }
+
+    /// Move the cursor to this snapshot, and return it.
+    pub fn select(&mut self, idx: usize) -> Option<&RunSnapshot> {
+        let idx = idx.min(self.snapshots.len().checked_sub(1)?);
+        self.cursor = idx;
+        self.snapshots.get(idx)
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Forget everything after the cursor, because the player is
+    /// going to play on from there.
+    pub fn branch(&mut self) {
+        self.snapshots.truncate(self.cursor + 1);
+    }
+
+    /// The snapshot on the bar under this position, if any
+    pub fn index_at(&self, mx: f32, my: f32) -> Option<usize> {
+        let grabbable = Rect::new(
+            BAR_RECT.x - 4.0,
+            BAR_RECT.y - 4.0,
+            BAR_RECT.w + 8.0,
+            BAR_RECT.h + 8.0,
+        );
+        if self.snapshots.is_empty() || !grabbable.contains(vec2(mx, my)) {
+            return None;
+        }
+        let frac = ((mx - BAR_RECT.x) / BAR_RECT.w).clamp(0.0, 1.0);
+        Some((frac * (self.snapshots.len() - 1) as f32).round() as usize)
+    }
+
+    pub fn draw(&self, globals: &Globals) {
+        let Rect { x, y, w, h } = BAR_RECT;
+        ui::draw_panel(Rect::new(x - 3.0, y - 10.0, w + 6.0, h + 13.0));
+
+        let fg = hexcolor(ui::FOREGROUND);
+        let dim = hexcolor(ui::DIM);
+        draw_line(x, y + h / 2.0, x + w, y + h / 2.0, 1.0, dim);
+
+        let last = self.snapshots.len().saturating_sub(1).max(1) as f32;
+        for idx in 0..self.snapshots.len() {
+            let tick_x = (x + w * idx as f32 / last).floor() + 0.5;
+            let color = if idx <= self.cursor { fg } else { dim };
+            draw_line(tick_x, y + 2.0, tick_x, y + h - 2.0, 1.0, color);
+        }
+        let cursor_x = (x + w * self.cursor as f32 / last).floor();
+        draw_rectangle(cursor_x - 1.0, y, 3.0, h, fg);
+
+        let seconds = self
+            .snapshots
+            .get(self.cursor)
+            .map_or(0, |snapshot| snapshot.frames_elapsed() / 60);
+        let label = format!(
+            "PAUSED  {}:{:02}  ARROWS/CLICK TO SCRUB",
+            seconds / 60,
+            seconds % 60
+        );
+        drawutils::draw_text_px(&label, x, y - 8.0, fg, globals);
+    }
+}
@@ -0,0 +1,36 @@
+//! What the tutorial gets to see of a run, and where it can point.
+//!
+//! The tutorial itself is [`ModeTutorial`](crate::modes::ModeTutorial);
+//! it plays a run the normal way and watches it through these.
+
+use super::{conveyor_slot_rect, events::PlayEvent, BlockKind, ModePlaying};
+
+use macroquad::math::Rect;
+
+impl ModePlaying {
+    /// What happened on the last step
+    pub fn events(&self) -> &[PlayEvent] {
+        &self.frame_events
+    }
+
+    /// The part of the belt blocks can be picked up from, on the canvas
+    pub fn conveyor_area(&self) -> Rect {
+        let bottom = conveyor_slot_rect(0);
+        let top = conveyor_slot_rect(self.config.conveyor_size.saturating_sub(1));
+        bottom.combine_with(top)
+    }
+
+    /// Around the anchors in view, on the canvas, if there are any
+    pub fn anchor_area(&self) -> Option<Rect> {
+        let view = self.viewport();
+        self.stable_blocks
+            .iter()
+            .filter(|(_, block)| block.kind == BlockKind::Anchor)
+            .map(|(&pos, _)| {
+                let (x, y) = view.block_to_screen(pos);
+                let size = view.block_size();
+                Rect::new(x - size / 2.0, y - size / 2.0, size, size)
+            })
+            .reduce(Rect::combine_with)
+    }
+}
@@ -0,0 +1,127 @@
+//! Taking back placements, for when a click lands in the wrong spot.
+//!
+//! A block can only be taken back while it's sitting where it was put, untouched.
+//! Once it's been knocked at or has fallen, it's part of the run for good.
+
+use super::{
+    artifacts::Artifacts, blocks::Block, combo::Combo, events::PlayEvent, spawner::Spawner,
+    ModePlaying,
+};
+use crate::{ui, Globals, HEIGHT};
+
+use cogs_gamedev::int_coords::ICoord;
+
+use std::collections::VecDeque;
+
+/// How many placements back can be undone
+const UNDO_DEPTH: usize = 5;
+
+/// How things were right before a block was placed.
+#[derive(Clone)]
+struct Placement {
+    pos: ICoord,
+    conveyor_blocks: Vec<Block>,
+    spawner: Spawner,
+    blocks_left: Option<usize>,
+    blocks_placed: u32,
+    solids_placed: u32,
+    points: u32,
+    artifacts: Artifacts,
+    combo: Combo,
+    last_placed: Option<ICoord>,
+}
+
+/// The last few placements, newest at the back.
+#[derive(Clone, Default)]
+pub struct UndoStack {
+    placements: VecDeque<Placement>,
+}
+
+impl UndoStack {
+    pub fn clear(&mut self) {
+        self.placements.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.placements.is_empty()
+    }
+}
+
+impl ModePlaying {
+    /// Whether placements can be taken back in this run.
+    /// In versus, the other player has already seen it.
+    pub(super) fn can_undo(&self) -> bool {
+        self.versus.is_none()
+    }
+
+    /// Note how things are before a block goes here, so it can be taken back.
+    pub(super) fn remember_placement(&mut self, pos: ICoord) {
+        if !self.can_undo() {
+            return;
+        }
+        let placements = &mut self.undo.placements;
+        if placements.len() >= UNDO_DEPTH {
+            placements.pop_front();
+        }
+        placements.push_back(Placement {
+            pos,
+            conveyor_blocks: self.conveyor_blocks.clone(),
+            spawner: self.spawner.clone(),
+            blocks_left: self.blocks_left,
+            blocks_placed: self.blocks_placed,
+            solids_placed: self.solids_placed,
+            points: self.points,
+            artifacts: self.artifacts.clone(),
+            combo: self.combo.clone(),
+            last_placed: self.last_placed,
+        });
+    }
+
+    /// Take back the last placement, if that block's still sitting untouched where it was put.
+    pub(super) fn undo_placement(&mut self) {
+        let placement = match self.undo.placements.pop_back() {
+            Some(it) => it,
+            None => return,
+        };
+        let untouched = self
+            .stable_blocks
+            .get(&placement.pos)
+            .map_or(false, |block| block.damage == 0);
+        if !untouched {
+            // anything before it has been built on since, so it's all stuck now
+            self.undo.clear();
+            self.toasts.push("CAN'T UNDO THAT ANYMORE");
+            return;
+        }
+
+        self.stable_blocks.remove(&placement.pos);
+        // the conveyor slots are about to shuffle around under anyone's hand
+        self.held = None;
+        if let Some(second) = &mut self.second {
+            second.held = None;
+        }
+        self.conveyor_offsets = vec![0.0; placement.conveyor_blocks.len()];
+        self.conveyor_blocks = placement.conveyor_blocks;
+        self.spawner = placement.spawner;
+        self.blocks_left = placement.blocks_left;
+        self.blocks_placed = placement.blocks_placed;
+        self.solids_placed = placement.solids_placed;
+        self.points = placement.points;
+        self.artifacts = placement.artifacts;
+        self.combo = placement.combo;
+        self.last_placed = placement.last_placed;
+        self.emit(PlayEvent::Undone);
+    }
+
+    /// Draw the button for undoing, if there's anything to undo.
+    pub(super) fn draw_undo_button(&self, globals: &Globals) {
+        if !self.undo.is_empty() {
+            undo_button().draw(false, globals);
+        }
+    }
+}
+
+/// Clicking it does the same as ctrl+Z
+pub(super) fn undo_button() -> ui::Button {
+    ui::Button::new(4.0, HEIGHT - 23.0, 32.0, 11.0, "UNDO")
+}
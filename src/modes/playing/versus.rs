@@ -0,0 +1,108 @@
+//! Hot-seat versus: two players take turns placing blocks on one structure,
+//! and whoever brings it down loses.
+
+use super::{coop, ModePlaying};
+use crate::{drawutils, ui};
+
+/// Any clump of at least this many blocks falling at once is a collapse, and ends the match
+pub const COLLAPSE_SIZE: usize = 3;
+
+/// Who's doing what in a versus match.
+#[derive(Clone, Default)]
+pub struct Versus {
+    /// Whose turn it is, 0 or 1
+    pub turn: usize,
+    /// How many blocks each player has placed
+    pub blocks_placed: [u32; 2],
+    /// How much mass broke or fell on each player's turns
+    pub mass_lost: [f32; 2],
+    /// Set once someone causes a collapse
+    pub collapse: Option<Collapse>,
+}
+
+/// The collapse that ended a match.
+#[derive(Clone, Copy)]
+pub struct Collapse {
+    /// Whose turn it was
+    pub player: usize,
+    pub blocks: usize,
+}
+
+/// How a versus match turned out.
+#[derive(Clone, Copy)]
+pub enum VersusOutcome {
+    /// This player brought the structure down
+    Collapse(Collapse),
+    /// Nobody collapsed it, but this player lost more mass
+    MassLost(usize),
+    Draw,
+}
+
+impl Versus {
+    /// Pass the turn along after a block is placed.
+    pub fn placed(&mut self) {
+        self.blocks_placed[self.turn] += 1;
+        self.turn = 1 - self.turn;
+    }
+
+    /// Blame whoever's turn it is for blocks coming loose.
+    pub fn lost(&mut self, mass: f32, blocks: usize) {
+        self.mass_lost[self.turn] += mass;
+        if blocks >= COLLAPSE_SIZE && self.collapse.is_none() {
+            self.collapse = Some(Collapse {
+                player: self.turn,
+                blocks,
+            });
+        }
+    }
+
+    /// The losing player, if there is one
+    pub fn outcome(&self) -> VersusOutcome {
+        if let Some(collapse) = self.collapse {
+            VersusOutcome::Collapse(collapse)
+        } else if self.mass_lost[0] > self.mass_lost[1] {
+            VersusOutcome::MassLost(0)
+        } else if self.mass_lost[1] > self.mass_lost[0] {
+            VersusOutcome::MassLost(1)
+        } else {
+            VersusOutcome::Draw
+        }
+    }
+}
+
+/// Name to show for this player
+pub fn player_name(player: usize) -> String {
+    format!("PLAYER {}", player + 1)
+}
+
+/// Color to show this player in
+pub fn player_color(player: usize) -> u32 {
+    if player == 0 {
+        coop::PLAYER_ONE_COLOR
+    } else {
+        coop::PLAYER_TWO_COLOR
+    }
+}
+
+impl ModePlaying {
+    /// Show whose turn it is.
+    pub(super) fn draw_turn_banner(&self, globals: &crate::Globals) {
+        use macroquad::prelude::*;
+
+        let versus = match &self.versus {
+            Some(it) => it,
+            None => return,
+        };
+        let label = format!("{}'S TURN", player_name(versus.turn));
+        let width = drawutils::text_width_px(&label) + 8.0;
+        let x = ((super::WIDTH - 70.0 - width) / 2.0).round();
+        ui::draw_panel(Rect::new(x, 4.0, width, 11.0));
+        drawutils::draw_text_px(
+            &label,
+            x + 4.0,
+            7.0,
+            drawutils::hexcolor(player_color(versus.turn)),
+            globals,
+        );
+    }
+}
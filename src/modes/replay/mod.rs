@@ -0,0 +1,329 @@
+mod picker;
+pub use picker::ModeReplayPicker;
+
+use crate::{
+    drawutils::{self, hexcolor, mouse_position_pixel},
+    modes::playing::{ReplayHeader, RunEvent, RunEventKind},
+    music::Track,
+    ui::{self, Button},
+    Gamemode, Globals, InputFrame, ModePlaying, ModeTitle, Replay, Transition, HEIGHT,
+};
+
+use macroquad::prelude::*;
+
+/// How many frames the playback speed button cycles through running each frame
+const SPEEDS: [usize; 3] = [1, 2, 4];
+/// How often a copy of the run is kept to seek backwards from, in frames
+const CHECKPOINT_INTERVAL: usize = 600;
+
+/// Where the progress bar goes
+const BAR_RECT: Rect = Rect {
+    x: 8.0,
+    y: 229.0,
+    w: 232.0,
+    h: 6.0,
+};
+
+/// Watch a saved replay play out.
+///
+/// Keys: space pauses, right arrow steps one frame, S changes the speed,
+/// and the brackets jump between depth milestones.
+/// Decisive moments are marked above the progress bar; click one to jump there.
+#[derive(Clone)]
+pub struct ModeReplay {
+    header: ReplayHeader,
+    playing: ModePlaying,
+    /// Input for every frame, in order
+    inputs: Vec<InputFrame>,
+    /// Collapses and such, from the recording
+    events: Vec<RunEvent>,
+    /// Index of the next input to play
+    cursor: usize,
+    /// Whether the run has played all the way out
+    finished: bool,
+
+    paused: bool,
+    /// Index into `SPEEDS`
+    speed: usize,
+    /// Copies of the run every `CHECKPOINT_INTERVAL` frames, and the cursor at each.
+    /// Seeking backwards starts from the closest one.
+    checkpoints: Vec<(usize, ModePlaying)>,
+    /// The cursor right after each depth milestone was passed, as far as it's been played
+    milestone_frames: Vec<usize>,
+}
+
+impl ModeReplay {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            playing: ModePlaying::for_replay(&replay.header),
+            inputs: replay.inputs.frames().collect(),
+            events: replay.events,
+            header: replay.header,
+            cursor: 0,
+            finished: false,
+            paused: false,
+            speed: 0,
+            checkpoints: Vec::new(),
+            milestone_frames: Vec::new(),
+        }
+    }
+
+    /// Play the next frame of the replay.
+    fn advance(&mut self, globals: &mut Globals) {
+        if self.finished {
+            self.playing.idle();
+            return;
+        }
+        let input = match self.inputs.get(self.cursor) {
+            Some(&it) => it,
+            None => {
+                self.finished = true;
+                return;
+            }
+        };
+
+        let milestones_before = self.playing.milestones_reached();
+        // Whatever the run wants to go to next, the replay is over
+        if !matches!(self.playing.step(input, globals), Transition::None) {
+            self.finished = true;
+        }
+        self.cursor += 1;
+        let cursor = self.cursor;
+
+        // After seeking backwards, these frames might have been played already
+        if self.playing.milestones_reached() > milestones_before
+            && self
+                .milestone_frames
+                .last()
+                .map_or(true, |&frame| frame < cursor)
+        {
+            self.milestone_frames.push(cursor);
+        }
+        if cursor % CHECKPOINT_INTERVAL == 0
+            && self
+                .checkpoints
+                .last()
+                .map_or(true, |(frame, _)| *frame < cursor)
+        {
+            self.checkpoints.push((cursor, self.playing.clone()));
+        }
+    }
+
+    /// Go to this frame of the replay, forwards or backwards.
+    fn seek(&mut self, target: usize, globals: &mut Globals) {
+        let target = target.min(self.inputs.len());
+        if target < self.cursor {
+            match self
+                .checkpoints
+                .iter()
+                .rev()
+                .find(|(frame, _)| *frame <= target)
+            {
+                Some((frame, playing)) => {
+                    self.cursor = *frame;
+                    self.playing = playing.clone();
+                }
+                None => {
+                    // all the way back to the start
+                    self.cursor = 0;
+                    self.playing = ModePlaying::for_replay(&self.header);
+                }
+            }
+            self.finished = false;
+        }
+        while self.cursor < target && !self.finished {
+            self.advance(globals);
+        }
+        self.playing.idle();
+    }
+
+    /// Jump forwards to the next time the run passes a depth milestone.
+    fn next_milestone(&mut self, globals: &mut Globals) {
+        let cursor = self.cursor;
+        if let Some(&frame) = self.milestone_frames.iter().find(|&&frame| frame > cursor) {
+            self.seek(frame, globals);
+        } else {
+            // haven't gotten that far yet; play until we do
+            let known = self.milestone_frames.len();
+            while !self.finished && self.milestone_frames.len() == known {
+                self.advance(globals);
+            }
+            self.playing.idle();
+        }
+    }
+
+    /// Jump backwards to the last time the run passed a depth milestone.
+    fn prev_milestone(&mut self, globals: &mut Globals) {
+        let cursor = self.cursor;
+        let target = self
+            .milestone_frames
+            .iter()
+            .rev()
+            .find(|&&frame| frame < cursor)
+            .copied()
+            .unwrap_or(0);
+        self.seek(target, globals);
+    }
+
+    /// Where on the screen the marker for an event goes
+    fn marker_rect(&self, event: &RunEvent) -> Rect {
+        let frac = event.frame as f32 / self.inputs.len().max(1) as f32;
+        let x = (BAR_RECT.x + BAR_RECT.w * frac).floor();
+        Rect::new(x - 1.0, BAR_RECT.y - 5.0, 3.0, 4.0)
+    }
+
+    /// The event whose marker is under the mouse, if any.
+    /// Later events win, because they're drawn on top.
+    fn event_at(&self, mx: f32, my: f32) -> Option<RunEvent> {
+        self.events
+            .iter()
+            .rev()
+            .find(|event| {
+                let rect = self.marker_rect(event);
+                // a little leeway, they're tiny
+                Rect::new(rect.x - 1.0, rect.y - 1.0, rect.w + 2.0, rect.h + 2.0)
+                    .contains(vec2(mx, my))
+            })
+            .copied()
+    }
+}
+
+impl Gamemode for ModeReplay {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        if is_key_pressed(KeyCode::Escape) || quit_button().clicked() {
+            return Transition::Swap(Box::new(ModeTitle::new()));
+        }
+
+        if is_key_pressed(KeyCode::Space) || pause_button(self.paused).clicked() {
+            self.paused = !self.paused;
+        }
+        if is_key_pressed(KeyCode::S) || speed_button(self.speed).clicked() {
+            self.speed = (self.speed + 1) % SPEEDS.len();
+        }
+
+        let (mx, my) = mouse_position_pixel();
+        let marker = self.event_at(mx, my);
+        let grabbable = Rect::new(BAR_RECT.x, BAR_RECT.y - 2.0, BAR_RECT.w, BAR_RECT.h + 4.0);
+        if let (Some(event), true) = (marker, is_mouse_button_pressed(MouseButton::Left)) {
+            // land just after it happened
+            let target = event.frame as usize + 1;
+            self.paused = true;
+            self.seek(target, globals);
+        } else if is_mouse_button_down(MouseButton::Left) && grabbable.contains(vec2(mx, my)) {
+            let frac = ((mx - BAR_RECT.x) / BAR_RECT.w).clamp(0.0, 1.0);
+            let target = (frac * self.inputs.len() as f32).round() as usize;
+            self.seek(target, globals);
+        } else if is_key_pressed(KeyCode::LeftBracket) || prev_button().clicked() {
+            self.prev_milestone(globals);
+        } else if is_key_pressed(KeyCode::RightBracket) || next_button().clicked() {
+            self.next_milestone(globals);
+        } else if is_key_pressed(KeyCode::Right) || step_button().clicked() {
+            self.paused = true;
+            self.advance(globals);
+        } else if !self.paused {
+            let steps = SPEEDS[self.speed] * globals.timestep.ticks() as usize;
+            if steps == 0 {
+                self.playing.between_steps();
+            }
+            for _ in 0..steps {
+                self.advance(globals);
+                self.playing.cue_event_sounds(globals);
+            }
+        } else {
+            self.playing.idle();
+        }
+
+        Transition::None
+    }
+
+    fn draw(&self, globals: &Globals) {
+        self.playing.draw(globals);
+
+        let fg = hexcolor(ui::FOREGROUND);
+        let dim = hexcolor(ui::DIM);
+        let label = format!("REPLAY: {}", self.header.player);
+        let width = drawutils::text_width_px(&label) + 8.0;
+        ui::draw_panel(Rect::new(56.0, 2.0, width, 11.0));
+        drawutils::draw_text_px(&label, 60.0, 5.0, fg, globals);
+
+        if self.finished {
+            ui::draw_centered_text("END OF REPLAY", HEIGHT / 2.0, fg, globals);
+        }
+
+        // The playback controls
+        ui::draw_panel(Rect::new(4.0, 210.0, 240.0, 28.0));
+        pause_button(self.paused).draw(self.paused, globals);
+        step_button().draw(false, globals);
+        speed_button(self.speed).draw(self.speed != 0, globals);
+        prev_button().draw(false, globals);
+        next_button().draw(false, globals);
+        quit_button().draw(false, globals);
+
+        let seconds = |frames: usize| {
+            let seconds = frames / 60;
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        };
+        let time = format!("{}/{}", seconds(self.cursor), seconds(self.inputs.len()));
+        drawutils::draw_text_px(&time, 166.0, 216.0, fg, globals);
+
+        let Rect { x, y, w, h } = BAR_RECT;
+        let frac_x = |frame: usize| x + w * frame as f32 / self.inputs.len().max(1) as f32;
+        draw_rectangle(x, y, w, h, hexcolor(ui::BUTTON));
+        draw_rectangle(x, y, (frac_x(self.cursor) - x).floor(), h, dim);
+        for &frame in &self.milestone_frames {
+            let tick_x = frac_x(frame).floor() + 0.5;
+            draw_line(tick_x, y - 1.0, tick_x, y + h + 1.0, 1.0, fg);
+        }
+        draw_rectangle(frac_x(self.cursor).floor() - 1.0, y - 1.0, 3.0, h + 2.0, fg);
+
+        for event in &self.events {
+            let Rect { x, y, w, h } = self.marker_rect(event);
+            let color = match event.kind {
+                RunEventKind::Collapse(_) => dim,
+                RunEventKind::AnchorLost => hexcolor(ui::ALERT),
+                RunEventKind::RowCompleted(_) => fg,
+            };
+            draw_rectangle(x, y, w, h, color);
+        }
+        let (mx, my) = mouse_position_pixel();
+        if let Some(event) = self.event_at(mx, my) {
+            let label = event.kind.describe();
+            let width = drawutils::text_width_px(&label) + 8.0;
+            let x = (mx - width / 2.0).clamp(2.0, 246.0 - width).round();
+            ui::draw_panel(Rect::new(x, 196.0, width, 11.0));
+            drawutils::draw_text_px(&label, x + 4.0, 199.0, fg, globals);
+        }
+    }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Playing)
+    }
+}
+
+fn pause_button(paused: bool) -> Button {
+    let label = if paused { "PLAY" } else { "PAUSE" };
+    Button::new(8.0, 213.0, 30.0, 11.0, label)
+}
+
+/// Pause and play one frame
+fn step_button() -> Button {
+    Button::new(40.0, 213.0, 26.0, 11.0, "STEP")
+}
+
+fn speed_button(speed: usize) -> Button {
+    Button::new(68.0, 213.0, 18.0, 11.0, format!("{}X", SPEEDS[speed]))
+}
+
+/// Back to the last depth milestone
+fn prev_button() -> Button {
+    Button::new(88.0, 213.0, 22.0, 11.0, "<<M")
+}
+
+/// Ahead to the next depth milestone
+fn next_button() -> Button {
+    Button::new(112.0, 213.0, 22.0, 11.0, "M>>")
+}
+
+fn quit_button() -> Button {
+    Button::new(136.0, 213.0, 26.0, 11.0, "QUIT")
+}
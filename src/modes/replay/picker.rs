@@ -0,0 +1,101 @@
+use super::ModeReplay;
+use crate::{
+    drawutils::{self, hexcolor},
+    ui::{self, Button},
+    Gamemode, Globals, Replay, Transition,
+};
+
+use macroquad::prelude::*;
+
+/// How many replays fit on the screen at once
+const PAGE_SIZE: usize = 8;
+
+/// Pick a saved replay to watch.
+#[derive(Clone)]
+pub struct ModeReplayPicker {
+    /// Each replay's save name, and the replay if it could be read
+    entries: Vec<(String, Result<Replay, String>)>,
+    back: Button,
+    /// Message about the last replay that couldn't be played
+    error: Option<String>,
+}
+
+impl ModeReplayPicker {
+    pub fn new() -> Self {
+        let entries = Replay::list()
+            .into_iter()
+            .take(PAGE_SIZE)
+            .map(|name| {
+                let replay = Replay::load(&name);
+                (name, replay)
+            })
+            .collect();
+        Self {
+            entries,
+            back: Button::new(128.0, 200.0, 64.0, 16.0, "BACK"),
+            error: None,
+        }
+    }
+}
+
+impl Gamemode for ModeReplayPicker {
+    fn update(&mut self, _globals: &mut Globals) -> Transition {
+        if self.back.clicked() || is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop;
+        }
+        for (idx, (name, replay)) in self.entries.iter().enumerate() {
+            if entry_button(idx, replay).clicked() {
+                match replay {
+                    Ok(replay) => {
+                        return Transition::Swap(Box::new(ModeReplay::new(replay.clone())))
+                    }
+                    Err(err) => self.error = Some(format!("{}: {}", name, err)),
+                }
+            }
+        }
+        Transition::None
+    }
+
+    fn draw(&self, globals: &Globals) {
+        clear_background(hexcolor(ui::BACKGROUND));
+        let fg = hexcolor(ui::FOREGROUND);
+        let dim = hexcolor(ui::DIM);
+
+        ui::draw_centered_text("REPLAYS", 24.0, fg, globals);
+        if self.entries.is_empty() {
+            ui::draw_centered_text("NO REPLAYS SAVED YET", 100.0, dim, globals);
+            ui::draw_centered_text("SAVE ONE AT THE END OF A RUN", 110.0, dim, globals);
+        }
+        for (idx, (_, replay)) in self.entries.iter().enumerate() {
+            entry_button(idx, replay).draw(false, globals);
+        }
+        if let Some(error) = &self.error {
+            ui::draw_centered_text(error, 188.0, dim, globals);
+        }
+        self.back.draw(false, globals);
+    }
+}
+
+/// The button for the replay at this index in the list
+fn entry_button(idx: usize, replay: &Result<Replay, String>) -> Button {
+    let label = match replay {
+        Ok(replay) => {
+            let header = &replay.header;
+            let player = if header.player.is_empty() {
+                "???"
+            } else {
+                &header.player
+            };
+            format!(
+                "{} - {} - DEPTH {}",
+                player,
+                header.config.game_type.name(),
+                header.result.depth.round() as i32
+            )
+        }
+        Err(_) => "UNREADABLE REPLAY".to_owned(),
+    };
+    let width = drawutils::text_width_px(&label) + 8.0;
+    let x = ((crate::WIDTH - width) / 2.0).round();
+    Button::new(x, 40.0 + idx as f32 * 18.0, width, 15.0, label)
+}
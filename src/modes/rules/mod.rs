@@ -1,25 +1,311 @@
-use crate::{Globals, Transition};
+use crate::{
+    drawutils::{self, hexcolor, TextAlign},
+    modes::playing::{Block, BlockKind, Connector, ConnectorShape, BLOCK_SIZE},
+    modes::{GameType, ModePlaying, RunConfig},
+    ui::{self, Button},
+    Gamemode, Globals, ModeTutorial, Transition, HEIGHT, WIDTH,
+};
 
+use cogs_gamedev::directions::Direction4;
 use macroquad::prelude::*;
 
+/// How long each diagram's animation takes before it starts over, in frames
+const DIAGRAM_LOOP: u64 = 180;
+/// Where the diagrams go, under the text
+const DIAGRAM_Y: f32 = 130.0;
+
+/// How to play, a page at a time, with little diagrams.
 #[derive(Clone)]
-pub struct ModeRules {}
+pub struct ModeRules {
+    page: usize,
+    frames_elapsed: u64,
+}
 
 impl ModeRules {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            page: 0,
+            frames_elapsed: 0,
+        }
+    }
+
+    /// Go to this page, starting its diagram over
+    fn turn_to(&mut self, page: usize) {
+        if page != self.page {
+            self.page = page;
+            self.frames_elapsed = 0;
+        }
+    }
+}
+
+/// Starts a little sandbox run to practice in, then comes back here
+fn try_button() -> Button {
+    Button::new(WIDTH - 120.0, HEIGHT - 24.0, 44.0, 16.0, "TRY IT")
+}
+
+/// Goes through the rules step by step in a little run, then comes back here
+fn tutorial_button() -> Button {
+    Button::new(WIDTH - 72.0, HEIGHT - 24.0, 64.0, 16.0, "TUTORIAL")
+}
+
+fn prev_button() -> Button {
+    Button::new(8.0, HEIGHT - 24.0, 40.0, 16.0, "PREV")
+}
+
+fn next_button() -> Button {
+    Button::new(52.0, HEIGHT - 24.0, 40.0, 16.0, "NEXT")
+}
+
+fn back_button() -> Button {
+    Button::new(96.0, HEIGHT - 24.0, 40.0, 16.0, "BACK")
+}
+
+#[derive(Clone, Copy)]
+enum Page {
+    Goal,
+    Connectors,
+    Wear,
+    Kinds,
+}
+
+const PAGES: [Page; 4] = [Page::Goal, Page::Connectors, Page::Wear, Page::Kinds];
+
+impl Page {
+    fn title(self) -> &'static str {
+        match self {
+            Page::Goal => "THE GOAL",
+            Page::Connectors => "CONNECTORS",
+            Page::Wear => "WEAR AND COLLAPSE",
+            Page::Kinds => "KINDS OF BLOCK",
+        }
+    }
+
+    fn text(self) -> &'static [&'static str] {
+        match self {
+            Page::Goal => &[
+                "BUILD DOWN INTO THE CHASM AS DEEP AS YOU CAN.",
+                "EVERYTHING HANGS OFF THE ANCHORS IN THE WALLS.",
+                "DRAG BLOCKS OFF THE CONVEYOR AND LET GO TO PLACE THEM.",
+                "SCROLL THE MOUSE WHEEL TO TURN THE BLOCK YOU'RE HOLDING.",
+            ],
+            Page::Connectors => &[
+                "BLOCKS LINK UP THROUGH THE CONNECTORS ON THEIR SIDES.",
+                "TWO CONNECTORS LINK IF THEY'RE THE SAME SHAPE",
+                "AND ONE STICKS OUT WHILE THE OTHER GOES IN.",
+                "A BLOCK HAS TO LINK TO SOMETHING TO BE PLACED.",
+            ],
+            Page::Wear => &[
+                "BLOCKS WEAR DOWN, FASTER THE MORE WEIGHT THEY HOLD UP.",
+                "WHEN ONE BREAKS, ANYTHING THAT NO LONGER LINKS BACK",
+                "TO AN ANCHOR FALLS. LINK THINGS MORE THAN ONE WAY",
+                "SO ONE BREAK DOESN'T BRING EVERYTHING DOWN.",
+            ],
+            Page::Kinds => &[
+                "SOME KINDS OF BLOCK SHOW UP DEEPER DOWN.",
+                "HEAVIER BLOCKS WEIGH ON WHAT HOLDS THEM UP,",
+                "BUT TOUGHER ONES TAKE LONGER TO WEAR OUT.",
+            ],
+        }
+    }
+
+    /// Draw this page's diagram, this far into its animation
+    fn draw_diagram(self, frame: u64, globals: &Globals) {
+        let t = (frame % DIAGRAM_LOOP) as f32 / DIAGRAM_LOOP as f32;
+        match self {
+            Page::Goal => draw_goal(globals),
+            Page::Connectors => draw_connectors(t, globals),
+            Page::Wear => draw_wear(t, globals),
+            Page::Kinds => draw_kinds(globals),
+        }
+    }
+}
+
+/// A block of this kind with these connectors
+fn block(kind: BlockKind, connectors: &[(Direction4, ConnectorShape, bool)]) -> Block {
+    let mut out = Block {
+        connectors: [None, None, None, None],
+        kind,
+        damage: 0,
+    };
+    for &(dir, shape, sticks_out) in connectors {
+        out.connectors[dir as usize] = Some(Connector { shape, sticks_out });
+    }
+    out
+}
+
+/// A chain of blocks hanging off an anchor
+fn hanging_column(len: usize) -> Vec<Block> {
+    use ConnectorShape::*;
+    use Direction4::*;
+
+    let mut column = vec![block(BlockKind::Anchor, &[(South, Round, true)])];
+    for idx in 0..len {
+        let mut connectors = vec![(North, Round, false)];
+        if idx + 1 < len {
+            connectors.push((South, Round, true));
+        }
+        column.push(block(BlockKind::Solid, &connectors));
     }
+    column
+}
 
-    pub fn update(&mut self, globals: &mut Globals) -> Transition {
-        if is_mouse_button_pressed(MouseButton::Left) {
-            Transition::Pop
+fn draw_goal(globals: &Globals) {
+    let column = hanging_column(3);
+    for (idx, block) in column.iter().enumerate() {
+        block.draw_absolute(WIDTH / 2.0, DIAGRAM_Y + idx as f32 * BLOCK_SIZE, globals);
+    }
+}
+
+/// Two pairs of blocks slide together; one pair links and the other doesn't
+fn draw_connectors(t: f32, globals: &Globals) {
+    use ConnectorShape::*;
+    use Direction4::*;
+
+    let pairs = [
+        (
+            block(BlockKind::Solid, &[(East, Round, true)]),
+            block(BlockKind::Solid, &[(West, Round, false)]),
+            WIDTH / 2.0 - 60.0,
+        ),
+        (
+            block(BlockKind::Solid, &[(East, Square, true)]),
+            block(BlockKind::Solid, &[(West, Pointy, false)]),
+            WIDTH / 2.0 + 60.0,
+        ),
+    ];
+    // slide in over the first half, then sit together
+    let gap = BLOCK_SIZE * 2.0 * (1.0 - (t * 2.0).min(1.0));
+    for (left, right, cx) in pairs.iter() {
+        let y = DIAGRAM_Y + BLOCK_SIZE;
+        left.draw_absolute(cx - BLOCK_SIZE / 2.0 - gap, y, globals);
+        right.draw_absolute(cx + BLOCK_SIZE / 2.0 + gap, y, globals);
+        if gap > 0.0 {
+            continue;
+        }
+        let links = match (
+            &left.connectors[East as usize],
+            &right.connectors[West as usize],
+        ) {
+            (Some(a), Some(b)) => a.links_with(b),
+            _ => false,
+        };
+        let (label, color) = if links {
+            ("LINKS", ui::FOREGROUND)
         } else {
-            Transition::None
+            ("NO LINK", ui::ALERT)
+        };
+        drawutils::draw_text_aligned(
+            label,
+            *cx,
+            y + BLOCK_SIZE,
+            TextAlign::Center,
+            hexcolor(color),
+            globals,
+        );
+    }
+}
+
+/// The top block of a column wears out, and everything under it falls
+fn draw_wear(t: f32, globals: &Globals) {
+    let mut column = hanging_column(3);
+    // wear over the first two thirds, then break and fall
+    let wear = (t * 1.5).min(1.0);
+    let resilience = column[1].resilience();
+    column[1].damage = (wear * resilience as f32) as u8;
+    let fall = (t * 1.5 - 1.0).max(0.0);
+    let fallen = fall * fall * 400.0;
+
+    for (idx, block) in column.iter().enumerate() {
+        let mut y = DIAGRAM_Y + idx as f32 * BLOCK_SIZE;
+        if idx >= 1 && wear >= 1.0 {
+            if idx == 1 {
+                // broken
+                continue;
+            }
+            y += fallen;
         }
+        if y < HEIGHT - 32.0 {
+            block.draw_absolute(WIDTH / 2.0, y, globals);
+        }
+    }
+}
+
+/// Every kind of block that gets placed, with how heavy and tough it is
+fn draw_kinds(globals: &Globals) {
+    let kinds = [
+        BlockKind::Scaffold,
+        BlockKind::Solid,
+        BlockKind::Girder,
+        BlockKind::Reinforced,
+        BlockKind::Explosive,
+        BlockKind::Glue,
+        BlockKind::Crane,
+    ];
+    let spacing = 42.0;
+    let left = WIDTH / 2.0 - spacing * (kinds.len() - 1) as f32 / 2.0;
+    for (idx, kind) in kinds.iter().enumerate() {
+        let cx = left + idx as f32 * spacing;
+        let block = block(kind.clone(), &[]);
+        block.draw_absolute(cx, DIAGRAM_Y, globals);
+        let y = DIAGRAM_Y + BLOCK_SIZE;
+        drawutils::draw_text_aligned(
+            kind.name(),
+            cx,
+            y,
+            TextAlign::Center,
+            hexcolor(ui::FOREGROUND),
+            globals,
+        );
+        let stats = format!("MASS {}\nTOUGH {}", block.mass(), block.resilience());
+        drawutils::draw_text_aligned(
+            &stats,
+            cx,
+            y + drawutils::LINE_HEIGHT,
+            TextAlign::Center,
+            hexcolor(ui::DIM),
+            globals,
+        );
     }
+}
+
+impl Gamemode for ModeRules {
+    fn update(&mut self, _globals: &mut Globals) -> Transition {
+        self.frames_elapsed += 1;
+        let last = PAGES.len() - 1;
+        if try_button().clicked() {
+            let sandbox = ModePlaying::new(RunConfig::new(GameType::Sandbox));
+            return Transition::Push(Box::new(sandbox));
+        } else if tutorial_button().clicked() {
+            return Transition::Push(Box::new(ModeTutorial::new()));
+        } else if back_button().clicked() || is_key_pressed(KeyCode::Escape) {
+            return Transition::Pop;
+        }
+
+        if prev_button().clicked() || is_key_pressed(KeyCode::Left) {
+            self.turn_to(self.page.saturating_sub(1));
+        } else if next_button().clicked() || is_key_pressed(KeyCode::Right) {
+            self.turn_to((self.page + 1).min(last));
+        }
+        Transition::None
+    }
+
+    fn draw(&self, globals: &Globals) {
+        clear_background(hexcolor(ui::BACKGROUND));
+        let page = PAGES[self.page];
+        let fg = hexcolor(ui::FOREGROUND);
+
+        ui::draw_centered_text(page.title(), 12.0, fg, globals);
+        for (idx, line) in page.text().iter().enumerate() {
+            ui::draw_centered_text(line, 32.0 + idx as f32 * 9.0, fg, globals);
+        }
+        page.draw_diagram(self.frames_elapsed, globals);
 
-    pub fn draw(&self, globals: &Globals) {
-        clear_background(WHITE);
-        draw_texture(globals.assets.textures.tutorial, 0.0, 0.0, WHITE);
+        let count = format!("{}/{}", self.page + 1, PAGES.len());
+        drawutils::draw_text_px(&count, 148.0, HEIGHT - 19.0, hexcolor(ui::DIM), globals);
+        prev_button().draw(false, globals);
+        next_button().draw(false, globals);
+        back_button().draw(false, globals);
+        try_button().draw(false, globals);
+        tutorial_button().draw(false, globals);
     }
 }
@@ -0,0 +1,62 @@
+use crate::{
+    drawutils::{self, hexcolor, TextAlign},
+    ui::{self, Button},
+    Gamemode, Globals, Transition,
+};
+
+use macroquad::prelude::*;
+
+/// Lifetime stats, over the title screen.
+#[derive(Clone)]
+pub struct ModeStats {
+    back: Button,
+}
+
+impl ModeStats {
+    pub fn new() -> Self {
+        Self {
+            back: Button::new(128.0, 190.0, 64.0, 16.0, "BACK"),
+        }
+    }
+}
+
+impl Gamemode for ModeStats {
+    fn update(&mut self, _globals: &mut Globals) -> Transition {
+        if self.back.clicked() || is_key_pressed(KeyCode::Escape) {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn translucent(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, globals: &Globals) {
+        let fg = hexcolor(ui::FOREGROUND);
+        let dim = hexcolor(ui::DIM);
+        let stats = &globals.stats;
+
+        ui::draw_centered_text("LIFETIME STATS", 40.0, fg, globals);
+        let (hours, minutes, seconds) = stats.play_time();
+        let rows = [
+            ("RUNS STARTED", stats.runs_started.to_string()),
+            (
+                "TIME PLAYED",
+                format!("{}:{:02}:{:02}", hours, minutes, seconds),
+            ),
+            ("DEEPEST", format!("{:.1}", stats.deepest)),
+            ("BLOCKS PLACED", stats.blocks_placed.to_string()),
+            ("BLOCKS BROKEN", stats.blocks_broken.to_string()),
+            ("COLLAPSES", stats.collapses.to_string()),
+            ("BLOCKS LOST", stats.blocks_lost.to_string()),
+        ];
+        for (idx, (label, value)) in rows.iter().enumerate() {
+            let y = 64.0 + idx as f32 * 14.0;
+            drawutils::draw_text_px(label, 80.0, y, dim, globals);
+            drawutils::draw_text_aligned(value, 240.0, y, TextAlign::Right, fg, globals);
+        }
+        self.back.draw(false, globals);
+    }
+}
@@ -1,10 +1,14 @@
-use macroquad::{
-    audio::play_sound_once,
-    prelude::{clear_background, draw_texture, WHITE},
-};
+use macroquad::prelude::{clear_background, draw_texture, WHITE};
 
 use crate::{
-    drawutils::mouse_position_pixel, Gamemode, Globals, ModePlaying, ModeRules, Transition,
+    drawutils::{self, hexcolor, mouse_position_pixel, TextAlign},
+    music::Track,
+    save::{self, PackedError},
+    seasonal,
+    ui::{self, Button},
+    GameType, Gamemode, Globals, ModeAssist, ModeDaily, ModeNameEntry, ModeOptions, ModePlaying,
+    ModeReplayPicker, ModeRules, ModeSelect, ModeStats, NameEntryPurpose, RunConfig, RunSnapshot,
+    SavePolicy, Transition,
 };
 
 #[derive(Clone)]
@@ -12,45 +16,105 @@ pub struct ModeTitle {
     play_highlighted: bool,
     rules_highlighted: bool,
 
-    play_click: bool,
+    /// A run that was saved and quit, waiting to be resumed.
+    /// Hardcore runs come first, since those can't be put off forever.
+    saved_run: Option<RunSnapshot>,
+    /// Whether there's a saved run that this version of the game can't pick up
+    outdated_run: bool,
 }
 
 impl ModeTitle {
     pub fn new() -> Self {
+        let mut outdated_run = false;
+        let saved_run = [SavePolicy::Hardcore, SavePolicy::Suspend]
+            .iter()
+            .filter_map(|policy| policy.save_name())
+            .find_map(|name| match RunSnapshot::load(name) {
+                Ok(it) => Some(it),
+                Err(PackedError::Outdated(_)) => {
+                    outdated_run = true;
+                    None
+                }
+                Err(_) => None,
+            });
         Self {
             play_highlighted: false,
             rules_highlighted: false,
-            play_click: false,
+            saved_run,
+            outdated_run,
         }
     }
+}
 
-    pub fn update(&mut self, globals: &mut Globals) -> Transition {
+impl Gamemode for ModeTitle {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
         use macroquad::prelude::*;
 
-        self.play_click = false;
+        let mut play_click = false;
 
         let (mx, my) = mouse_position_pixel();
 
         let play_rect = Rect::new(76.0, 121.0, 67.0, 23.0);
         let hovering_play = play_rect.contains(vec2(mx, my));
         if !self.play_highlighted && hovering_play {
-            self.play_click = true;
+            play_click = true;
         }
         self.play_highlighted = hovering_play;
 
         let rules_rect = Rect::new(76.0, 147.0, 83.0, 23.0);
         let hovering_rules = rules_rect.contains(vec2(mx, my));
         if !self.rules_highlighted && hovering_rules {
-            self.play_click = true;
+            play_click = true;
         }
         self.rules_highlighted = hovering_rules;
+        // this happens here instead of in draw, since the title gets drawn under other modes
+        if let (true, Some(click)) = (play_click, globals.assets.sounds.rotate) {
+            globals.audio.cue(click, 1.0, 0.0);
+        }
 
         if is_mouse_button_pressed(MouseButton::Left) {
             macroquad::rand::srand((mx.to_bits() as u64) + ((my.to_bits() as u64) << 32));
             if self.play_highlighted {
-                Transition::Swap(Gamemode::Playing(ModePlaying::new()))
+                Transition::Swap(Box::new(ModePlaying::new(
+                    RunConfig::new(GameType::Classic).with_assists(&globals.settings.assist),
+                )))
+            } else if modes_button().hovered() {
+                Transition::Swap(Box::new(ModeSelect::new()))
+            } else if daily_button().hovered() {
+                Transition::Swap(Box::new(ModeDaily::new()))
+            } else if let (Some(snapshot), true) = (&self.saved_run, continue_button().hovered()) {
+                // You only get to do this once
+                if let Some(save_name) = snapshot.config.save_policy.save_name() {
+                    save::delete(save_name);
+                }
+                let mut snapshot = snapshot.clone();
+                snapshot.resumed = true;
+                Transition::Swap(Box::new(ModePlaying::from_snapshot(snapshot)))
             } else if self.rules_highlighted {
-                Transition::Push(Gamemode::Rules(ModeRules::new()))
+                Transition::Push(Box::new(ModeRules::new()))
+            } else if camera_button(globals).hovered() {
+                globals.settings.camera = globals.settings.camera.next();
+                globals.settings.save();
+                Transition::None
+            } else if options_button().hovered() {
+                Transition::Push(Box::new(ModeOptions::new()))
+            } else if assist_button().hovered() {
+                Transition::Push(Box::new(ModeAssist::new()))
+            } else if replays_button().hovered() {
+                Transition::Push(Box::new(ModeReplayPicker::new()))
+            } else if stats_button().hovered() {
+                Transition::Push(Box::new(ModeStats::new()))
+            } else if seed_button().hovered() {
+                // this swaps instead of pushing, since it goes straight into a run
+                Transition::Swap(Box::new(ModeNameEntry::new(
+                    NameEntryPurpose::Seed,
+                    globals,
+                )))
+            } else if profile_button(globals).hovered() {
+                Transition::Push(Box::new(ModeNameEntry::new(
+                    NameEntryPurpose::ProfileName,
+                    globals,
+                )))
             } else {
                 Transition::None
             }
@@ -59,12 +123,109 @@ impl ModeTitle {
         }
     }
 
-    pub fn draw(&self, globals: &Globals) {
+    fn draw(&self, globals: &Globals) {
         clear_background(WHITE);
         draw_texture(globals.assets.textures.title_screen, 0.0, 0.0, WHITE);
-
-        if self.play_click {
-            play_sound_once(globals.assets.sounds.rotate);
+        profile_button(globals).draw(false, globals);
+        modes_button().draw(false, globals);
+        assist_button().draw(globals.settings.assist.is_active(), globals);
+        replays_button().draw(false, globals);
+        stats_button().draw(false, globals);
+        camera_button(globals).draw(false, globals);
+        let table = globals.high_scores.table(GameType::Classic);
+        ui::draw_high_scores(4.0, 121.0, &table, None, globals);
+        options_button().draw(false, globals);
+        seed_button().draw(false, globals);
+        daily_button().draw(false, globals);
+        draw_daily_bests(globals);
+        if self.saved_run.is_some() {
+            continue_button().draw(false, globals);
+        } else if self.outdated_run {
+            // where the continue button would be
+            drawutils::draw_text_aligned(
+                "SAVED RUN IS\nFROM AN OLDER\nVERSION",
+                208.0,
+                175.0,
+                TextAlign::Center,
+                hexcolor(ui::DIM),
+                globals,
+            );
         }
     }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Title)
+    }
+}
+
+/// Shows who's playing; click it to change your name.
+fn profile_button(globals: &Globals) -> Button {
+    let label = format!("PLAYER: {}", globals.profile.name);
+    let width = drawutils::text_width_px(&label) + 8.0;
+    Button::new(20.0, 226.0, width, 11.0, label)
+}
+
+/// All the other ways to play.
+fn modes_button() -> Button {
+    Button::new(176.0, 121.0, 64.0, 23.0, "MODES")
+}
+
+/// Make the game a bit easier.
+fn assist_button() -> Button {
+    Button::new(76.0, 173.0, 64.0, 23.0, "ASSIST")
+}
+
+/// Watch saved runs back.
+fn replays_button() -> Button {
+    Button::new(176.0, 147.0, 64.0, 23.0, "REPLAYS")
+}
+
+/// Lifetime stats, over every run.
+fn stats_button() -> Button {
+    Button::new(248.0, 173.0, 64.0, 23.0, "STATS")
+}
+
+/// Cycle through what the camera does on its own during runs.
+fn camera_button(globals: &Globals) -> Button {
+    let label = format!("CAM: {}", globals.settings.camera.label());
+    Button::new(176.0, 199.0, 64.0, 23.0, label)
+}
+
+/// Volume, fullscreen and such.
+fn options_button() -> Button {
+    Button::new(76.0, 199.0, 64.0, 23.0, "OPTIONS")
+}
+
+/// Play a classic run from a seed, to try the same one again.
+fn seed_button() -> Button {
+    Button::new(176.0, 226.0, 64.0, 11.0, "SEED RUN")
+}
+
+/// Today's daily challenge.
+fn daily_button() -> Button {
+    Button::new(248.0, 121.0, 64.0, 23.0, "DAILY")
+}
+
+/// How today's and yesterday's daily challenges went, under the button for it.
+fn draw_daily_bests(globals: &Globals) {
+    use macroquad::prelude::*;
+
+    let rect = daily_button().rect;
+    let panel = Rect::new(rect.x, rect.bottom() + 2.0, rect.w, 22.0);
+    ui::draw_panel(panel);
+    let today = seasonal::today();
+    for (idx, (label, day)) in [("TODAY", today), ("YDAY", today - 1)].iter().enumerate() {
+        let depth = match globals.daily_bests.best(*day) {
+            Some(best) => (best.depth.round() as i32).to_string(),
+            None => "---".to_owned(),
+        };
+        let line = format!("{:<6}{:>4}", label, depth);
+        let y = panel.y + 4.0 + idx as f32 * 8.0;
+        drawutils::draw_text_px(&line, panel.x + 4.0, y, hexcolor(ui::FOREGROUND), globals);
+    }
+}
+
+/// Resume a suspended run.
+fn continue_button() -> Button {
+    Button::new(176.0, 173.0, 64.0, 23.0, "CONTINUE")
 }
@@ -0,0 +1,251 @@
+use crate::{
+    cursor::CursorIcon,
+    drawutils::{self, hexcolor},
+    modes::playing::PlayEvent,
+    music::Track,
+    ui::{self, Button},
+    GameType, Gamemode, Globals, ModePlaying, ModeTitle, RunConfig, Transition, HEIGHT,
+};
+
+use macroquad::prelude::*;
+
+/// The tutorial run always gets the same blocks, so the steps always make sense
+const TUTORIAL_SEED: u64 = 0x7475_746f_7269_616c;
+const PANEL_WIDTH: f32 = 240.0;
+const PANEL_LINE_HEIGHT: f32 = 7.0;
+const HIGHLIGHT_COLOR: u32 = ui::ALERT;
+
+/// Walks a new player through a little run, one thing at a time.
+///
+/// Some steps wait for the player to do something in the run; the rest explain
+/// something and wait for NEXT. The run holds still while they're reading.
+#[derive(Clone)]
+pub struct ModeTutorial {
+    playing: ModePlaying,
+    step: Step,
+    /// If this is part of the onboarding flow, go to the title afterwards
+    /// instead of popping back to it.
+    first_launch: bool,
+    frames_elapsed: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Step {
+    PickUp,
+    Rotate,
+    Place,
+    Anchors,
+    Collapse,
+    Done,
+}
+
+impl Step {
+    fn text(self) -> &'static [&'static str] {
+        match self {
+            Step::PickUp => &[
+                "BLOCKS COME IN ON THE CONVEYOR.",
+                "CLICK AND HOLD ONE TO PICK IT UP.",
+            ],
+            Step::Rotate => &[
+                "THE LINES ON ITS SIDES ARE CONNECTORS.",
+                "SCROLL THE MOUSE WHEEL TO TURN IT.",
+            ],
+            Step::Place => &[
+                "LET GO NEXT TO AN ANCHOR TO PLACE IT.",
+                "CONNECTORS HAVE TO MATCH UP TO LINK.",
+            ],
+            Step::Anchors => &[
+                "ANCHORS ARE BOLTED INTO THE ROCK.",
+                "EVERYTHING HANGS OFF THEM THROUGH ITS LINKS.",
+                "BLOCKS THAT AREN'T LINKED BACK TO ONE FALL.",
+            ],
+            Step::Collapse => &[
+                "BLOCKS WEAR DOWN UNDER THE WEIGHT THEY HOLD.",
+                "WHEN ONE BREAKS, EVERYTHING IT HELD UP FALLS.",
+                "LINK THINGS MORE THAN ONE WAY TO SPREAD THE LOAD.",
+            ],
+            Step::Done => &[
+                "THAT'S ALL THERE IS TO IT.",
+                "BUILD AS DEEP AS YOU CAN BEFORE IT COMES DOWN.",
+            ],
+        }
+    }
+
+    fn next(self) -> Option<Step> {
+        match self {
+            Step::PickUp => Some(Step::Rotate),
+            Step::Rotate => Some(Step::Place),
+            Step::Place => Some(Step::Anchors),
+            Step::Anchors => Some(Step::Collapse),
+            Step::Collapse => Some(Step::Done),
+            Step::Done => None,
+        }
+    }
+
+    /// Whether this step waits for NEXT, instead of for something to happen in the run
+    fn explains(self) -> bool {
+        matches!(self, Step::Anchors | Step::Collapse | Step::Done)
+    }
+
+    /// Whether this just happened in the run, for steps that wait for it
+    fn done_by(self, event: &PlayEvent) -> bool {
+        matches!(
+            (self, event),
+            (Step::PickUp, PlayEvent::PickedUp)
+                | (Step::Rotate, PlayEvent::Rotated)
+                | (Step::Place, PlayEvent::BlockPlaced { .. })
+        )
+    }
+
+    /// The part of the screen this step is about, if there is one
+    fn highlight(self, playing: &ModePlaying) -> Option<Rect> {
+        match self {
+            Step::PickUp => Some(playing.conveyor_area()),
+            Step::Place | Step::Anchors => playing.anchor_area(),
+            _ => None,
+        }
+    }
+}
+
+impl ModeTutorial {
+    pub fn new() -> Self {
+        Self {
+            playing: tutorial_run(),
+            step: Step::PickUp,
+            first_launch: false,
+            frames_elapsed: 0,
+        }
+    }
+
+    /// The tutorial as the last step of onboarding a new player
+    pub fn first_launch() -> Self {
+        Self {
+            first_launch: true,
+            ..Self::new()
+        }
+    }
+
+    fn panel_rect(&self) -> Rect {
+        // with a row under the text for the buttons
+        let h = self.step.text().len() as f32 * PANEL_LINE_HEIGHT + 25.0;
+        Rect::new(4.0, HEIGHT - h - 4.0, PANEL_WIDTH, h)
+    }
+
+    fn next_button(&self) -> Button {
+        let panel = self.panel_rect();
+        let label = if self.step == Step::Done {
+            "DONE"
+        } else {
+            "NEXT"
+        };
+        Button::new(
+            panel.right() - 44.0,
+            panel.bottom() - 17.0,
+            40.0,
+            13.0,
+            label,
+        )
+    }
+
+    fn finish(&self, globals: &mut Globals) -> Transition {
+        if self.first_launch {
+            globals.profile.first_launch = false;
+            globals.profile.save();
+            Transition::Swap(Box::new(ModeTitle::new()))
+        } else {
+            Transition::Pop
+        }
+    }
+}
+
+fn tutorial_run() -> ModePlaying {
+    ModePlaying::with_seed(RunConfig::new(GameType::Sandbox), TUTORIAL_SEED)
+}
+
+impl Gamemode for ModeTutorial {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        self.frames_elapsed += 1;
+        if is_key_pressed(KeyCode::Escape) {
+            return self.finish(globals);
+        }
+
+        if self.step.explains() {
+            self.playing.idle();
+            if self.next_button().clicked() {
+                match self.step.next() {
+                    Some(next) => self.step = next,
+                    None => return self.finish(globals),
+                }
+            }
+            return Transition::None;
+        }
+
+        // Whatever the run wants to go to next, the tutorial isn't over
+        if !matches!(self.playing.play_frame(globals), Transition::None) {
+            self.playing = tutorial_run();
+        }
+        let step = self.step;
+        if self
+            .playing
+            .events()
+            .iter()
+            .any(|event| step.done_by(event))
+        {
+            if let Some(next) = step.next() {
+                self.step = next;
+            }
+        }
+        Transition::None
+    }
+
+    fn draw(&self, globals: &Globals) {
+        self.playing.draw_screen(globals);
+
+        if let Some(area) = self.step.highlight(&self.playing) {
+            // pulses, so it catches the eye
+            let grow = ((self.frames_elapsed as f32 / 8.0).sin() + 1.0) * 1.5 + 2.0;
+            draw_rectangle_lines(
+                area.x - grow,
+                area.y - grow,
+                area.w + grow * 2.0,
+                area.h + grow * 2.0,
+                2.0,
+                hexcolor(HIGHLIGHT_COLOR),
+            );
+        }
+
+        let panel = self.panel_rect();
+        ui::draw_panel(panel);
+        for (idx, line) in self.step.text().iter().enumerate() {
+            drawutils::draw_text_px(
+                line,
+                panel.x + 4.0,
+                panel.y + 4.0 + idx as f32 * PANEL_LINE_HEIGHT,
+                hexcolor(ui::FOREGROUND),
+                globals,
+            );
+        }
+        if self.step.explains() {
+            self.next_button().draw(false, globals);
+        }
+        drawutils::draw_text_px(
+            "ESC: SKIP",
+            panel.x + 4.0,
+            panel.bottom() - 4.0 - PANEL_LINE_HEIGHT,
+            hexcolor(ui::DIM),
+            globals,
+        );
+    }
+
+    fn cursor_icon(&self, mx: f32, my: f32) -> CursorIcon {
+        if self.step.explains() {
+            CursorIcon::Pointer
+        } else {
+            self.playing.cursor_icon(mx, my)
+        }
+    }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Playing)
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{
+    cursor::CursorIcon,
+    drawutils::{self, hexcolor, TextAlign},
+    modes::playing::{player_color, player_name, InputSource, RaceFinish, RACE_DEPTH},
+    music::Track,
+    ui::{self, Button},
+    Gamemode, Globals, ModePlaying, ModeTitle, RunConfig, Transition, HEIGHT, WIDTH,
+};
+
+use macroquad::prelude::{clear_background, draw_line, is_key_pressed, vec2, KeyCode, Rect};
+use quad_rand::compat::QuadRand;
+use rand::Rng;
+
+/// Two players race side by side, each down their own copy of the same chasm.
+/// The first player has the mouse and the left side; the second has the keyboard and the right.
+#[derive(Clone)]
+pub struct ModeVersus {
+    players: [ModePlaying; 2],
+    /// For a rematch
+    config: RunConfig,
+    /// Set once somebody's side ends
+    result: Option<RaceResult>,
+    rematch: Button,
+    quit: Button,
+}
+
+/// How a race turned out.
+#[derive(Clone, Copy)]
+struct RaceResult {
+    /// Nobody wins if both sides end the same way on the same frame
+    winner: Option<usize>,
+    finishes: [Option<RaceFinish>; 2],
+}
+
+impl ModeVersus {
+    pub fn new(config: RunConfig) -> Self {
+        // the same chasm and the same blocks for both, to be fair
+        let seed = QuadRand.gen();
+        let sources = [InputSource::Mouse, InputSource::Keyboard];
+        let players = [0, 1].map(|player| {
+            ModePlaying::with_seed(config.clone(), seed)
+                .with_screen(side(player))
+                .with_input(sources[player])
+        });
+        Self {
+            players,
+            config,
+            result: None,
+            rematch: Button::new(96.0, 208.0, 60.0, 19.0, "REMATCH"),
+            quit: Button::new(164.0, 208.0, 60.0, 19.0, "QUIT"),
+        }
+    }
+}
+
+/// The part of the canvas this player's side goes in
+fn side(player: usize) -> Rect {
+    Rect::new(player as f32 * WIDTH / 2.0, 0.0, WIDTH / 2.0, HEIGHT)
+}
+
+/// How well a side is doing, to see who's ahead once one ends.
+/// Still going beats falling down, and getting there beats both.
+fn standing(finish: Option<RaceFinish>) -> u8 {
+    match finish {
+        Some(RaceFinish::Reached) => 2,
+        None => 1,
+        Some(RaceFinish::Collapsed(_)) | Some(RaceFinish::LostAnchors) => 0,
+    }
+}
+
+/// What to say about how this player's side ended
+fn describe(player: usize, finish: RaceFinish) -> String {
+    let name = player_name(player);
+    match finish {
+        RaceFinish::Reached => format!("{} REACHED DEPTH {}", name, RACE_DEPTH),
+        RaceFinish::Collapsed(blocks) => format!("{} DROPPED {} BLOCKS", name, blocks),
+        RaceFinish::LostAnchors => format!("{} LOST EVERY ANCHOR", name),
+    }
+}
+
+impl Gamemode for ModeVersus {
+    fn update(&mut self, globals: &mut Globals) -> Transition {
+        if self.result.is_some() {
+            return if self.rematch.clicked() {
+                Transition::Swap(Box::new(ModeVersus::new(self.config.clone())))
+            } else if self.quit.clicked() || is_key_pressed(KeyCode::Escape) {
+                Transition::Swap(Box::new(ModeTitle::new()))
+            } else {
+                Transition::None
+            };
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            return Transition::Swap(Box::new(ModeTitle::new()));
+        }
+
+        // Each side would go to its results when it ends; the race reads how it ended instead.
+        // Both go before checking, so ending on the same frame is a tie.
+        for player in self.players.iter_mut() {
+            player.play_frame(globals);
+        }
+        let finishes = [self.players[0].race_finish(), self.players[1].race_finish()];
+        if finishes.iter().any(Option::is_some) {
+            let (left, right) = (standing(finishes[0]), standing(finishes[1]));
+            self.result = Some(RaceResult {
+                winner: match left.cmp(&right) {
+                    std::cmp::Ordering::Greater => Some(0),
+                    std::cmp::Ordering::Less => Some(1),
+                    std::cmp::Ordering::Equal => None,
+                },
+                finishes,
+            });
+        }
+        Transition::None
+    }
+
+    fn draw(&self, globals: &Globals) {
+        clear_background(hexcolor(ui::BACKGROUND));
+
+        let controls = ["MOUSE", "KEYBOARD"];
+        for (player, mode) in self.players.iter().enumerate() {
+            mode.draw_screen(globals);
+
+            let area = side(player);
+            let color = hexcolor(player_color(player));
+            let label = format!("{} - {}", player_name(player), controls[player]);
+            let cx = area.x + area.w / 2.0;
+            drawutils::draw_text_aligned(&label, cx, 44.0, TextAlign::Center, color, globals);
+            let depth = format!(
+                "DEPTH {} OF {}",
+                (mode.depth() as u32).min(RACE_DEPTH),
+                RACE_DEPTH
+            );
+            drawutils::draw_text_aligned(
+                &depth,
+                cx,
+                188.0,
+                TextAlign::Center,
+                hexcolor(ui::DIM),
+                globals,
+            );
+        }
+        draw_line(
+            WIDTH / 2.0,
+            0.0,
+            WIDTH / 2.0,
+            HEIGHT,
+            1.0,
+            hexcolor(ui::DIM),
+        );
+
+        if let Some(result) = self.result {
+            ui::draw_dim_overlay();
+            match result.winner {
+                Some(winner) => ui::draw_centered_text(
+                    &format!("{} WINS", player_name(winner)),
+                    96.0,
+                    hexcolor(player_color(winner)),
+                    globals,
+                ),
+                None => ui::draw_centered_text("DRAW", 96.0, hexcolor(ui::FOREGROUND), globals),
+            }
+            for (idx, (player, finish)) in result
+                .finishes
+                .iter()
+                .enumerate()
+                .filter_map(|(player, finish)| finish.map(|finish| (player, finish)))
+                .enumerate()
+            {
+                ui::draw_centered_text(
+                    &describe(player, finish),
+                    110.0 + idx as f32 * 8.0,
+                    hexcolor(ui::DIM),
+                    globals,
+                );
+            }
+            self.rematch.draw(false, globals);
+            self.quit.draw(false, globals);
+        }
+    }
+
+    fn cursor_icon(&self, mx: f32, my: f32) -> CursorIcon {
+        // the keyboard player's side has no use for the mouse
+        if self.result.is_none() && side(0).contains(vec2(mx, my)) {
+            self.players[0].cursor_icon(mx, my)
+        } else {
+            CursorIcon::Pointer
+        }
+    }
+
+    fn screen_shake(&self) -> (f32, f32) {
+        if self.result.is_some() {
+            return (0.0, 0.0);
+        }
+        // each side is drawn at half size, so it shakes half as hard
+        let (lx, ly) = self.players[0].screen_shake();
+        let (rx, ry) = self.players[1].screen_shake();
+        ((lx + rx) / 2.0, (ly + ry) / 2.0)
+    }
+
+    fn music(&self) -> Option<Track> {
+        if self.result.is_some() {
+            Some(Track::Denoument)
+        } else {
+            Some(Track::Playing)
+        }
+    }
+
+    fn claims_key(&self, key: KeyCode) -> bool {
+        self.players.iter().any(|player| player.claims_key(key))
+    }
+}
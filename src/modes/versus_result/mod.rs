@@ -0,0 +1,108 @@
+use crate::{
+    drawutils::{self, hexcolor},
+    modes::playing::{player_color, player_name, Versus, VersusOutcome},
+    music::Track,
+    score::RunResult,
+    ui::{self, Button},
+    Gamemode, Globals, ModePlaying, ModeTitle, RunConfig, Transition,
+};
+
+use macroquad::prelude::*;
+
+/// Who won a versus match, and how the players did.
+#[derive(Clone)]
+pub struct ModeVersusResult {
+    versus: Versus,
+    result: RunResult,
+    /// For a rematch
+    config: RunConfig,
+    rematch: Button,
+    quit: Button,
+}
+
+impl ModeVersusResult {
+    pub fn new(versus: Versus, result: RunResult, config: RunConfig) -> Self {
+        Self {
+            versus,
+            result,
+            config,
+            rematch: Button::new(96.0, 180.0, 60.0, 19.0, "REMATCH"),
+            quit: Button::new(164.0, 180.0, 60.0, 19.0, "QUIT"),
+        }
+    }
+}
+
+impl Gamemode for ModeVersusResult {
+    fn update(&mut self, _globals: &mut Globals) -> Transition {
+        if self.rematch.clicked() {
+            Transition::Swap(Box::new(ModePlaying::new(self.config.clone())))
+        } else if self.quit.clicked() || is_key_pressed(KeyCode::Escape) {
+            Transition::Swap(Box::new(ModeTitle::new()))
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&self, globals: &Globals) {
+        clear_background(hexcolor(ui::BACKGROUND));
+        let fg = hexcolor(ui::FOREGROUND);
+        let dim = hexcolor(ui::DIM);
+
+        let (headline, reason) = match self.versus.outcome() {
+            VersusOutcome::Collapse(collapse) => (
+                Some(1 - collapse.player),
+                format!(
+                    "{} COLLAPSED {} BLOCKS",
+                    player_name(collapse.player),
+                    collapse.blocks
+                ),
+            ),
+            VersusOutcome::MassLost(loser) => (
+                Some(1 - loser),
+                format!("{} LOST MORE MASS", player_name(loser)),
+            ),
+            VersusOutcome::Draw => (None, "NOBODY LOST ANY MORE THAN THE OTHER".to_owned()),
+        };
+        match headline {
+            Some(winner) => ui::draw_centered_text(
+                &format!("{} WINS", player_name(winner)),
+                40.0,
+                hexcolor(player_color(winner)),
+                globals,
+            ),
+            None => ui::draw_centered_text("DRAW", 40.0, fg, globals),
+        }
+        ui::draw_centered_text(&reason, 54.0, dim, globals);
+
+        for player in 0..2 {
+            let x = 72.0 + player as f32 * 100.0;
+            let lines = [
+                format!("PLACED {}", self.versus.blocks_placed[player]),
+                format!("MASS LOST {}", self.versus.mass_lost[player].round() as i32),
+            ];
+            drawutils::draw_text_px(
+                &player_name(player),
+                x,
+                88.0,
+                hexcolor(player_color(player)),
+                globals,
+            );
+            for (idx, line) in lines.iter().enumerate() {
+                drawutils::draw_text_px(line, x, 100.0 + idx as f32 * 8.0, fg, globals);
+            }
+        }
+        ui::draw_centered_text(
+            &format!("FINAL DEPTH {}", self.result.depth.round() as i32),
+            140.0,
+            dim,
+            globals,
+        );
+
+        self.rematch.draw(false, globals);
+        self.quit.draw(false, globals);
+    }
+
+    fn music(&self) -> Option<Track> {
+        Some(Track::Denoument)
+    }
+}
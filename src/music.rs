@@ -0,0 +1,108 @@
+//! Which music is playing, and fading from one track into the next.
+//!
+//! Modes say which track goes with them with [`Gamemode::music`](crate::Gamemode::music).
+//! Every frame the main loop tells the [`MusicManager`] what the top mode wants,
+//! so the music changes over whenever the mode does, and stops when nothing wants any.
+
+use crate::assets::{Music, Sounds};
+
+use macroquad::audio::PlaySoundParams;
+
+/// How many frames it takes one track to fade out, and the next to fade in
+const CROSSFADE_FRAMES: f32 = 45.0;
+
+/// A piece of music that goes with some part of the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Track {
+    /// The logo and the title screen
+    Title,
+    /// During a run
+    Playing,
+    /// Looking back over a finished run.
+    /// Until it gets a piece of its own, this is the title jingle again.
+    Denoument,
+}
+
+impl Track {
+    fn music(self, sounds: &Sounds) -> &Music {
+        match self {
+            Track::Title | Track::Denoument => &sounds.title_jingle,
+            Track::Playing => &sounds.engineer_gaming,
+        }
+    }
+
+    /// Whether it goes around again when it's over, instead of leaving it quiet
+    fn looped(self) -> bool {
+        match self {
+            Track::Title | Track::Denoument => false,
+            Track::Playing => true,
+        }
+    }
+}
+
+/// What's playing, and what's fading out.
+#[derive(Clone, Default)]
+pub struct MusicManager {
+    /// What should be playing, as of the last `play` or `stop`
+    wanted: Option<Track>,
+    /// What's playing, and how far it's faded in, from 0 to 1
+    current: Option<(Track, f32)>,
+    /// What's on its way out, and how loud it still is, from 0 to 1
+    fading: Option<(Track, f32)>,
+}
+
+impl MusicManager {
+    /// Fade over to this track, if it isn't the one playing already.
+    pub fn play(&mut self, track: Track) {
+        self.wanted = Some(track);
+    }
+
+    /// Fade out whatever's playing.
+    pub fn stop(&mut self) {
+        self.wanted = None;
+    }
+
+    /// Start and stop tracks, and move the fades along. Call this once a frame.
+    pub fn update(&mut self, sounds: &Sounds, gain: f32) {
+        let playing = self.current.map(|(track, _)| track);
+        if self.wanted != playing {
+            // anything still fading from before gets cut off
+            if let Some((old, _)) = self.fading.take() {
+                old.music(sounds).stop();
+            }
+            self.fading = self.current.take();
+            if let Some(track) = self.wanted {
+                let music = track.music(sounds);
+                match self.fading {
+                    // tracks that share a recording carry on instead of starting over
+                    Some((old, level)) if old.music(sounds).same_recording(music) => {
+                        self.fading = None;
+                        self.current = Some((track, level));
+                    }
+                    _ => {
+                        music.play(PlaySoundParams {
+                            looped: track.looped(),
+                            volume: 0.0,
+                        });
+                        self.current = Some((track, 0.0));
+                    }
+                }
+            }
+        }
+
+        let step = CROSSFADE_FRAMES.recip();
+        if let Some((track, level)) = &mut self.current {
+            *level = (*level + step).min(1.0);
+            track.music(sounds).set_volume(*level * gain);
+        }
+        if let Some((track, level)) = &mut self.fading {
+            *level -= step;
+            if *level > 0.0 {
+                track.music(sounds).set_volume(*level * gain);
+            } else {
+                track.music(sounds).stop();
+                self.fading = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Little cosmetic bits that drift around the screen.
+//!
+//! These only ever use the cosmetic randomness, so they can't change how a run plays out.
+
+use macroquad::prelude::*;
+
+#[derive(Clone, Default)]
+pub struct Particles {
+    particles: Vec<Particle>,
+}
+
+#[derive(Clone)]
+struct Particle {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    /// How much it sways side to side, and how far along its sway it is
+    sway: f32,
+    phase: f32,
+    size: f32,
+    color: Color,
+    frames_left: u32,
+}
+
+impl Particles {
+    pub fn update(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.phase += 0.05;
+            particle.x += particle.vx + particle.phase.sin() * particle.sway;
+            particle.y += particle.vy;
+            particle.frames_left = particle.frames_left.saturating_sub(1);
+        }
+        self.particles.retain(|particle| particle.frames_left > 0);
+    }
+
+    pub fn draw(&self) {
+        for particle in self.particles.iter() {
+            draw_rectangle(
+                particle.x.round(),
+                particle.y.round(),
+                particle.size,
+                particle.size,
+                particle.color,
+            );
+        }
+    }
+
+    /// Fling a few bits out in every direction from this spot.
+    pub fn burst(&mut self, x: f32, y: f32, color: Color, count: usize) {
+        use macroquad::rand::gen_range;
+
+        for _ in 0..count {
+            let angle = gen_range(0.0, std::f32::consts::TAU);
+            let speed = gen_range(0.3, 0.8);
+            self.particles.push(Particle {
+                x,
+                y,
+                vx: angle.cos() * speed,
+                vy: angle.sin() * speed,
+                sway: 0.0,
+                phase: 0.0,
+                size: 1.0,
+                color,
+                frames_left: gen_range(10, 20),
+            });
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+}
+
+/// Something that keeps making particles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Emitter {
+    /// Flakes drifting down from the top of the screen
+    Snow,
+    /// Leaves tumbling down from the top of the screen
+    Leaves,
+}
+
+impl Emitter {
+    /// Make this frame's particles, if any.
+    pub fn emit(self, particles: &mut Particles) {
+        use macroquad::rand::gen_range;
+
+        let (chance, color, vy, sway, size) = match self {
+            Emitter::Snow => (0.3, Color::new(1.0, 1.0, 1.0, 0.8), 0.4, 0.2, 1.0),
+            Emitter::Leaves => {
+                let colors = [0xd77b3bff, 0xa8532eff, 0xe3b346ff];
+                let color = crate::drawutils::hexcolor(colors[gen_range(0, colors.len())]);
+                (0.05, color, 0.6, 0.6, 2.0)
+            }
+        };
+        if gen_range(0.0, 1.0) > chance {
+            return;
+        }
+        particles.particles.push(Particle {
+            x: gen_range(0.0, crate::WIDTH),
+            y: -size,
+            vx: gen_range(-0.1, 0.1),
+            vy: vy * gen_range(0.7, 1.3),
+            sway,
+            phase: gen_range(0.0, std::f32::consts::TAU),
+            size,
+            color,
+            frames_left: ((crate::HEIGHT + size) / vy * 1.5) as u32,
+        });
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use crate::save;
+
+/// Information about the player that sticks around between sessions.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    /// Whether the player has yet to go through the onboarding flow:
+    /// from the logo to naming their profile, then the tutorial, then the title.
+    /// Finishing or skipping the tutorial clears it.
+    pub first_launch: bool,
+    /// How many campaign levels have been cleared, and so which is the furthest unlocked
+    pub levels_cleared: usize,
+}
+
+impl Profile {
+    const SAVE_NAME: &'static str = "profile.toml";
+
+    pub fn load() -> Self {
+        save::load_toml(Self::SAVE_NAME)
+    }
+
+    pub fn save(&self) {
+        save::store_toml(Self::SAVE_NAME, self);
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            first_launch: true,
+            levels_cleared: 0,
+        }
+    }
+}
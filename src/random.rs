@@ -1,4 +1,16 @@
-use rand::RngCore;
+//! Randomness.
+//!
+//! There are two streams of random numbers:
+//! - Each run has its own [`GameRng`], for anything that changes how the run plays out.
+//!   It's seeded when the run starts and saved along with the run.
+//! - Anything cosmetic uses the global `quad_rand` generator (through `QuadRand`).
+//!
+//! Keeping them apart means visual stuff can roll all the dice it wants without
+//! changing what happens in a run with a given seed.
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
 fn rng_is_hard(buf: &mut [u8]) -> Result<(), getrandom::Error> {
     quad_rand::compat::QuadRand.fill_bytes(buf);
@@ -6,3 +18,86 @@ fn rng_is_hard(buf: &mut [u8]) -> Result<(), getrandom::Error> {
 }
 
 getrandom::register_custom_getrandom!(rng_is_hard);
+
+/// The random number stream for gameplay.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "GameRngState", into = "GameRngState")]
+pub struct GameRng {
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed this stream started from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// A seed for one thing that's generated from the run's seed, like where the water is.
+///
+/// Each of those things mixes its own salt in, so they don't all come out of the same
+/// stream of numbers and line up with each other, or with the run's own [`GameRng`].
+pub fn derived_seed(seed: u64, salt: u64) -> u64 {
+    seed ^ salt
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// `ChaCha8Rng` can't be serialized directly, but it can be rebuilt from
+/// its seed and how far along the stream it is.
+#[derive(Serialize, Deserialize)]
+struct GameRngState {
+    seed: u64,
+    /// The position in the stream, split into the high and low halves
+    /// because JSON can't hold a whole `u128`
+    word_pos: (u64, u64),
+}
+
+impl From<GameRng> for GameRngState {
+    fn from(rng: GameRng) -> Self {
+        let word_pos = rng.rng.get_word_pos();
+        Self {
+            seed: rng.seed,
+            word_pos: ((word_pos >> 64) as u64, word_pos as u64),
+        }
+    }
+}
+
+impl From<GameRngState> for GameRng {
+    fn from(state: GameRngState) -> Self {
+        let mut rng = GameRng::new(state.seed);
+        let (high, low) = state.word_pos;
+        rng.rng.set_word_pos(((high as u128) << 64) | low as u128);
+        rng
+    }
+}
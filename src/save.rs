@@ -0,0 +1,478 @@
+//! Persisting things between sessions.
+//!
+//! On desktop everything lives in files in the platform config directory.
+//! On wasm it lives in the browser's localStorage, through the little plugin
+//! in `release/esa_storage.js`. Exports, like photos, get downloaded there instead.
+//!
+//! Small things like settings are TOML, so people can poke at them.
+//! Big things like runs and replays are packed: bincode, then deflated,
+//! behind a version number that has to match for them to load.
+//! Pictures are PNGs.
+//!
+//! Writes don't happen right away. They go in a queue that a background thread works
+//! through on desktop, so autosaving a huge structure doesn't hitch a frame.
+//! Wasm has no threads, so there [`pump`] does one write each frame instead.
+//! Anything that reads saves waits for the queue to empty first.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Load a TOML-serialized value saved under this name.
+///
+/// If it's missing or unreadable, you get the default instead.
+/// Saves are never worth crashing the game over.
+pub fn load_toml<T: DeserializeOwned + Default>(name: &str) -> T {
+    let bytes = match read(name) {
+        Some(it) => it,
+        None => return T::default(),
+    };
+    let parsed = std::str::from_utf8(&bytes)
+        .map_err(|err| err.to_string())
+        .and_then(|text| toml::from_str(text).map_err(|err| err.to_string()));
+    match parsed {
+        Ok(it) => it,
+        Err(err) => {
+            eprintln!("couldn't read save {}, using the default: {}", name, err);
+            T::default()
+        }
+    }
+}
+
+/// Save a value as TOML under this name.
+pub fn store_toml<T: Serialize>(name: &str, value: &T) {
+    // it's small, so it might as well serialize now and save cloning it
+    match toml::to_string(value) {
+        Ok(text) => store_text(name, text),
+        Err(err) => eprintln!("couldn't serialize save {}: {}", name, err),
+    }
+}
+
+/// Save some plain text under this name, for people to read instead of the game.
+pub fn store_text(name: &str, text: String) {
+    queue::push(Job::Write(
+        name.to_owned(),
+        Box::new(move || Some(text.into_bytes())),
+    ));
+}
+
+/// Save a picture as a PNG under this name, for people to look at and share.
+/// On wasm, the browser downloads it instead, since pictures are no use in localStorage.
+///
+/// `rgba` is four bytes a pixel, row by row from the top.
+/// It's encoded off the main thread, like packed saves.
+pub fn store_png(name: &str, width: u32, height: u32, rgba: Vec<u8>) {
+    let owned_name = name.to_owned();
+    queue::push(Job::Export(
+        name.to_owned(),
+        Box::new(move || {
+            let mut bytes = Vec::new();
+            let encoded = image::png::PngEncoder::new(&mut bytes).encode(
+                &rgba,
+                width,
+                height,
+                image::ColorType::Rgba8,
+            );
+            match encoded {
+                Ok(()) => Some(bytes),
+                Err(err) => {
+                    eprintln!("couldn't encode picture {}: {}", owned_name, err);
+                    None
+                }
+            }
+        }),
+    ));
+}
+
+/// Packed saves start with this, then their version, so they can be told apart from JSON ones
+const PACKED_MAGIC: &[u8] = b"ESA\x02";
+/// What packed saves started with before they had versions
+const UNVERSIONED_MAGIC: &[u8] = b"ESA\x01";
+/// How hard to deflate packed saves. Autosaves happen mid-run, so this favors speed.
+const PACKED_LEVEL: u8 = 3;
+
+/// Why a packed save didn't load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackedError {
+    /// There's nothing saved under that name
+    Missing,
+    /// It was saved with a different version of what's in it, if it says which.
+    /// Bincode doesn't know field names, so there's no telling what's where.
+    Outdated(Option<u32>),
+    /// It's damaged, or it was never a save
+    Corrupt(String),
+}
+
+/// Load a value saved under this name with [`store_packed`].
+///
+/// `version` has to be the same one it was stored with. Bump it whenever what gets
+/// saved changes shape: `#[serde(default)]` does nothing for packed saves.
+/// Saves from before packing existed were JSON; those still load, defaults and all.
+pub fn load_packed<T: DeserializeOwned>(name: &str, version: u32) -> Result<T, PackedError> {
+    let bytes = read(name).ok_or(PackedError::Missing)?;
+    let parsed = unpack(&bytes, version);
+    if let Err(err) = &parsed {
+        eprintln!("couldn't read save {}: {:?}", name, err);
+    }
+    parsed
+}
+
+/// Save a value in the packed format under this name, marked with this version.
+///
+/// It's taken by value so it can be serialized and compressed off the main thread.
+pub fn store_packed<T: Serialize + Send + 'static>(name: &str, version: u32, value: T) {
+    let owned_name = name.to_owned();
+    queue::push(Job::Write(
+        name.to_owned(),
+        Box::new(move || match pack(&value, version) {
+            Ok(bytes) => Some(bytes),
+            Err(err) => {
+                eprintln!("couldn't serialize save {}: {}", owned_name, err);
+                None
+            }
+        }),
+    ));
+}
+
+fn pack<T: Serialize>(value: &T, version: u32) -> Result<Vec<u8>, String> {
+    let raw = bincode::serialize(value).map_err(|err| err.to_string())?;
+    let mut bytes = PACKED_MAGIC.to_vec();
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend(miniz_oxide::deflate::compress_to_vec(&raw, PACKED_LEVEL));
+    Ok(bytes)
+}
+
+fn unpack<T: DeserializeOwned>(bytes: &[u8], version: u32) -> Result<T, PackedError> {
+    if bytes.starts_with(UNVERSIONED_MAGIC) {
+        return Err(PackedError::Outdated(None));
+    }
+    let versioned = match bytes.strip_prefix(PACKED_MAGIC) {
+        Some(it) => it,
+        None => {
+            return serde_json::from_slice(bytes)
+                .map_err(|err| PackedError::Corrupt(err.to_string()))
+        }
+    };
+    if versioned.len() < 4 {
+        return Err(PackedError::Corrupt(
+            "cut off before the version".to_owned(),
+        ));
+    }
+    let (found, packed) = versioned.split_at(4);
+    let found = u32::from_le_bytes([found[0], found[1], found[2], found[3]]);
+    if found != version {
+        return Err(PackedError::Outdated(Some(found)));
+    }
+    let raw = miniz_oxide::inflate::decompress_to_vec(packed)
+        .map_err(|err| PackedError::Corrupt(format!("{:?}", err)))?;
+    bincode::deserialize(&raw).map_err(|err| PackedError::Corrupt(err.to_string()))
+}
+
+/// Get rid of whatever is saved under this name.
+pub fn delete(name: &str) {
+    queue::push(Job::Remove(name.to_owned()));
+}
+
+/// Names of everything saved under this prefix, like `"replays/"`.
+pub fn list(prefix: &str) -> Vec<String> {
+    queue::wait();
+    backend::list(prefix)
+}
+
+/// Do some of the queued writes. Call this once a frame.
+///
+/// On desktop the background thread does them, so this does nothing.
+pub fn pump() {
+    queue::pump();
+}
+
+fn read(name: &str) -> Option<Vec<u8>> {
+    queue::wait();
+    backend::read(name)
+}
+
+/// Something queued up to do to the saves. They happen in the order they were queued.
+enum Job {
+    /// Write whatever this makes under the name, if it makes anything
+    Write(String, Box<dyn FnOnce() -> Option<Vec<u8>> + Send>),
+    /// Like `Write`, but it's for the player to take somewhere else
+    Export(String, Box<dyn FnOnce() -> Option<Vec<u8>> + Send>),
+    Remove(String),
+}
+
+impl Job {
+    fn run(self) {
+        match self {
+            Job::Write(name, make_bytes) => {
+                if let Some(bytes) = make_bytes() {
+                    if let Err(err) = backend::write(&name, &bytes) {
+                        eprintln!("couldn't write save {}: {}", name, err);
+                    }
+                }
+            }
+            Job::Export(name, make_bytes) => {
+                if let Some(bytes) = make_bytes() {
+                    if let Err(err) = backend::export(&name, &bytes) {
+                        eprintln!("couldn't export {}: {}", name, err);
+                    }
+                }
+            }
+            Job::Remove(name) => backend::remove(&name),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod queue {
+    use super::Job;
+
+    use crossbeam::channel::{self, Sender};
+    use once_cell::sync::Lazy;
+
+    enum Message {
+        Job(Job),
+        /// Reply once everything before this is done
+        Wait(Sender<()>),
+    }
+
+    static WORKER: Lazy<Sender<Message>> = Lazy::new(|| {
+        let (tx, rx) = channel::unbounded();
+        std::thread::spawn(move || {
+            for message in rx {
+                match message {
+                    Message::Job(job) => job.run(),
+                    Message::Wait(reply) => {
+                        let _ = reply.send(());
+                    }
+                }
+            }
+        });
+        tx
+    });
+
+    pub fn push(job: Job) {
+        if let Err(err) = WORKER.send(Message::Job(job)) {
+            // the thread's gone somehow, so do it here
+            if let Message::Job(job) = err.into_inner() {
+                job.run();
+            }
+        }
+    }
+
+    pub fn wait() {
+        let (tx, rx) = channel::bounded(1);
+        if WORKER.send(Message::Wait(tx)).is_ok() {
+            let _ = rx.recv();
+        }
+    }
+
+    pub fn pump() {}
+}
+
+#[cfg(target_arch = "wasm32")]
+mod queue {
+    use super::Job;
+
+    use std::{cell::RefCell, collections::VecDeque};
+
+    thread_local! {
+        static JOBS: RefCell<VecDeque<Job>> = RefCell::new(VecDeque::new());
+    }
+
+    pub fn push(job: Job) {
+        JOBS.with(|jobs| jobs.borrow_mut().push_back(job));
+    }
+
+    pub fn wait() {
+        while let Some(job) = JOBS.with(|jobs| jobs.borrow_mut().pop_front()) {
+            job.run();
+        }
+    }
+
+    pub fn pump() {
+        if let Some(job) = JOBS.with(|jobs| jobs.borrow_mut().pop_front()) {
+            job.run();
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::{fs, path::PathBuf};
+
+    fn save_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+    }
+
+    pub fn read(name: &str) -> Option<Vec<u8>> {
+        fs::read(save_dir()?.join(name)).ok()
+    }
+
+    pub fn write(name: &str, data: &[u8]) -> Result<(), String> {
+        let dir = save_dir().ok_or_else(|| "no config directory on this platform".to_owned())?;
+        let path = dir.join(name);
+        // names can have folders in them
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        fs::write(path, data).map_err(|err| err.to_string())
+    }
+
+    /// Exports are just files, next to the saves
+    pub fn export(name: &str, data: &[u8]) -> Result<(), String> {
+        write(name, data)
+    }
+
+    pub fn list(prefix: &str) -> Vec<String> {
+        let dir = match save_dir() {
+            Some(it) => it.join(prefix),
+            None => return Vec::new(),
+        };
+        let entries = match fs::read_dir(dir) {
+            Ok(it) => it,
+            Err(_) => return Vec::new(),
+        };
+        entries
+            .filter_map(|entry| {
+                let file_name = entry.ok()?.file_name().into_string().ok()?;
+                Some(format!("{}{}", prefix, file_name))
+            })
+            .collect()
+    }
+
+    pub fn remove(name: &str) {
+        if let Some(dir) = save_dir() {
+            // it's fine if it wasn't there in the first place
+            let _ = fs::remove_file(dir.join(name));
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    extern "C" {
+        /// Length of the stored value in bytes, or -1 if there isn't one
+        fn esa_storage_len(key: *const u8, key_len: usize) -> i32;
+        fn esa_storage_get(key: *const u8, key_len: usize, buf: *mut u8, buf_len: usize);
+        fn esa_storage_set(key: *const u8, key_len: usize, val: *const u8, val_len: usize);
+        fn esa_storage_remove(key: *const u8, key_len: usize);
+        /// Length in bytes of the newline-separated list of keys with this prefix
+        fn esa_storage_list_len(prefix: *const u8, prefix_len: usize) -> i32;
+        fn esa_storage_list_get(prefix: *const u8, prefix_len: usize, buf: *mut u8, buf_len: usize);
+        /// Have the browser download these bytes as a file with this name
+        fn esa_download(name: *const u8, name_len: usize, data: *const u8, data_len: usize);
+    }
+
+    pub fn read(name: &str) -> Option<Vec<u8>> {
+        let len = unsafe { esa_storage_len(name.as_ptr(), name.len()) };
+        if len < 0 {
+            return None;
+        }
+        let mut buf = vec![0; len as usize];
+        unsafe { esa_storage_get(name.as_ptr(), name.len(), buf.as_mut_ptr(), buf.len()) };
+        Some(buf)
+    }
+
+    pub fn write(name: &str, data: &[u8]) -> Result<(), String> {
+        unsafe { esa_storage_set(name.as_ptr(), name.len(), data.as_ptr(), data.len()) };
+        Ok(())
+    }
+
+    pub fn remove(name: &str) {
+        unsafe { esa_storage_remove(name.as_ptr(), name.len()) };
+    }
+
+    pub fn export(name: &str, data: &[u8]) -> Result<(), String> {
+        unsafe { esa_download(name.as_ptr(), name.len(), data.as_ptr(), data.len()) };
+        Ok(())
+    }
+
+    pub fn list(prefix: &str) -> Vec<String> {
+        let len = unsafe { esa_storage_list_len(prefix.as_ptr(), prefix.len()) };
+        if len <= 0 {
+            return Vec::new();
+        }
+        let mut buf = vec![0; len as usize];
+        unsafe { esa_storage_list_get(prefix.as_ptr(), prefix.len(), buf.as_mut_ptr(), buf.len()) };
+        String::from_utf8_lossy(&buf)
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Before {
+        name: String,
+        blocks: Vec<(isize, isize)>,
+    }
+
+    /// `Before` with a field added afterwards
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct After {
+        name: String,
+        blocks: Vec<(isize, isize)>,
+        #[serde(default)]
+        points: u32,
+    }
+
+    fn before() -> Before {
+        Before {
+            name: "DIGGER".to_owned(),
+            blocks: (-50..50).map(|x| (x, x * x - 7)).collect(),
+        }
+    }
+
+    #[test]
+    fn packed_round_trips() {
+        let value = before();
+        let bytes = pack(&value, 7).unwrap();
+        assert_eq!(unpack::<Before>(&bytes, 7), Ok(value));
+    }
+
+    #[test]
+    fn legacy_json_loads_with_defaults() {
+        let value = before();
+        let bytes = serde_json::to_vec(&value).unwrap();
+        assert_eq!(unpack::<Before>(&bytes, 7), Ok(before()));
+        assert_eq!(
+            unpack::<After>(&bytes, 7),
+            Ok(After {
+                name: value.name,
+                blocks: value.blocks,
+                points: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn other_versions_are_outdated() {
+        let bytes = pack(&before(), 7).unwrap();
+        assert_eq!(
+            unpack::<After>(&bytes, 8),
+            Err(PackedError::Outdated(Some(7)))
+        );
+
+        let mut unversioned = UNVERSIONED_MAGIC.to_vec();
+        let raw = bincode::serialize(&before()).unwrap();
+        unversioned.extend(miniz_oxide::deflate::compress_to_vec(&raw, PACKED_LEVEL));
+        assert_eq!(
+            unpack::<Before>(&unversioned, 7),
+            Err(PackedError::Outdated(None))
+        );
+    }
+
+    #[test]
+    fn garbage_is_corrupt() {
+        for bytes in [&b"not a save"[..], PACKED_MAGIC, b"ESA\x02\x07\0\0\0oops"] {
+            match unpack::<Before>(bytes, 7) {
+                Err(PackedError::Corrupt(_)) => {}
+                other => panic!("{:?} loaded as {:?}", bytes, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{save, GameType};
+
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Ordering;
+
+/// How a run turned out.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunResult {
+    /// Different game types get separate leaderboards
+    pub game_type: GameType,
+    /// Final depth of the center of mass. This is the main score.
+    pub depth: f32,
+    /// Bonus points earned along the way
+    pub points: u32,
+    pub blocks_placed: u32,
+    /// Whether any assist options were on
+    pub assisted: bool,
+    /// How well the blueprint was matched, in blueprint runs
+    #[serde(default)]
+    pub blueprint: Option<BlueprintResult>,
+    /// How high above the rim the structure got, in tower runs
+    #[serde(default)]
+    pub tower_height: u32,
+    /// Whether the run ended by meeting its objective
+    #[serde(default)]
+    pub objective_met: bool,
+    /// How many buried artifacts were dug out
+    #[serde(default)]
+    pub artifacts: u32,
+    /// Most blocks that came loose at once
+    #[serde(default)]
+    pub biggest_collapse: u32,
+}
+
+/// How a blueprint challenge went.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlueprintResult {
+    pub name: String,
+    /// From 0 to 1
+    pub accuracy: f32,
+    /// How long it took
+    pub frames: u64,
+    /// Accuracy, docked for taking a long time. For blueprint runs this is the main score.
+    pub score: u32,
+}
+
+impl RunResult {
+    /// Depth, plus height in tower runs
+    pub fn total(&self) -> f32 {
+        self.depth + self.tower_height as f32
+    }
+
+    /// How much depth each block placed bought you.
+    /// Elegant structures score higher than filling the chasm solid.
+    pub fn efficiency(&self) -> f32 {
+        efficiency(self.depth, self.blocks_placed)
+    }
+}
+
+/// Depth per block placed.
+pub fn efficiency(depth: f32, blocks_placed: u32) -> f32 {
+    if blocks_placed == 0 {
+        0.0
+    } else {
+        depth / blocks_placed as f32
+    }
+}
+
+/// How many runs each high score table keeps
+pub const HIGH_SCORE_COUNT: usize = 10;
+
+/// One run on a high score table.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub game_type: GameType,
+    /// Who played it, like on an arcade cabinet
+    pub initials: String,
+    /// Depth, plus height in tower runs
+    pub depth: f32,
+    /// For breaking ties, like on the leaderboards
+    pub blocks_placed: u32,
+    /// When it was played, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl HighScore {
+    /// Compare for ranking; better runs are `Greater`.
+    /// Ties on (displayed) depth go to the more efficient run.
+    fn rank_cmp(&self, other: &HighScore) -> Ordering {
+        let depth = self.depth.round() as i32;
+        let other_depth = other.depth.round() as i32;
+        depth.cmp(&other_depth).then_with(|| {
+            efficiency(self.depth, self.blocks_placed)
+                .partial_cmp(&efficiency(other.depth, other.blocks_placed))
+                .unwrap_or(Ordering::Equal)
+        })
+    }
+}
+
+/// The best runs of each game type, kept between sessions.
+///
+/// Assisted runs and blueprint runs don't go on them; those are scored differently.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    /// Best first
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    const SAVE_NAME: &'static str = "high_scores.toml";
+
+    pub fn load() -> Self {
+        save::load_toml(Self::SAVE_NAME)
+    }
+
+    pub fn save(&self) {
+        save::store_toml(Self::SAVE_NAME, self);
+    }
+
+    /// The table for one game type, best first.
+    pub fn table(&self, game_type: GameType) -> Vec<&HighScore> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.game_type == game_type)
+            .collect()
+    }
+
+    /// Put a finished run on its table, if it's good enough.
+    ///
+    /// Returns where it landed on the table, counting from 0.
+    pub fn submit(&mut self, result: &RunResult, player: &str) -> Option<usize> {
+        // those aren't comparable from one to the next
+        let one_off = result.blueprint.is_some()
+            || result.game_type == GameType::Campaign
+            || result.game_type == GameType::Daily;
+        if result.assisted || one_off {
+            return None;
+        }
+        let entry = HighScore {
+            game_type: result.game_type,
+            initials: initials(player),
+            depth: result.total(),
+            blocks_placed: result.blocks_placed,
+            timestamp: macroquad::miniquad::date::now() as u64,
+        };
+        self.insert(entry)
+    }
+
+    /// Put an entry on its table if it's good enough, and say where it landed.
+    fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        // ties go to whoever got there first
+        let game_type = entry.game_type;
+        let rank = self
+            .table(game_type)
+            .iter()
+            .filter(|other| other.rank_cmp(&entry) != Ordering::Less)
+            .count();
+        if rank >= HIGH_SCORE_COUNT {
+            return None;
+        }
+        // the other tables are mixed in, so only this one's entries say where it goes
+        let idx = self
+            .entries
+            .iter()
+            .position(|other| {
+                other.game_type == game_type && other.rank_cmp(&entry) == Ordering::Less
+            })
+            .unwrap_or(self.entries.len());
+        self.entries.insert(idx, entry);
+
+        // bump whoever fell off the bottom
+        let mut kept = 0;
+        self.entries.retain(|entry| {
+            if entry.game_type != game_type {
+                return true;
+            }
+            kept += 1;
+            kept <= HIGH_SCORE_COUNT
+        });
+        Some(rank)
+    }
+}
+
+/// How many days of daily challenge bests are kept
+const DAILY_DAYS_KEPT: usize = 30;
+
+/// The best run on one day's daily challenge.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DailyBest {
+    /// Days since the Unix epoch
+    pub day: i64,
+    /// Depth, like on the high score tables
+    pub depth: f32,
+    pub blocks_placed: u32,
+}
+
+/// The player's best daily challenge runs, one per day, kept apart from the high scores
+/// since each day's challenge is different.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyBests {
+    /// Newest first
+    entries: Vec<DailyBest>,
+}
+
+impl DailyBests {
+    const SAVE_NAME: &'static str = "daily_bests.toml";
+
+    pub fn load() -> Self {
+        save::load_toml(Self::SAVE_NAME)
+    }
+
+    pub fn save(&self) {
+        save::store_toml(Self::SAVE_NAME, self);
+    }
+
+    /// The best run on this day, if there was one.
+    pub fn best(&self, day: i64) -> Option<&DailyBest> {
+        self.entries.iter().find(|entry| entry.day == day)
+    }
+
+    /// Count a finished run towards this day's best.
+    ///
+    /// Returns whether it was the new best.
+    pub fn submit(&mut self, day: i64, result: &RunResult) -> bool {
+        if result.assisted {
+            return false;
+        }
+        let entry = DailyBest {
+            day,
+            depth: result.total(),
+            blocks_placed: result.blocks_placed,
+        };
+        match self.entries.iter_mut().find(|other| other.day == day) {
+            Some(other) => {
+                let better = entry.depth.round() as i32 > other.depth.round() as i32
+                    || (entry.depth.round() == other.depth.round()
+                        && efficiency(entry.depth, entry.blocks_placed)
+                            > efficiency(other.depth, other.blocks_placed));
+                if !better {
+                    return false;
+                }
+                *other = entry;
+            }
+            None => {
+                self.entries.push(entry);
+                self.entries
+                    .sort_by_key(|entry| std::cmp::Reverse(entry.day));
+                self.entries.truncate(DAILY_DAYS_KEPT);
+            }
+        }
+        true
+    }
+}
+
+/// Up to three letters to show for a player on the high score table.
+pub fn initials(name: &str) -> String {
+    let initials: String = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(3)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if initials.is_empty() {
+        "???".to_owned()
+    } else {
+        initials
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(game_type: GameType, depth: f32) -> HighScore {
+        HighScore {
+            game_type,
+            initials: "AAA".to_owned(),
+            depth,
+            blocks_placed: 50,
+            timestamp: 0,
+        }
+    }
+
+    fn depths(scores: &HighScores, game_type: GameType) -> Vec<f32> {
+        scores
+            .table(game_type)
+            .iter()
+            .map(|entry| entry.depth)
+            .collect()
+    }
+
+    #[test]
+    fn other_tables_dont_decide_the_order() {
+        let mut scores = HighScores::default();
+        assert_eq!(scores.insert(entry(GameType::Hardcore, 10.0)), Some(0));
+        assert_eq!(scores.insert(entry(GameType::Classic, 50.0)), Some(0));
+        assert_eq!(scores.insert(entry(GameType::Classic, 30.0)), Some(1));
+        assert_eq!(scores.insert(entry(GameType::Hardcore, 20.0)), Some(0));
+        assert_eq!(scores.insert(entry(GameType::Classic, 40.0)), Some(1));
+
+        assert_eq!(depths(&scores, GameType::Classic), vec![50.0, 40.0, 30.0]);
+        assert_eq!(depths(&scores, GameType::Hardcore), vec![20.0, 10.0]);
+    }
+
+    #[test]
+    fn full_tables_bump_only_their_worst() {
+        let mut scores = HighScores::default();
+        for idx in 0..HIGH_SCORE_COUNT {
+            let depth = (idx as f32 + 1.0) * 10.0;
+            scores.insert(entry(GameType::Classic, depth));
+            scores.insert(entry(GameType::Zen, depth - 5.0));
+        }
+        // worse than everything on a full table
+        assert_eq!(scores.insert(entry(GameType::Classic, 1.0)), None);
+
+        assert_eq!(scores.insert(entry(GameType::Classic, 55.0)), Some(5));
+        let classic = depths(&scores, GameType::Classic);
+        assert_eq!(classic.len(), HIGH_SCORE_COUNT);
+        assert_eq!(classic[0], 100.0);
+        assert_eq!(classic[5], 55.0);
+        assert_eq!(*classic.last().unwrap(), 20.0);
+        // and the other table is untouched
+        assert_eq!(depths(&scores, GameType::Zen).len(), HIGH_SCORE_COUNT);
+        assert_eq!(*depths(&scores, GameType::Zen).last().unwrap(), 5.0);
+    }
+}
@@ -0,0 +1,101 @@
+//! Cosmetic touches for times of the year, like snow in December.
+//!
+//! They're part of the theme, which only changes how things look.
+//! This is also where the game works out what day it is, for the daily challenge.
+
+use macroquad::color::Color;
+
+use crate::particles::Emitter;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Season {
+    /// Nothing special going on
+    Ordinary,
+    /// October
+    Autumn,
+    /// December
+    Winter,
+}
+
+impl Season {
+    /// Which season it is right now, going by the clock.
+    pub fn today() -> Self {
+        match date_from_days(today()).1 {
+            10 => Season::Autumn,
+            12 => Season::Winter,
+            _ => Season::Ordinary,
+        }
+    }
+}
+
+/// Colors and effects that change with the season.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    /// Multiplied into the color of every block
+    pub block_tint: Color,
+    /// Multiplied into the color of the rock
+    pub rock_tint: Color,
+    /// Something extra floating around during runs
+    pub emitter: Option<Emitter>,
+}
+
+impl Theme {
+    pub fn for_season(season: Season) -> Self {
+        match season {
+            Season::Ordinary => Self {
+                block_tint: Color::new(1.0, 1.0, 1.0, 1.0),
+                rock_tint: Color::new(1.0, 1.0, 1.0, 1.0),
+                emitter: None,
+            },
+            Season::Autumn => Self {
+                block_tint: Color::new(1.0, 0.95, 0.85, 1.0),
+                rock_tint: Color::new(1.0, 0.9, 0.8, 1.0),
+                emitter: Some(Emitter::Leaves),
+            },
+            Season::Winter => Self {
+                // frosty
+                block_tint: Color::new(0.85, 0.95, 1.0, 1.0),
+                rock_tint: Color::new(0.85, 0.9, 1.0, 1.0),
+                emitter: Some(Emitter::Snow),
+            },
+        }
+    }
+}
+
+/// Multiply two colors together, channel by channel.
+pub fn tint(color: Color, by: Color) -> Color {
+    Color::new(
+        color.r * by.r,
+        color.g * by.g,
+        color.b * by.b,
+        color.a * by.a,
+    )
+}
+
+/// How many days it's been since the Unix epoch. Days start at midnight UTC,
+/// so it's the same day for everyone at once.
+pub fn today() -> i64 {
+    (macroquad::miniquad::date::now() / 86400.0) as i64
+}
+
+/// The year, month (1 to 12) and day of the month this many days after the Unix epoch.
+///
+/// This is Howard Hinnant's `civil_from_days`.
+pub fn date_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index as u32 + 3
+    } else {
+        month_index as u32 - 9
+    };
+    // the year starts in March here, so January and February are in the next one
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+
+use crate::save;
+
+/// Player preferences that stick around between sessions.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub assist: AssistSettings,
+    /// What the camera does on its own during a run
+    pub camera: CameraMode,
+    /// How much to spend on looking nice
+    pub graphics: GraphicsQuality,
+    /// Everything else gets turned down by this too, in percent
+    pub master_volume: u8,
+    /// No sound at all, without losing track of the volumes
+    pub muted: bool,
+    /// In percent
+    pub music_volume: u8,
+    /// In percent
+    pub sfx_volume: u8,
+    pub fullscreen: bool,
+    /// How fast the camera scrolls, in percent of normal
+    pub scroll_speed: u8,
+    /// Whether to dress things up for the time of year
+    pub seasonal: bool,
+    /// Keep the screen from shaking
+    pub reduced_motion: bool,
+    /// Tint connectors by their shape, so they can be told apart at a glance
+    pub tint_connectors: bool,
+    /// Mark connectors with a glyph for their shape, and wear with hatching,
+    /// for anyone who can't go by color
+    pub connector_glyphs: bool,
+    /// Show a see-through copy of the best run of the same kind, to race against
+    pub ghost: bool,
+    /// Start runs zoomed in, with bigger blocks but fewer rows on screen
+    pub zoomed: bool,
+}
+
+impl Settings {
+    const SAVE_NAME: &'static str = "settings.toml";
+
+    pub const MAX_VOLUME: u8 = 100;
+    pub const MIN_SCROLL_SPEED: u8 = 50;
+    pub const NORMAL_SCROLL_SPEED: u8 = 100;
+    pub const MAX_SCROLL_SPEED: u8 = 200;
+
+    pub fn load() -> Self {
+        save::load_toml(Self::SAVE_NAME)
+    }
+
+    pub fn save(&self) {
+        save::store_toml(Self::SAVE_NAME, self);
+    }
+
+    /// Music volume, to play sounds at
+    pub fn music_gain(&self) -> f32 {
+        self.master_gain() * self.music_volume as f32 / 100.0
+    }
+
+    /// Sound effect volume, to play sounds at
+    pub fn sfx_gain(&self) -> f32 {
+        self.master_gain() * self.sfx_volume as f32 / 100.0
+    }
+
+    fn master_gain(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master_volume as f32 / 100.0
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            assist: AssistSettings::default(),
+            camera: CameraMode::default(),
+            graphics: GraphicsQuality::default(),
+            master_volume: Self::MAX_VOLUME,
+            muted: false,
+            music_volume: 70,
+            sfx_volume: Self::MAX_VOLUME,
+            fullscreen: false,
+            scroll_speed: Self::NORMAL_SCROLL_SPEED,
+            seasonal: true,
+            reduced_motion: false,
+            tint_connectors: false,
+            connector_glyphs: false,
+            ghost: false,
+            zoomed: false,
+        }
+    }
+}
+
+/// Options to make the game easier. Using any of them marks runs as assisted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssistSettings {
+    /// How fast blocks wear out, in percent of normal
+    pub decay_percent: u8,
+    /// Nudge placements that are one cell off onto a cell where the block links up
+    pub snap_placement: bool,
+}
+
+impl AssistSettings {
+    pub const MIN_DECAY_PERCENT: u8 = 50;
+    pub const MAX_DECAY_PERCENT: u8 = 100;
+
+    pub fn is_active(&self) -> bool {
+        self.decay_percent < Self::MAX_DECAY_PERCENT || self.snap_placement
+    }
+}
+
+/// How the camera moves during a run, other than when the player scrolls it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CameraMode {
+    /// Only moves when you scroll it
+    Manual,
+    /// Scrolls to wherever the last block was placed
+    FollowPlacement,
+    /// Keeps the center of mass line in the middle of the screen
+    CenterOfMass,
+}
+
+impl CameraMode {
+    /// The mode after this one, for cycling through them with a button
+    pub fn next(self) -> Self {
+        match self {
+            CameraMode::Manual => CameraMode::FollowPlacement,
+            CameraMode::FollowPlacement => CameraMode::CenterOfMass,
+            CameraMode::CenterOfMass => CameraMode::Manual,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CameraMode::Manual => "MANUAL",
+            CameraMode::FollowPlacement => "FOLLOW",
+            CameraMode::CenterOfMass => "CENTER",
+        }
+    }
+}
+
+impl Default for CameraMode {
+    fn default() -> Self {
+        CameraMode::Manual
+    }
+}
+
+impl Default for AssistSettings {
+    fn default() -> Self {
+        Self {
+            decay_percent: Self::MAX_DECAY_PERCENT,
+            snap_placement: false,
+        }
+    }
+}
+
+/// Extra hints drawn on blocks, so they don't only go by color and shading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct RenderStyle {
+    /// Connectors get tinted by their shape
+    pub tint_connectors: bool,
+    /// Connectors get a glyph for their shape: filled if they stick out, hollow if they don't.
+    /// Worn blocks get hatched more the more worn they are.
+    pub glyphs: bool,
+}
+
+impl RenderStyle {
+    /// The style after this one, for cycling through them with a button
+    pub fn next(self) -> Self {
+        match (self.tint_connectors, self.glyphs) {
+            (false, false) => Self {
+                tint_connectors: true,
+                glyphs: false,
+            },
+            (true, false) => Self {
+                tint_connectors: false,
+                glyphs: true,
+            },
+            (false, true) => Self {
+                tint_connectors: true,
+                glyphs: true,
+            },
+            (true, true) => Self::default(),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match (self.tint_connectors, self.glyphs) {
+            (false, false) => "OFF",
+            (true, false) => "COLOR",
+            (false, true) => "GLYPHS",
+            (true, true) => "BOTH",
+        }
+    }
+}
+
+/// How fancy to draw things. Low is for weak laptops and old phones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GraphicsQuality {
+    High,
+    /// No antialiasing, and the background only gets redrawn when the camera moves
+    Low,
+}
+
+impl GraphicsQuality {
+    pub fn next(self) -> Self {
+        match self {
+            GraphicsQuality::High => GraphicsQuality::Low,
+            GraphicsQuality::Low => GraphicsQuality::High,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GraphicsQuality::High => "HIGH",
+            GraphicsQuality::Low => "LOW",
+        }
+    }
+
+    /// Antialiasing samples for the window. This only changes on the next launch.
+    pub fn sample_count(self) -> i32 {
+        match self {
+            GraphicsQuality::High => 16,
+            GraphicsQuality::Low => 1,
+        }
+    }
+}
+
+impl Default for GraphicsQuality {
+    fn default() -> Self {
+        GraphicsQuality::High
+    }
+}
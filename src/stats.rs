@@ -0,0 +1,53 @@
+//! Lifetime stats, added up over every run ever played.
+
+use serde::{Deserialize, Serialize};
+
+use crate::save;
+
+/// Totals and bests across every run, for the stats page.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub runs_started: u32,
+    pub blocks_placed: u64,
+    pub blocks_broken: u64,
+    /// Times any blocks came loose and fell
+    pub collapses: u32,
+    /// Blocks that fell in all those collapses
+    pub blocks_lost: u64,
+    /// The deepest any run's center of mass has been, even if it didn't stay there
+    pub deepest: f32,
+    /// Steps of the simulation spent playing, of which there are 60 a second
+    pub steps_played: u64,
+    /// Whether anything changed since the last save
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl Stats {
+    const SAVE_NAME: &'static str = "stats.toml";
+
+    pub fn load() -> Self {
+        save::load_toml(Self::SAVE_NAME)
+    }
+
+    /// Save, if there's anything new to save.
+    pub fn save_if_changed(&mut self) {
+        if self.changed {
+            save::store_toml(Self::SAVE_NAME, self);
+            self.changed = false;
+        }
+    }
+
+    /// Change the stats with this, and note they need saving.
+    pub fn update(&mut self, change: impl FnOnce(&mut Self)) {
+        change(self);
+        self.changed = true;
+    }
+
+    /// How long has been spent playing, as hours, minutes and seconds
+    pub fn play_time(&self) -> (u64, u64, u64) {
+        let seconds = self.steps_played / 60;
+        (seconds / 3600, seconds / 60 % 60, seconds % 60)
+    }
+}
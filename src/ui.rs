@@ -0,0 +1,278 @@
+//! Little widgets for menus that don't have hand-drawn art.
+
+use macroquad::prelude::*;
+
+use crate::{
+    drawutils::{self, hexcolor, mouse_position_pixel, TextAlign},
+    score::{HighScore, HIGH_SCORE_COUNT},
+    Globals, WIDTH,
+};
+
+/// Background of menus
+pub const BACKGROUND: u32 = 0x21181bff;
+/// Background of buttons
+pub const BUTTON: u32 = 0x3b2027ff;
+/// Most text and outlines
+pub const FOREGROUND: u32 = 0xffee83ff;
+/// Less important text
+pub const DIM: u32 = 0xa3a7c2ff;
+/// Things going wrong
+pub const ALERT: u32 = 0xd95763ff;
+
+/// A clickable rectangle with a label in the middle.
+#[derive(Clone)]
+pub struct Button {
+    pub rect: Rect,
+    pub label: String,
+}
+
+impl Button {
+    pub fn new(x: f32, y: f32, w: f32, h: f32, label: impl Into<String>) -> Self {
+        Self {
+            rect: Rect::new(x, y, w, h),
+            label: label.into(),
+        }
+    }
+
+    pub fn hovered(&self) -> bool {
+        self.rect.contains(mouse_position_pixel().into())
+    }
+
+    pub fn clicked(&self) -> bool {
+        self.hovered() && is_mouse_button_pressed(MouseButton::Left)
+    }
+
+    /// Draw the button. It lights up when hovered, or when `selected`
+    /// (for keyboard navigation).
+    pub fn draw(&self, selected: bool, globals: &Globals) {
+        let lit = selected || self.hovered();
+        let (bg, fg) = if lit {
+            (hexcolor(FOREGROUND), hexcolor(BACKGROUND))
+        } else {
+            (hexcolor(BUTTON), hexcolor(FOREGROUND))
+        };
+        let Rect { x, y, w, h } = self.rect;
+        draw_rectangle(x, y, w, h, bg);
+        draw_rectangle_lines(x, y, w, h, 1.0, hexcolor(FOREGROUND));
+
+        let text_y = (y + (h - 5.0) / 2.0).round();
+        drawutils::draw_text_aligned(
+            &self.label,
+            x + w / 2.0,
+            text_y,
+            TextAlign::Center,
+            fg,
+            globals,
+        );
+    }
+}
+
+/// Draw a plain box for tooltips and info cards to go in.
+pub fn draw_panel(rect: Rect) {
+    let Rect { x, y, w, h } = rect;
+    let mut bg = hexcolor(BACKGROUND);
+    bg.a = 0.9;
+    draw_rectangle(x, y, w, h, bg);
+    draw_rectangle_lines(x, y, w, h, 1.0, hexcolor(FOREGROUND));
+}
+
+/// How much of the modes under a translucent one gets covered up
+const DIM_OVERLAY_ALPHA: f32 = 0.85;
+
+/// Shade the whole canvas, so a mode drawn over the top stands out from the ones below.
+pub fn draw_dim_overlay() {
+    let mut bg = hexcolor(BACKGROUND);
+    bg.a = DIM_OVERLAY_ALPHA;
+    draw_rectangle(0.0, 0.0, crate::WIDTH, crate::HEIGHT, bg);
+}
+
+/// Draw a high score table in a panel, with `highlight` picked out if there is one.
+pub fn draw_high_scores(
+    x: f32,
+    y: f32,
+    table: &[&HighScore],
+    highlight: Option<usize>,
+    globals: &Globals,
+) {
+    let height = 14.0 + HIGH_SCORE_COUNT as f32 * 7.0;
+    draw_panel(Rect::new(x, y, 64.0, height));
+    drawutils::draw_text_px(
+        "HIGH SCORES",
+        x + 4.0,
+        y + 4.0,
+        hexcolor(FOREGROUND),
+        globals,
+    );
+    for idx in 0..HIGH_SCORE_COUNT {
+        let row_y = y + 12.0 + idx as f32 * 7.0;
+        let color = if highlight == Some(idx) {
+            hexcolor(FOREGROUND)
+        } else {
+            hexcolor(DIM)
+        };
+        let line = match table.get(idx) {
+            Some(entry) => format!(
+                "{:>2} {:<3} {:>4}",
+                idx + 1,
+                entry.initials,
+                entry.depth.round() as i32
+            ),
+            None => format!("{:>2} ---", idx + 1),
+        };
+        drawutils::draw_text_px(&line, x + 4.0, row_y, color, globals);
+    }
+}
+
+/// Draw text centered horizontally on the screen.
+pub fn draw_centered_text(text: &str, y: f32, color: Color, globals: &Globals) {
+    drawutils::draw_text_aligned(
+        text,
+        crate::WIDTH / 2.0,
+        y,
+        TextAlign::Center,
+        color,
+        globals,
+    );
+}
+
+/// How long a toast stays up, in frames
+const TOAST_LIFETIME: u32 = 150;
+/// How long a toast takes to fade out, in frames
+const TOAST_FADE: u32 = 30;
+
+/// Little popup messages that stack at the top of the screen and fade away.
+#[derive(Clone, Default)]
+pub struct Toasts {
+    /// Each message and how many frames it has left
+    active: Vec<(String, u32)>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: impl Into<String>) {
+        self.active.push((text.into(), TOAST_LIFETIME));
+    }
+
+    pub fn update(&mut self) {
+        for (_, frames_left) in self.active.iter_mut() {
+            *frames_left = frames_left.saturating_sub(1);
+        }
+        self.active.retain(|(_, frames_left)| *frames_left > 0);
+    }
+
+    pub fn draw(&self, globals: &Globals) {
+        for (idx, (text, frames_left)) in self.active.iter().enumerate() {
+            let alpha = (*frames_left as f32 / TOAST_FADE as f32).min(1.0);
+            let width = drawutils::text_width_px(text) + 8.0;
+            let x = ((crate::WIDTH - width) / 2.0).round();
+            let y = 20.0 + idx as f32 * 13.0;
+
+            let mut bg = hexcolor(BACKGROUND);
+            bg.a = alpha * 0.9;
+            let mut fg = hexcolor(FOREGROUND);
+            fg.a = alpha;
+            draw_rectangle(x, y, width, 11.0, bg);
+            draw_rectangle_lines(x, y, width, 11.0, 1.0, fg);
+            drawutils::draw_text_px(text, x + 4.0, y + 3.0, fg, globals);
+        }
+    }
+}
+
+/// How many missing assets get listed before the rest are just counted
+const MISSING_LISTED: usize = 8;
+
+/// How many frames the volume shows for after it gets changed
+const VOLUME_SHOWN_FOR: u32 = 60;
+
+/// The master volume in the corner, for a little while after the hotkeys change it.
+#[derive(Default)]
+pub struct VolumePopup {
+    frames_left: u32,
+}
+
+impl VolumePopup {
+    pub fn show(&mut self) {
+        self.frames_left = VOLUME_SHOWN_FOR;
+    }
+
+    pub fn update(&mut self) {
+        self.frames_left = self.frames_left.saturating_sub(1);
+    }
+
+    pub fn draw(&self, globals: &Globals) {
+        if self.frames_left == 0 {
+            return;
+        }
+        let text = if globals.settings.muted {
+            "MUTED".to_owned()
+        } else {
+            format!("VOLUME {}%", globals.settings.master_volume)
+        };
+        let width = drawutils::text_width_px(&text);
+        let rect = Rect::new(WIDTH - width - 12.0, 4.0, width + 8.0, 13.0);
+        draw_panel(rect);
+        drawutils::draw_text_px(
+            &text,
+            rect.x + 4.0,
+            rect.y + 4.0,
+            hexcolor(FOREGROUND),
+            globals,
+        );
+    }
+}
+
+/// A panel in the corner listing assets that couldn't be loaded, until it's clicked away.
+#[derive(Default)]
+pub struct MissingAssets {
+    dismissed: bool,
+}
+
+impl MissingAssets {
+    pub fn update(&mut self) {
+        if self.dismissed || !is_mouse_button_pressed(MouseButton::Left) {
+            return;
+        }
+        let (mx, my) = mouse_position_pixel();
+        if let Some((rect, _)) = Self::layout() {
+            self.dismissed = rect.contains(vec2(mx, my));
+        }
+    }
+
+    pub fn draw(&self, globals: &Globals) {
+        if self.dismissed {
+            return;
+        }
+        let (rect, lines) = match Self::layout() {
+            Some(it) => it,
+            None => return,
+        };
+        draw_panel(rect);
+        for (idx, (line, color)) in lines.iter().enumerate() {
+            let y = rect.y + 4.0 + idx as f32 * 7.0;
+            drawutils::draw_text_px(line, rect.x + 4.0, y, hexcolor(*color), globals);
+        }
+    }
+
+    /// Where the panel goes and what it says, if anything's missing
+    fn layout() -> Option<(Rect, Vec<(String, u32)>)> {
+        let missing = crate::assets::missing();
+        if missing.is_empty() {
+            return None;
+        }
+        let mut lines = vec![("MISSING ASSETS:".to_owned(), ALERT)];
+        for path in missing.iter().take(MISSING_LISTED) {
+            lines.push((path.clone(), FOREGROUND));
+        }
+        if missing.len() > MISSING_LISTED {
+            let more = format!("AND {} MORE", missing.len() - MISSING_LISTED);
+            lines.push((more, FOREGROUND));
+        }
+        lines.push(("CLICK TO HIDE".to_owned(), DIM));
+
+        let width = lines
+            .iter()
+            .map(|(line, _)| drawutils::text_width_px(line))
+            .fold(0.0, f32::max);
+        let height = lines.len() as f32 * 7.0 + 6.0;
+        Some((Rect::new(4.0, 4.0, width + 8.0, height), lines))
+    }
+}